@@ -19,6 +19,8 @@ mkdir -p usr/share/overskride
 mkdir -p usr/share/applications
 mkdir -p usr/share/appdata
 mkdir -p usr/share/glib-2.0/schemas
+mkdir -p usr/share/polkit-1/actions
+mkdir -p usr/libexec
 mkdir -p usr/share/icons/hicolor/scalable/apps
 mkdir -p usr/share/icons/hicolor/symbolic/apps
 
@@ -26,6 +28,8 @@ cp ../../overskride usr/bin/overskride
 cp ../../overskride.gresource usr/share/overskride/
 cp ../../../data/io.github.kaii_lb.Overskride.desktop usr/share/applications/
 cp ../../../data/io.github.kaii_lb.Overskride.appdata.xml usr/share/appdata/
+cp ../../../data/io.github.kaii_lb.Overskride.policy usr/share/polkit-1/actions/
+cp /home/$USER/Projects/overskride/data/overskride-keys-helper usr/libexec/
 cp /home/$USER/Projects/overskride/data/io.github.kaii_lb.Overskride.gschema.xml usr/share/glib-2.0/schemas/
 cp /home/$USER/Projects/overskride/data/icons/hicolor/scalable/apps/io.github.kaii_lb.Overskride.svg usr/share/icons/hicolor/scalable/apps/
 cp /home/$USER/Projects/overskride/data/icons/hicolor/symbolic/apps/io.github.kaii_lb.Overskride-symbolic.svg usr/share/icons/hicolor/symbolic/apps/
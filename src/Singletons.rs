@@ -1,6 +1,8 @@
 use async_channel::Sender;
 use bluer::Address;
 use crate::message::Message;
+use crate::rfkill::RfkillState;
+use crate::bluetooth_settings::DiscoverySettings;
 
 pub struct OverskrideProperties {
     pub name: String,
@@ -16,7 +18,28 @@ pub struct OverskrideProperties {
     pub pin_code: String,
     pub pass_key: u32,
     pub store_folder: String,
-    pub confirm_authorization: bool
+    pub confirm_authorization: bool,
+    pub audio_sink_mode: bool,
+    pub streaming_address: Address,
+    pub call_integration: bool,
+    pub call_audio_profile: String,
+    pub current_call: String,
+    pub broadcast_scanning: bool,
+    pub show_all_adapters: bool,
+    pub device_adapter: String,
+    pub rfkill_state: RfkillState,
+    pub adapter_missing: bool,
+    pub run_in_background: bool,
+    pub device_filter: String,
+    pub device_grouping: String,
+    pub discovery_settings: DiscoverySettings,
+    pub scanning: bool,
+    pub scan_timeout: u32,
+    pub scan_only_when_focused: bool,
+    pub keep_connected: Vec<Address>,
+    pub audio_priority: Vec<Address>,
+    pub audio_switching: String,
+    pub distance_environment: String
 }
 
 impl OverskrideProperties {
@@ -36,7 +59,28 @@ impl OverskrideProperties {
             pin_code: empty_string.to_string(),
             pass_key: 0,
             store_folder: empty_string,
-            confirm_authorization: false
+            confirm_authorization: false,
+            audio_sink_mode: false,
+            streaming_address: Address::any(),
+            call_integration: false,
+            call_audio_profile: "".to_string(),
+            current_call: "".to_string(),
+            broadcast_scanning: false,
+            show_all_adapters: false,
+            device_adapter: "".to_string(),
+            rfkill_state: RfkillState::default(),
+            adapter_missing: false,
+            run_in_background: false,
+            device_filter: "all".to_string(),
+            device_grouping: "none".to_string(),
+            discovery_settings: DiscoverySettings::default(),
+            scanning: false,
+            scan_timeout: 60,
+            scan_only_when_focused: false,
+            keep_connected: vec![],
+            audio_priority: vec![],
+            audio_switching: "off".to_string(),
+            distance_environment: "indoors".to_string()
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,213 @@
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use async_channel::{Receiver, Sender};
+use dbus::blocking::SyncConnection;
+use dbus::channel::{MatchingReceiver, Sender as DbusSender};
+use dbus::message::MatchRule;
+use dbus_crossroads::{Crossroads, MethodErr};
+use futures::executor::block_on;
+use crate::message::Message;
+use crate::window::{runtime, OVERSKRIDE_PROPS};
+use crate::obex;
+use crate::reconnect;
+use crate::event_log::{self, Source};
+
+/// the bus name, flatpak lets us own anything under our app id
+const API_NAME: &str = "io.github.kaii_lb.Overskride.Api";
+const API_PATH: &str = "/io/github/kaii_lb/Overskride";
+const API_INTERFACE: &str = "io.github.kaii_lb.Overskride1";
+const API_ERROR: &str = "io.github.kaii_lb.Overskride1.Error.Failed";
+
+static SIGNALS: OnceLock<Sender<ApiSignal>> = OnceLock::new();
+
+/// things that happened in the app which the API announces as signals
+#[derive(Debug, Clone)]
+pub enum ApiSignal {
+    /// a device was connected (`true`) or disconnected (`false`)
+    ConnectionChanged(bluer::Address, bool),
+    /// a transfer started, with its object path, filename, and whether we're sending it
+    TransferStarted(String, String, bool),
+    /// a transfer ended, with its object path, filename, and status ("complete" or "error")
+    TransferFinished(String, String, String),
+}
+
+/// queues a signal to be emitted by the API, does nothing if the API isn't up
+pub fn emit(signal: ApiSignal) {
+    if let Some(signals) = SIGNALS.get() {
+        let _ = signals.try_send(signal);
+    }
+}
+
+/// Exports the `io.github.kaii_lb.Overskride1` interface on the session bus, so scripts and other apps can drive Overskride.
+/// This blocks forever, so run it in its own thread.
+pub fn serve_api(sender: Sender<Message>) -> Result<(), dbus::Error> {
+    let conn = Arc::new(SyncConnection::new_session()?);
+    conn.request_name(API_NAME, false, true, false)?;
+
+    let (signal_sender, signal_receiver) = async_channel::unbounded::<ApiSignal>();
+    let _ = SIGNALS.set(signal_sender);
+
+    // methods that talk to bluez run on the runtime and reply when they're done, so a slow connect doesn't hold up other clients
+    let mut cr = Crossroads::new();
+    cr.set_async_support(Some((conn.clone(), Box::new(|future| {
+        runtime().spawn(future);
+    }))));
+    let api = create_api(&mut cr);
+    cr.insert(API_PATH, &[api], sender);
+
+    let cr = Mutex::new(cr);
+    conn.start_receive(MatchRule::new_method_call(), Box::new(move |msg, conn| {
+        let member = msg.member().map(|member| member.to_string()).unwrap_or_default();
+        if cr.lock().unwrap().handle_message(msg, conn).is_err() {
+            event_log::warning(Source::App, "api cannot answer a message").field("method", member).record();
+        }
+        true
+    }));
+
+    event_log::info(Source::App, "serving the api").field("name", API_NAME).record();
+
+    loop {
+        conn.process(Duration::from_millis(250))?;
+        send_signals(&conn, &signal_receiver);
+    }
+}
+
+fn send_signals(conn: &SyncConnection, signals: &Receiver<ApiSignal>) {
+    while let Ok(signal) = signals.try_recv() {
+        let message = match signal {
+            ApiSignal::ConnectionChanged(address, connected) => {
+                dbus::Message::new_signal(API_PATH, API_INTERFACE, "ConnectionChanged").unwrap().append2(address.to_string(), connected)
+            }
+            ApiSignal::TransferStarted(transfer, filename, outbound) => {
+                dbus::Message::new_signal(API_PATH, API_INTERFACE, "TransferStarted").unwrap().append3(transfer, filename, outbound)
+            }
+            ApiSignal::TransferFinished(transfer, filename, status) => {
+                dbus::Message::new_signal(API_PATH, API_INTERFACE, "TransferFinished").unwrap().append3(transfer, filename, status)
+            }
+        };
+
+        let _ = conn.send(message);
+    }
+}
+
+/// describes the API's methods and signals
+fn create_api(cr: &mut Crossroads) -> dbus_crossroads::IfaceToken<Sender<Message>> {
+    cr.register(API_INTERFACE, |b| {
+        b.signal::<(String, bool), _>("ConnectionChanged", ("address", "connected"));
+        b.signal::<(String, String, bool), _>("TransferStarted", ("transfer", "filename", "outbound"));
+        b.signal::<(String, String, String), _>("TransferFinished", ("transfer", "filename", "status"));
+
+        b.method_with_cr_async("ListDevices", (), ("devices",), |mut ctx, _, _: ()| {
+            async move {
+                let devices = list_devices().await.map_err(to_method_err);
+                ctx.reply(devices.map(|devices| (devices,)))
+            }
+        });
+
+        b.method_with_cr_async("Connect", ("address",), (), |mut ctx, _, (address,): (String,)| {
+            async move {
+                let result = match parse_address(&address) {
+                    Ok(address) => set_connected(address, true).await.map_err(to_method_err),
+                    Err(err) => Err(err),
+                };
+                ctx.reply(result)
+            }
+        });
+
+        b.method_with_cr_async("Disconnect", ("address",), (), |mut ctx, _, (address,): (String,)| {
+            async move {
+                let result = match parse_address(&address) {
+                    Ok(address) => set_connected(address, false).await.map_err(to_method_err),
+                    Err(err) => Err(err),
+                };
+                ctx.reply(result)
+            }
+        });
+
+        b.method_with_cr_async("SendFiles", ("address", "paths"), (), |mut ctx, cr, (address, paths): (String, Vec<String>)| {
+            let sender = cr.data_mut::<Sender<Message>>(ctx.path()).cloned();
+
+            async move {
+                let result = match (parse_address(&address), sender) {
+                    (Ok(destination), Some(sender)) => send_files(destination, paths, sender).await,
+                    (Err(err), _) => Err(err),
+                    (_, None) => Err(MethodErr::no_path(ctx.path())),
+                };
+                ctx.reply(result)
+            }
+        });
+
+        b.method("ShowDevice", ("address",), (), |_, sender: &mut Sender<Message>, (address,): (String,)| {
+            let address = parse_address(&address)?;
+            block_on(sender.send(Message::ShowDevice(address))).expect("cannot send message");
+            Ok(())
+        });
+    })
+}
+
+fn parse_address(address: &str) -> Result<bluer::Address, MethodErr> {
+    bluer::Address::from_str(address).map_err(|_| MethodErr::invalid_arg(address))
+}
+
+fn to_method_err(err: bluer::Error) -> MethodErr {
+    MethodErr::from((API_ERROR, err.message.as_str()))
+}
+
+/// every device the current adapter knows of, as (address, name, paired, connected)
+async fn list_devices() -> bluer::Result<Vec<(String, String, bool, bool)>> {
+    let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let mut devices = vec![];
+
+    for address in adapter.device_addresses().await? {
+        let device = adapter.device(address)?;
+
+        devices.push((
+            address.to_string(),
+            device.alias().await?,
+            device.is_paired().await?,
+            device.is_connected().await?,
+        ));
+    }
+
+    Ok(devices)
+}
+
+async fn set_connected(address: bluer::Address, connected: bool) -> bluer::Result<()> {
+    let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let device = adapter.device(address)?;
+
+    if connected {
+        device.connect().await
+    } else {
+        reconnect::user_disconnected(address);
+        device.disconnect().await
+    }
+}
+
+/// checks the files, then sends them from the current adapter in their own thread
+async fn send_files(destination: bluer::Address, paths: Vec<String>, sender: Sender<Message>) -> Result<(), MethodErr> {
+    if paths.is_empty() {
+        return Err(MethodErr::invalid_arg("paths"));
+    }
+
+    // the same checks the file picker does, obex can't tell us about missing files in a useful way
+    if let Some(missing) = paths.iter().find(|path| !std::path::Path::new(path).is_file()) {
+        return Err(MethodErr::invalid_arg(missing));
+    }
+
+    let source = current_adapter_address().await.map_err(to_method_err)?;
+
+    std::thread::spawn(move || {
+        obex::start_send_file(destination, source, paths, sender);
+    });
+
+    Ok(())
+}
+
+async fn current_adapter_address() -> bluer::Result<bluer::Address> {
+    let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+    bluer::Session::new().await?.adapter(adapter_name.as_str())?.address().await
+}
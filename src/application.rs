@@ -18,6 +18,8 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
+use std::ops::ControlFlow;
+use std::path::PathBuf;
 use gtk::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::{gio, glib};
@@ -25,6 +27,9 @@ use gtk::{gio, glib};
 use crate::config::VERSION;
 use crate::OverskrideWindow;
 
+/// glib's name for the arguments that aren't options, the files to send here
+const FILES_OPTION: &str = "";
+
 mod imp {
     use super::*;
 
@@ -43,6 +48,7 @@ mod imp {
             self.parent_constructed();
             let obj = self.obj();
             obj.setup_gactions();
+            obj.setup_options();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
             obj.set_accels_for_action("win.refresh-devices", &["<primary>r"])
         }
@@ -66,6 +72,39 @@ mod imp {
             // Ask the window manager/compositor to present the window
             window.present();
         }
+
+        // glib turns down unknown options before this, files without --send (or --send without files) are turned down here,
+        // before anything is handed to the running instance
+        fn handle_local_options(&self, options: &glib::VariantDict) -> ControlFlow<glib::ExitCode> {
+            let send = options.contains("send");
+            let has_files = options.lookup::<Vec<PathBuf>>(FILES_OPTION).ok().flatten().is_some_and(|files| !files.is_empty());
+
+            if send != has_files {
+                eprintln!("usage: overskride [--send FILE…]");
+                return ControlFlow::Break(glib::ExitCode::FAILURE);
+            }
+
+            self.parent_handle_local_options(options)
+        }
+
+        // files given with `overskride --send file1 file2` (what the file managers' "send to" runs)
+        // are sent to a device picked from a list, without asking for files again
+        fn command_line(&self, command_line: &gio::ApplicationCommandLine) -> glib::ExitCode {
+            let application = self.obj();
+            let files = command_line.options_dict().lookup::<Vec<PathBuf>>(FILES_OPTION).ok().flatten().unwrap_or_default();
+
+            // relative paths are relative to where the command ran, which isn't always where the app runs
+            let file_paths: Vec<String> = files
+                .iter()
+                .filter_map(|file| command_line.create_file_for_arg(file).path())
+                .map(|path| path.to_string_lossy().to_string())
+                .collect();
+
+            application.activate();
+            application.send_files(file_paths);
+
+            glib::ExitCode::SUCCESS
+        }
     }
 
     impl GtkApplicationImpl for OverskrideApplication {}
@@ -96,6 +135,37 @@ impl OverskrideApplication {
         self.add_action_entries([quit_action, about_action]);
     }
 
+    fn setup_options(&self) {
+        self.add_main_option(
+            "send",
+            glib::Char::from(b's'),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::None,
+            "Send files to a Bluetooth device picked from a list",
+            None,
+        );
+        self.add_main_option(
+            FILES_OPTION,
+            glib::Char::from(0),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::FilenameArray,
+            "",
+            Some("FILE…"),
+        );
+    }
+
+    /// hands the files to the window so the user can pick which device to send them to
+    fn send_files(&self, file_paths: Vec<String>) {
+        if file_paths.is_empty() {
+            return;
+        }
+
+        // if startup failed there's an error window instead, nothing to send with
+        if let Some(window) = self.active_window().and_then(|window| window.downcast::<OverskrideWindow>().ok()) {
+            window.send_files_to_picked_device(file_paths);
+        }
+    }
+
     fn show_about(&self) {
         let window = self.active_window().unwrap();
         let about = adw::AboutWindow::builder()
@@ -0,0 +1,281 @@
+use std::str::FromStr;
+use uuid::{uuid, Uuid};
+
+const APPLE: u16 = 0x004c;
+const MICROSOFT: u16 = 0x0006;
+
+const GOOGLE_FAST_PAIR: Uuid = uuid!("0000fe2c-0000-1000-8000-00805f9b34fb");
+const EDDYSTONE: Uuid = uuid!("0000feaa-0000-1000-8000-00805f9b34fb");
+
+// advertising data types, from the assigned numbers
+const SHORTENED_LOCAL_NAME: u8 = 0x08;
+const COMPLETE_LOCAL_NAME: u8 = 0x09;
+
+/// a name for a device that didn't send one, worked out from what it advertises
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdvertisedName {
+    pub name: String,
+    /// beacons and trackers only broadcast, nobody connects to them, so they count as unknown devices
+    pub beacon: bool,
+}
+
+impl AdvertisedName {
+    fn device(name: impl Into<String>) -> Self {
+        AdvertisedName { name: name.into(), beacon: false }
+    }
+
+    fn beacon(name: impl Into<String>) -> Self {
+        AdvertisedName { name: name.into(), beacon: true }
+    }
+}
+
+/// devices without a name get their address as their alias, with either ':' or '-' in between
+pub fn is_nameless(alias: &str) -> bool {
+    bluer::Address::from_str(alias.replace('-', ":").as_str()).is_ok()
+}
+
+/// Looks through the advertising, service and manufacturer data of `device` for something that tells us what it is.
+/// Pairing announcements (Swift Pair, Fast Pair, AirPods) win over beacons, since those are what people look for.
+pub async fn resolve_name(device: &bluer::Device) -> bluer::Result<Option<AdvertisedName>> {
+    let advertising_data = device.advertising_data().await?.unwrap_or_default();
+    for kind in [COMPLETE_LOCAL_NAME, SHORTENED_LOCAL_NAME] {
+        if let Some(name) = advertising_data.get(&kind).map(|name| String::from_utf8_lossy(name).trim().to_string()) {
+            if !name.is_empty() {
+                return Ok(Some(AdvertisedName::device(name)));
+            }
+        }
+    }
+
+    let manufacturer_data = device.manufacturer_data().await?.unwrap_or_default();
+    let service_data = device.service_data().await?.unwrap_or_default();
+
+    let candidates = [
+        manufacturer_data.get(&MICROSOFT).and_then(|data| parse_swift_pair(data)),
+        service_data.get(&GOOGLE_FAST_PAIR).map(|data| parse_fast_pair(data)),
+        manufacturer_data.get(&APPLE).and_then(|data| parse_apple_continuity(data)),
+        service_data.get(&EDDYSTONE).and_then(|data| parse_eddystone(data)),
+    ];
+
+    let mut candidates = candidates.into_iter().flatten().collect::<Vec<AdvertisedName>>();
+    candidates.sort_by_key(|candidate| candidate.beacon);
+
+    Ok(candidates.into_iter().next())
+}
+
+/// Microsoft Swift Pair: a 0x03 beacon id, a sub scenario, a reserved rssi byte, and for the scenarios that
+/// include BR/EDR a 6 byte address and 3 byte class of device, then the name
+fn parse_swift_pair(data: &[u8]) -> Option<AdvertisedName> {
+    if data.first() != Some(&0x03) {
+        return None;
+    }
+
+    let name_start = match data.get(1)? {
+        0x00 => 3,
+        0x01 | 0x02 => 12,
+        _ => return None,
+    };
+
+    let name = String::from_utf8_lossy(data.get(name_start..)?).trim_end_matches('\0').trim().to_string();
+    if name.is_empty() {
+        Some(AdvertisedName::device("Swift Pair Device"))
+    } else {
+        Some(AdvertisedName::device(name))
+    }
+}
+
+/// Google Fast Pair: a 3 byte model id while in pairing mode, an account key filter otherwise.
+/// The model names live in Google's online registry, so the best we can do is show the id.
+fn parse_fast_pair(data: &[u8]) -> AdvertisedName {
+    if data.len() == 3 {
+        AdvertisedName::device(format!("Fast Pair Device ({:02X}{:02X}{:02X})", data[0], data[1], data[2]))
+    } else {
+        AdvertisedName::device("Fast Pair Device")
+    }
+}
+
+/// Apple Continuity messages are a list of (type, length, data), the first one we understand names the device
+fn parse_apple_continuity(data: &[u8]) -> Option<AdvertisedName> {
+    let mut index = 0;
+    let mut found = vec![];
+
+    while index + 2 <= data.len() {
+        let kind = data[index];
+        let length = data[index + 1] as usize;
+        let Some(payload) = data.get(index + 2..index + 2 + length) else {
+            break;
+        };
+
+        let name = match kind {
+            0x02 if payload.len() >= 20 => {
+                let major = u16::from_be_bytes([payload[16], payload[17]]);
+                let minor = u16::from_be_bytes([payload[18], payload[19]]);
+                Some(AdvertisedName::beacon(format!("iBeacon ({}, {})", major, minor)))
+            }
+            0x07 if payload.len() >= 3 => Some(AdvertisedName::device(apple_audio_model(u16::from_be_bytes([payload[1], payload[2]])))),
+            0x09 => Some(AdvertisedName::device("AirPlay Device")),
+            0x0b => Some(AdvertisedName::device("Apple Watch")),
+            0x0c | 0x10 => Some(AdvertisedName::beacon("Apple Device")),
+            0x0e => Some(AdvertisedName::beacon("Apple Hotspot")),
+            0x12 => Some(AdvertisedName::beacon("Find My Accessory")),
+            _ => None,
+        };
+
+        found.extend(name);
+        index += 2 + length;
+    }
+
+    found.sort_by_key(|name| name.beacon);
+    found.into_iter().next()
+}
+
+/// the model ids sent in Apple's proximity pairing messages
+fn apple_audio_model(model: u16) -> String {
+    match model {
+        0x0220 => "AirPods",
+        0x0f20 => "AirPods (2nd generation)",
+        0x1320 => "AirPods (3rd generation)",
+        0x0e20 => "AirPods Pro",
+        0x1420 => "AirPods Pro (2nd generation)",
+        0x0a20 => "AirPods Max",
+        0x0320 => "Powerbeats3",
+        0x0520 => "BeatsX",
+        0x0620 => "Beats Solo3",
+        0x0920 => "Beats Studio3",
+        0x0b20 => "Powerbeats Pro",
+        0x0c20 => "Beats Solo Pro",
+        0x1020 => "Beats Flex",
+        0x1120 => "Beats Studio Buds",
+        0x1220 => "Beats Fit Pro",
+        _ => "Apple Headphones",
+    }
+    .to_string()
+}
+
+/// Eddystone frames, a 0x10 frame holds a compressed URL which is the most useful thing to show
+fn parse_eddystone(data: &[u8]) -> Option<AdvertisedName> {
+    match data.first()? {
+        0x00 => Some(AdvertisedName::beacon("Eddystone Beacon")),
+        0x10 => {
+            let scheme = match data.get(2)? {
+                0x00 => "http://www.",
+                0x01 => "https://www.",
+                0x02 => "http://",
+                0x03 => "https://",
+                _ => return None,
+            };
+
+            let mut url = scheme.to_string();
+            for byte in data.get(3..)? {
+                match byte {
+                    0x00..=0x0d => url.push_str([
+                        ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/",
+                        ".com", ".org", ".edu", ".net", ".info", ".biz", ".gov",
+                    ][*byte as usize]),
+                    0x21..=0x7e => url.push(*byte as char),
+                    _ => {}
+                }
+            }
+
+            Some(AdvertisedName::beacon(url))
+        }
+        0x20 => Some(AdvertisedName::beacon("Eddystone Telemetry")),
+        0x30 => Some(AdvertisedName::beacon("Eddystone Beacon")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nameless_aliases() {
+        assert!(is_nameless("AA:BB:CC:11:22:33"));
+        assert!(is_nameless("AA-BB-CC-11-22-33"));
+        assert!(!is_nameless("Headphones"));
+    }
+
+    #[test]
+    fn swift_pair() {
+        let mut le_only = vec![0x03, 0x00, 0x80];
+        le_only.extend(b"Surface Mouse");
+        assert_eq!(parse_swift_pair(&le_only), Some(AdvertisedName::device("Surface Mouse")));
+
+        // the BR/EDR scenarios carry an address and class of device before the name
+        let mut with_address = vec![0x03, 0x02, 0x80, 0x33, 0x22, 0x11, 0xcc, 0xbb, 0xaa, 0x04, 0x04, 0x24];
+        with_address.extend(b"Headphones\0");
+        assert_eq!(parse_swift_pair(&with_address), Some(AdvertisedName::device("Headphones")));
+
+        assert_eq!(parse_swift_pair(&[0x03, 0x00, 0x80]), Some(AdvertisedName::device("Swift Pair Device")));
+    }
+
+    #[test]
+    fn not_swift_pair() {
+        assert_eq!(parse_swift_pair(&[]), None);
+        assert_eq!(parse_swift_pair(&[0x01, 0x00, 0x80, b'a']), None);
+        assert_eq!(parse_swift_pair(&[0x03, 0x07, 0x80, b'a']), None);
+        // cut short before the name
+        assert_eq!(parse_swift_pair(&[0x03, 0x01, 0x80, 0x33]), None);
+    }
+
+    #[test]
+    fn fast_pair() {
+        assert_eq!(parse_fast_pair(&[0x2c, 0x3d, 0x0f]), AdvertisedName::device("Fast Pair Device (2C3D0F)"));
+        assert_eq!(parse_fast_pair(&[0x00, 0x11, 0x22, 0x33, 0x44]), AdvertisedName::device("Fast Pair Device"));
+    }
+
+    #[test]
+    fn apple_continuity() {
+        let airpods_pro = [0x07, 0x03, 0x01, 0x0e, 0x20];
+        assert_eq!(parse_apple_continuity(&airpods_pro), Some(AdvertisedName::device("AirPods Pro")));
+
+        let unknown_model = [0x07, 0x03, 0x01, 0xff, 0x20];
+        assert_eq!(parse_apple_continuity(&unknown_model), Some(AdvertisedName::device("Apple Headphones")));
+
+        let mut ibeacon = vec![0x02, 0x15];
+        ibeacon.extend([0u8; 16]);
+        ibeacon.extend([0x00, 0x01, 0x00, 0x2a, 0xc5]);
+        assert_eq!(parse_apple_continuity(&ibeacon), Some(AdvertisedName::beacon("iBeacon (1, 42)")));
+    }
+
+    #[test]
+    fn apple_devices_win_over_beacons() {
+        // a nearby info message followed by a watch message
+        let data = [0x10, 0x02, 0x01, 0x02, 0x0b, 0x01, 0x00];
+        assert_eq!(parse_apple_continuity(&data), Some(AdvertisedName::device("Apple Watch")));
+        assert_eq!(parse_apple_continuity(&data[..4]), Some(AdvertisedName::beacon("Apple Device")));
+    }
+
+    #[test]
+    fn not_apple_continuity() {
+        assert_eq!(parse_apple_continuity(&[]), None);
+        assert_eq!(parse_apple_continuity(&[0x01, 0x00]), None);
+        // the length runs past the end
+        assert_eq!(parse_apple_continuity(&[0x07, 0x09, 0x01, 0x0e, 0x20]), None);
+    }
+
+    #[test]
+    fn eddystone() {
+        let mut url = vec![0x10, 0xeb, 0x03];
+        url.extend(b"example");
+        url.extend([0x07, 0xff]);
+        assert_eq!(parse_eddystone(&url), Some(AdvertisedName::beacon("https://example.com")));
+
+        let mut path = vec![0x10, 0xeb, 0x00];
+        path.extend(b"gnome");
+        path.extend([0x01]);
+        path.extend(b"apps");
+        assert_eq!(parse_eddystone(&path), Some(AdvertisedName::beacon("http://www.gnome.org/apps")));
+
+        assert_eq!(parse_eddystone(&[0x00, 0xeb]), Some(AdvertisedName::beacon("Eddystone Beacon")));
+        assert_eq!(parse_eddystone(&[0x20, 0x00]), Some(AdvertisedName::beacon("Eddystone Telemetry")));
+    }
+
+    #[test]
+    fn not_eddystone() {
+        assert_eq!(parse_eddystone(&[]), None);
+        assert_eq!(parse_eddystone(&[0x40]), None);
+        assert_eq!(parse_eddystone(&[0x10, 0xeb, 0x09]), None);
+        assert_eq!(parse_eddystone(&[0x10, 0xeb]), None);
+    }
+}
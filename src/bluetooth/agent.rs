@@ -1,12 +1,16 @@
 use async_channel::Sender;
 use futures::FutureExt;
 
+use crate::audio_sink::is_sink_service;
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
 use crate::message::Message;
+use crate::telephony::is_call_service;
 use crate::window::OVERSKRIDE_PROPS;
 
 async fn request_pin_code(request: bluer::agent::RequestPinCode, sender: Sender<Message>) -> bluer::agent::ReqResult<String> {
-    println!("request pincode incoming");
 	let address = request.device;
+    event_log::info(Source::Agent, "pin code requested").device(address).record();
 
     sender.send(Message::RequestPinCode(request)).await.expect("cannot send message");
 
@@ -16,18 +20,21 @@ async fn request_pin_code(request: bluer::agent::RequestPinCode, sender: Sender<
 
     let final_pin_code = OVERSKRIDE_PROPS.lock().unwrap().pin_code.clone();
 
-    println!("pin code is: {:?}", final_pin_code);
     if final_pin_code.is_empty() {
+        event_log::info(Source::Agent, "pin code request rejected").device(address).record();
       	Err(bluer::agent::ReqError::Rejected)
     }
     else {
+        // the pin itself stays out of the log, it ends up in bug reports
+        event_log::info(Source::Agent, "pin code entered").device(address).record();
         sender.send(Message::SwitchActive(true, address, true)).await.expect("cannot send message");
 	    Ok(final_pin_code)
     }
 }
 
 async fn display_pin_code(request: bluer::agent::DisplayPinCode, sender: Sender<Message>) -> bluer::agent::ReqResult<()> {
-    println!("display pincode incoming");
+	let address = request.device;
+    event_log::info(Source::Agent, "displaying pin code").device(address).record();
 
     sender.send(Message::DisplayPinCode(request)).await.expect("cannot send message");
 
@@ -35,13 +42,13 @@ async fn display_pin_code(request: bluer::agent::DisplayPinCode, sender: Sender<
 
     wait_for_dialog_exit().await;
 
-    println!("displaying pin code finished");
+    event_log::info(Source::Agent, "pin code dismissed").device(address).record();
     Ok(())
 }
 
 async fn request_pass_key(request: bluer::agent::RequestPasskey, sender: Sender<Message>) -> bluer::agent::ReqResult<u32> {
-    println!("request passkey incoming");
 	let address = request.device;
+    event_log::info(Source::Agent, "passkey requested").device(address).record();
 
     sender.send(Message::RequestPassKey(request)).await.expect("cannot send message");
 
@@ -50,18 +57,19 @@ async fn request_pass_key(request: bluer::agent::RequestPasskey, sender: Sender<
     wait_for_dialog_exit().await;
 
     let pass_key = OVERSKRIDE_PROPS.lock().unwrap().pass_key.clone();
-    println!("pass key is: {}", pass_key);
     if pass_key == 0 {
+        event_log::info(Source::Agent, "passkey request rejected").device(address).record();
     	Err(bluer::agent::ReqError::Rejected)
     }
     else {
+        event_log::info(Source::Agent, "passkey entered").device(address).record();
         sender.send(Message::SwitchActive(true, address, true)).await.expect("cannot send message");
     	Ok(pass_key)
     }
 }   
 
 async fn display_pass_key(request: bluer::agent::DisplayPasskey, sender: Sender<Message>) -> bluer::agent::ReqResult<()> {
-    println!("display passkey incoming");
+    event_log::info(Source::Agent, "displaying passkey").device(request.device).field("entered", request.entered).record();
     
     sender.send(Message::DisplayPassKey(request)).await.expect("cannot send message");
 
@@ -73,8 +81,8 @@ async fn display_pass_key(request: bluer::agent::DisplayPasskey, sender: Sender<
 }
 
 async fn request_confirmation(request: bluer::agent::RequestConfirmation, _: bluer::Session, _: bool, sender: Sender<Message>) -> bluer::agent::ReqResult<()> {
-    println!("pairing confirmation incoming");
 	let address = request.device;
+    event_log::info(Source::Agent, "pairing confirmation requested").device(address).record();
     
     sender.send(Message::RequestConfirmation(request)).await.expect("cannot send message");
 
@@ -85,19 +93,19 @@ async fn request_confirmation(request: bluer::agent::RequestConfirmation, _: blu
     let confirmed = OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization;
 
     if confirmed {
-        println!("allowed pairing with device");
+        event_log::info(Source::Agent, "request allowed").device(address).record();
         sender.send(Message::SwitchActive(true, address, true)).await.expect("cannot send message");
         Ok(())
     }
     else {
-        println!("rejected pairing with device");
+        event_log::info(Source::Agent, "request rejected").device(address).record();
         Err(bluer::agent::ReqError::Rejected)
     }
 }
 
 async fn request_authorization(request: bluer::agent::RequestAuthorization, _: bluer::Session, _: bool, sender: Sender<Message>) -> bluer::agent::ReqResult<()> {
-    println!("pairing authorization incoming");
 	let address = request.device;
+    event_log::info(Source::Agent, "pairing authorization requested").device(address).record();
 
     sender.send(Message::RequestAuthorization(request)).await.expect("cannot send message");
 
@@ -107,20 +115,34 @@ async fn request_authorization(request: bluer::agent::RequestAuthorization, _: b
 
     let confirmed = OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization;
     if confirmed {
-        println!("allowed pairing with device");
+        event_log::info(Source::Agent, "request allowed").device(address).record();
         sender.send(Message::SwitchActive(true, address, true)).await.expect("cannot send message");
         Ok(())
     }
     else {
-        println!("rejected pairing with device");
+        event_log::info(Source::Agent, "request rejected").device(address).record();
         Err(bluer::agent::ReqError::Rejected)
     }
 
 }
 
 async fn authorize_service(request: bluer::agent::AuthorizeService, sender: Sender<Message>) -> bluer::agent::ReqResult<()> {
-    println!("service authorization incoming");
 	let address = request.device;
+    event_log::info(Source::Agent, "service authorization requested").device(address).field("service", request.service).record();
+
+    // when acting as a speaker, trusted phones can start streaming without asking every time
+    let audio_sink_mode = OVERSKRIDE_PROPS.lock().unwrap().audio_sink_mode;
+    if audio_sink_mode && is_sink_service(request.service) && is_device_trusted(&request.adapter, address).await {
+        event_log::info(Source::Agent, "audio sink service authorized, the device is trusted").device(address).record();
+        return Ok(());
+    }
+
+    // same goes for phones that want to route their calls through us
+    let call_integration = OVERSKRIDE_PROPS.lock().unwrap().call_integration;
+    if call_integration && is_call_service(request.service) && is_device_trusted(&request.adapter, address).await {
+        event_log::info(Source::Agent, "hands-free service authorized, the device is trusted").device(address).record();
+        return Ok(());
+    }
 
     sender.send(Message::AuthorizeService(request)).await.expect("cannot send message");
 
@@ -131,17 +153,27 @@ async fn authorize_service(request: bluer::agent::AuthorizeService, sender: Send
     let confirmed = OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization;
 
     if confirmed {
-        println!("allowed pairing with device");
+        event_log::info(Source::Agent, "request allowed").device(address).record();
         sender.send(Message::SwitchActive(true, address, true)).await.expect("cannot send message");
         Ok(())
     }
     else {
-        println!("rejected pairing with device");
+        event_log::info(Source::Agent, "request rejected").device(address).record();
         Err(bluer::agent::ReqError::Rejected)
     }
 }
 
-pub async fn register_agent(session: &bluer::Session, request_default: bool, set_trust: bool, sender_to_be_sent: Sender<Message>) -> bluer::Result<bluer::agent::AgentHandle> {
+/// checks if the device requesting something is trusted, any failure counts as not trusted
+async fn is_device_trusted(adapter_name: &str, address: bluer::Address) -> bool {
+    let trusted = async {
+        let adapter = bluer::Session::new().await?.adapter(adapter_name)?;
+        adapter.device(address)?.is_trusted().await
+    };
+
+    trusted.await.unwrap_or(false)
+}
+
+pub async fn register_agent(session: &bluer::Session, request_default: bool, set_trust: bool, sender_to_be_sent: Sender<Message>) -> Result<bluer::agent::AgentHandle, OverskrideError> {
     let session1 = session.clone();
     let session2 = session.clone();
 
@@ -170,7 +202,12 @@ pub async fn register_agent(session: &bluer::Session, request_default: bool, set
         ..Default::default()
     };
 
-    let handle = session.register_agent(agent).await.expect("unable to register agent, fuck-");
+    // bluez only takes one agent per connection, so this means the session already has ours.
+    // another app's agent being the default doesn't fail this, asking to be the default takes over from it
+    let handle = session.register_agent(agent).await.map_err(|err| match err.kind {
+        bluer::ErrorKind::AlreadyExists => OverskrideError::AgentAlreadyRegistered,
+        _ => err.into(),
+    })?;
 
     Ok(handle)
 }
@@ -185,14 +222,3 @@ pub async fn wait_for_dialog_exit() {
         }
     }
 }
-
-#[tokio::main]
-pub async fn register_bluetooth_agent(sender: Sender<Message>) -> bluer::Result<()> {
-	let session = bluer::Session::new().await?;
-	let agent = register_agent(&session, true, false, sender.clone()).await?;	
-   	println!("registered agent standalone {:?}", agent);
-
-   	loop {
-   		tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-   	}
-} 
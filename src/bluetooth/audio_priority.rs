@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use async_channel::Sender;
+use bluer::Uuid;
+
+use crate::audio_sink::A2DP_SINK;
+use crate::device;
+use crate::event_log::{self, Source};
+use crate::message::Message;
+use crate::reconnect;
+use crate::telephony::{HFP_HANDS_FREE, HSP_HEADSET};
+use crate::window::OVERSKRIDE_PROPS;
+
+/// whether a device is something audio can be played on (headphones, speakers, headsets), from the services it has
+pub fn is_audio_device(uuids: &HashSet<Uuid>) -> bool {
+    [A2DP_SINK, HFP_HANDS_FREE, HSP_HEADSET].iter().any(|uuid| uuids.contains(uuid))
+}
+
+pub fn is_prioritized(address: bluer::Address) -> bool {
+    OVERSKRIDE_PROPS.lock().unwrap().audio_priority.contains(&address)
+}
+
+/// where the device is in the priority list, lower is more important
+fn rank(address: bluer::Address) -> Option<usize> {
+    OVERSKRIDE_PROPS.lock().unwrap().audio_priority.iter().position(|other| *other == address)
+}
+
+fn switching_mode() -> String {
+    OVERSKRIDE_PROPS.lock().unwrap().audio_switching.clone()
+}
+
+/// Called when a prioritized device shows up, connects it if nothing more important is connected already.
+/// Only does anything when switching is set to "always".
+pub async fn on_device_available(address: bluer::Address, adapter_name: String, sender: Sender<Message>) -> bluer::Result<()> {
+    let Some(device_rank) = rank(address) else {
+        return Ok(());
+    };
+
+    if switching_mode() != "always" {
+        return Ok(());
+    }
+
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    if adapter.device(address)?.is_connected().await? {
+        return Ok(());
+    }
+
+    if let Some((_, connected_rank)) = highest_connected(&adapter).await? {
+        if connected_rank < device_rank {
+            return Ok(());
+        }
+    }
+
+    event_log::info(Source::Device, "switching to the device, it has a higher audio priority").device(address).record();
+    connect(address, adapter_name, sender).await
+}
+
+/// Called when a device got connected, if it's prioritized every less important audio device is disconnected.
+/// A device the user connected by hand while a more important one is connected is left alone.
+pub async fn on_device_connected(address: bluer::Address, adapter_name: String) -> bluer::Result<()> {
+    if switching_mode() != "always" || rank(address).is_none() {
+        return Ok(());
+    }
+
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    disconnect_lower(&adapter, address).await
+}
+
+/// Connects the most important prioritized device that can be reached and disconnects the ones below it, used on startup.
+/// Devices out of reach fail to connect, so the list is gone through until one works.
+pub async fn connect_highest_present(adapter_name: String, sender: Sender<Message>) -> bluer::Result<()> {
+    if switching_mode() == "off" {
+        return Ok(());
+    }
+
+    let priority = OVERSKRIDE_PROPS.lock().unwrap().audio_priority.clone();
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let known = adapter.device_addresses().await?;
+
+    for address in priority.into_iter().filter(|address| known.contains(address)) {
+        let device = adapter.device(address)?;
+
+        if !device.is_connected().await? {
+            if let Err(err) = connect(address, adapter_name.clone(), sender.clone()).await {
+                event_log::warning(Source::Device, "cannot connect on startup, trying the next one").device(address).field("error", &err).record();
+                continue;
+            }
+        }
+
+        return disconnect_lower(&adapter, address).await;
+    }
+
+    Ok(())
+}
+
+/// the most important prioritized device that's connected, with its rank
+async fn highest_connected(adapter: &bluer::Adapter) -> bluer::Result<Option<(bluer::Address, usize)>> {
+    let priority = OVERSKRIDE_PROPS.lock().unwrap().audio_priority.clone();
+    let known = adapter.device_addresses().await?;
+
+    for (index, address) in priority.into_iter().enumerate() {
+        if known.contains(&address) && adapter.device(address)?.is_connected().await? {
+            return Ok(Some((address, index)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// disconnects every connected prioritized device that's less important than `address`
+async fn disconnect_lower(adapter: &bluer::Adapter, address: bluer::Address) -> bluer::Result<()> {
+    let priority = OVERSKRIDE_PROPS.lock().unwrap().audio_priority.clone();
+    let known = adapter.device_addresses().await?;
+
+    let lower = priority.into_iter().skip_while(|other| *other != address).skip(1);
+    for other in lower.filter(|other| known.contains(other)) {
+        let device = adapter.device(other)?;
+
+        if device.is_connected().await? {
+            event_log::info(Source::Device, "disconnecting, another device has a higher audio priority").device(other).field("preferred", address).record();
+            // switching away on purpose, keep connected shouldn't bring it back
+            reconnect::user_disconnected(other);
+            device.disconnect().await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// the shown device goes through the connected switch so the device page updates along, others are connected quietly
+async fn connect(address: bluer::Address, adapter_name: String, sender: Sender<Message>) -> bluer::Result<()> {
+    let current_address = OVERSKRIDE_PROPS.lock().unwrap().address;
+
+    if address == current_address {
+        device::set_device_active(address, sender, adapter_name).await
+    }
+    else {
+        let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+        adapter.device(address)?.connect().await
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use bluer::Error;
 use std::{cell::RefCell, rc::Rc, ops::Deref};
+use crate::event_log::{self, Source};
 
 use pulseaudio::{mainloop::standard::{IterateResult, Mainloop}, context::Context, proplist::Proplist, def::Retval};
 use pulseaudio::context::FlagSet as ContextFlagSet;
@@ -35,7 +36,7 @@ impl AudioProfiles {
 			match mainloop.borrow_mut().iterate(false) {
 				IterateResult::Quit(_) |
 				IterateResult::Err(_) => {
-					eprintln!("Iterate state was not success, quitting...");
+					event_log::warning(Source::App, "cannot talk to the sound server").record();
 					return Err(Error { kind: bluer::ErrorKind::Failed, message: "iterate state was not a success".to_string() });
 				},
 				IterateResult::Success(_) => {},
@@ -44,7 +45,7 @@ impl AudioProfiles {
 				pulseaudio::context::State::Ready => { break; },
 				pulseaudio::context::State::Failed |
 				pulseaudio::context::State::Terminated => {
-					eprintln!("Context state failed/terminated, quitting...");
+					event_log::warning(Source::App, "sound server connection failed").record();
 					return Err(Error { kind: bluer::ErrorKind::Failed, message: "context state failed".to_string() });
 				},
 				_ => {},
@@ -69,8 +70,6 @@ impl AudioProfiles {
 				pulseaudio::callbacks::ListResult::Item(item) => {	
 					let card_profiles = &item.profiles;
 					for card_profile in card_profiles {
-						// println!("\nprofile: {:?}", card_profile.name);
-						// println!("profile description: {:?}", card_profile.description);
 
 						if let Some(profile) = &card_profile.name {
 							if let Some(description) = &card_profile.description {
@@ -91,13 +90,12 @@ impl AudioProfiles {
 						"".to_string()
 					};
 
-					// println!("\ncard active profile: {:?}", item.active_profile.as_ref().unwrap());
 				},
 				pulseaudio::callbacks::ListResult::End => {
-					println!("device's audio profiles enumerated");
+					event_log::debug(Source::Device, "audio profiles listed").record();
 				},
 				pulseaudio::callbacks::ListResult::Error => {
-					println!("could not get audio profiles for device");
+					event_log::warning(Source::Device, "cannot list audio profiles").record();
 					*error_clone.borrow_mut() = true;
 				}
 			}
@@ -155,7 +153,7 @@ pub fn device_set_profile(address: String, profile: String) {
 		match mainloop.borrow_mut().iterate(false) {
 			IterateResult::Quit(_) |
 			IterateResult::Err(_) => {
-				eprintln!("Iterate state was not success, quitting...");
+				event_log::warning(Source::App, "cannot talk to the sound server").record();
 				return;
 			},
 			IterateResult::Success(_) => {},
@@ -164,7 +162,7 @@ pub fn device_set_profile(address: String, profile: String) {
 			pulseaudio::context::State::Ready => { break; },
 			pulseaudio::context::State::Failed |
 			pulseaudio::context::State::Terminated => {
-				eprintln!("Context state failed/terminated, quitting...");
+				event_log::warning(Source::App, "sound server connection failed").record();
 				return;
 			},
 			_ => {},
@@ -176,9 +174,9 @@ pub fn device_set_profile(address: String, profile: String) {
 	let clonable_state = Rc::new(RefCell::new(false));
 	let clone = clonable_state.clone();
 	let done = Rc::new(RefCell::new(false));
-	let done_clone = clonable_state.clone();
+	let done_clone = done.clone();
 
-	println!("{} {}", &card_name, &profile);
+	event_log::info(Source::Device, "setting audio profile").field("card", &card_name).field("profile", &profile).record();
 
 	// sets the card profile, then updates the state and the done-ness of this function
 	// should move to using the returned "Operation" value instead of weird ass borrows
@@ -198,3 +196,61 @@ pub fn device_set_profile(address: String, profile: String) {
 	mainloop.borrow_mut().quit(Retval(0));
 	context.borrow_mut().disconnect();
 }
+
+/// a mainloop and its connected context
+pub type PulseConnection = (Rc<RefCell<Mainloop>>, Rc<RefCell<Context>>);
+
+/// connects to the sound server, returning the mainloop and a ready context, or an error if the server isn't reachable
+pub fn connect_context() -> Result<PulseConnection, Error> {
+	let mut proplist = Proplist::new().unwrap();
+	proplist.set_str(pulseaudio::proplist::properties::APPLICATION_NAME, "Overskride")
+		.unwrap();
+
+	let mainloop = Rc::new(RefCell::new(Mainloop::new()
+		.expect("Failed to create mainloop")));
+
+	let context = Rc::new(RefCell::new(Context::new_with_proplist(
+		mainloop.borrow().deref(),
+		"OverskrideContext",
+		&proplist
+		).expect("Failed to create new context")));
+
+	context.borrow_mut().connect(None, ContextFlagSet::NOFLAGS, None)
+		.map_err(|_| Error { kind: bluer::ErrorKind::Failed, message: "cannot connect to sound server".to_string() })?;
+
+	// Wait for context to be ready
+	loop {
+		match mainloop.borrow_mut().iterate(false) {
+			IterateResult::Quit(_) |
+			IterateResult::Err(_) => {
+				event_log::warning(Source::App, "cannot talk to the sound server").record();
+				return Err(Error { kind: bluer::ErrorKind::Failed, message: "iterate state was not a success".to_string() });
+			},
+			IterateResult::Success(_) => {},
+		}
+		match context.borrow().get_state() {
+			pulseaudio::context::State::Ready => { break; },
+			pulseaudio::context::State::Failed |
+			pulseaudio::context::State::Terminated => {
+				event_log::warning(Source::App, "sound server connection failed").record();
+				return Err(Error { kind: bluer::ErrorKind::Failed, message: "context state failed".to_string() });
+			},
+			_ => {},
+		}
+	}
+
+	Ok((mainloop, context))
+}
+
+/// iterates the mainloop until the given operation is done
+pub fn wait_for_operation<T: ?Sized>(mainloop: &Rc<RefCell<Mainloop>>, operation: &pulseaudio::operation::Operation<T>) {
+	while operation.get_state() == pulseaudio::operation::State::Running {
+		mainloop.borrow_mut().iterate(true);
+	}
+}
+
+/// disconnects from the sound server, see [connect_context](connect_context)
+pub fn disconnect_context(mainloop: &Rc<RefCell<Mainloop>>, context: &Rc<RefCell<Context>>) {
+	mainloop.borrow_mut().quit(Retval(0));
+	context.borrow_mut().disconnect();
+}
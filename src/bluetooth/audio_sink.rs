@@ -0,0 +1,165 @@
+use std::{cell::RefCell, rc::Rc};
+use async_channel::Sender;
+use bluer::Uuid;
+use uuid::uuid;
+
+use crate::audio_profiles::{connect_context, disconnect_context, wait_for_operation};
+use crate::message::Message;
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
+use crate::window::OVERSKRIDE_PROPS;
+
+/// the local service a phone connects to when it wants to stream audio to us
+pub const A2DP_SINK: Uuid = uuid!("0000110b-0000-1000-8000-00805f9b34fb");
+/// the service a phone advertises when it is able to stream audio
+pub const A2DP_SOURCE: Uuid = uuid!("0000110a-0000-1000-8000-00805f9b34fb");
+
+/// every service a phone asks for while setting up an audio stream to us (audio sink, advanced audio and remote control)
+const SINK_SERVICES: [Uuid; 4] = [
+	A2DP_SINK,
+	uuid!("0000110d-0000-1000-8000-00805f9b34fb"),
+	uuid!("0000110e-0000-1000-8000-00805f9b34fb"),
+	uuid!("0000110c-0000-1000-8000-00805f9b34fb"),
+];
+
+/// checks if the requested service is part of a phone streaming audio to us
+pub fn is_sink_service(service: Uuid) -> bool {
+	SINK_SERVICES.contains(&service)
+}
+
+/// turns the "act as a speaker" mode on or off, making the adapter visible to phones while it's on
+pub async fn set_audio_sink_mode(adapter_name: String, enabled: bool, sender: Sender<Message>) -> Result<(), OverskrideError> {
+	let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+
+	if enabled {
+		// the sink endpoint itself is registered by the sound server (pipewire/pulseaudio),
+		// if the adapter doesn't list it then nothing can stream to us
+		let uuids = adapter.uuids().await?.unwrap_or_default();
+		if !uuids.contains(&A2DP_SINK) {
+			sender.send(Message::SetAudioSinkMode(false)).await.expect("cannot send message");
+			return Err(OverskrideError::AudioSinkUnsupported);
+		}
+
+		adapter.set_pairable(true).await?;
+		adapter.set_discoverable(true).await?;
+	}
+
+	OVERSKRIDE_PROPS.lock().unwrap().audio_sink_mode = enabled;
+	sender.send(Message::SetAudioSinkMode(enabled)).await.expect("cannot send message");
+
+	tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
+	let discoverable = adapter.is_discoverable().await?;
+	sender.send(Message::SwitchAdapterDiscoverable(discoverable)).await.expect("cannot send message");
+
+	Ok(())
+}
+
+/// updates which phone is currently streaming to us, routing its audio to the default output when it connects
+pub async fn update_streaming_device(address: bluer::Address, connected: bool, adapter_name: String, sender: Sender<Message>) -> bluer::Result<()> {
+	if !connected {
+		let streaming = OVERSKRIDE_PROPS.lock().unwrap().streaming_address;
+
+		if streaming == address {
+			OVERSKRIDE_PROPS.lock().unwrap().streaming_address = bluer::Address::any();
+			sender.send(Message::SetStreamingDevice(None)).await.expect("cannot send message");
+		}
+
+		return Ok(());
+	}
+
+	let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+	let device = adapter.device(address)?;
+
+	// only phones and the like can stream audio, headphones and such can't
+	if !device.uuids().await?.unwrap_or_default().contains(&A2DP_SOURCE) {
+		return Ok(());
+	}
+
+	// give the sound server some time to create the bluetooth source
+	tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+	let address_string = address.to_string();
+	let routed = tokio::task::spawn_blocking(move || route_to_default_output(address_string)).await;
+
+	if !matches!(routed, Ok(Ok(()))) {
+		event_log::warning(Source::Device, "cannot route the device's audio to the default output").device(address).record();
+		sender.send(Message::PopupError(OverskrideError::AudioSinkRouteFailed, adw::ToastPriority::High)).await.expect("cannot send message");
+		return Ok(());
+	}
+
+	let alias = device.alias().await?;
+	OVERSKRIDE_PROPS.lock().unwrap().streaming_address = address;
+	sender.send(Message::SetStreamingDevice(Some(alias))).await.expect("cannot send message");
+
+	Ok(())
+}
+
+/// plays the bluetooth source of a device on the default output.
+/// pipewire does this by itself, so this only has work to do on pulseaudio where the source has to be looped back by hand
+pub fn route_to_default_output(address: String) -> Result<(), OverskrideError> {
+	let (mainloop, context) = connect_context()?;
+
+	// pulse audio bluetooth sources are called "bluez_source.XX_XX_XX_XX_XX_XX.a2dp_source"
+	let source_prefix = "bluez_source.".to_string() + &address.replace(':', "_");
+
+	let source_name = Rc::new(RefCell::new(String::new()));
+	let source_name_clone = source_name.clone();
+
+	let operation = context.borrow().introspect().get_source_info_list(move |result| {
+		if let pulseaudio::callbacks::ListResult::Item(item) = result {
+			if let Some(name) = &item.name {
+				if name.starts_with(&source_prefix) {
+					*source_name_clone.borrow_mut() = name.to_string();
+				}
+			}
+		}
+	});
+	wait_for_operation(&mainloop, &operation);
+
+	let source = source_name.borrow().clone();
+	if source.is_empty() {
+		event_log::debug(Source::Device, "no bluetooth source, assuming the sound server routes it").field("device", &address).record();
+		disconnect_context(&mainloop, &context);
+		return Ok(());
+	}
+
+	// don't loop the same source back twice if it is already playing
+	let already_routed = Rc::new(RefCell::new(false));
+	let already_routed_clone = already_routed.clone();
+	let source_clone = source.clone();
+
+	let operation = context.borrow().introspect().get_module_info_list(move |result| {
+		if let pulseaudio::callbacks::ListResult::Item(item) = result {
+			let is_loopback = item.name.as_deref() == Some("module-loopback");
+			let has_source = item.argument.as_deref().unwrap_or("").contains(&source_clone);
+
+			if is_loopback && has_source {
+				*already_routed_clone.borrow_mut() = true;
+			}
+		}
+	});
+	wait_for_operation(&mainloop, &operation);
+
+	if !*already_routed.borrow() {
+		// leaving out the sink makes the loopback follow the default output
+		let arguments = "source=".to_string() + &source + " source_dont_move=true";
+		let module_index = Rc::new(RefCell::new(u32::MAX));
+		let module_index_clone = module_index.clone();
+
+		let mut introspector = context.borrow().introspect();
+		let operation = introspector.load_module("module-loopback", &arguments, move |index| {
+			*module_index_clone.borrow_mut() = index;
+		});
+		wait_for_operation(&mainloop, &operation);
+
+		if *module_index.borrow() == u32::MAX {
+			disconnect_context(&mainloop, &context);
+			return Err(OverskrideError::AudioSinkRouteFailed);
+		}
+
+		event_log::info(Source::App, "routing the device's audio to the default output").field("source", &source).record();
+	}
+
+	disconnect_context(&mainloop, &context);
+	Ok(())
+}
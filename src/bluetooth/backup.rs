@@ -0,0 +1,376 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::io::Write;
+use std::fs::OpenOptions;
+use std::os::unix::fs::OpenOptionsExt;
+use std::time::Duration;
+use gtk::glib;
+
+use crate::config::LIBEXECDIR;
+use crate::device_store::{self, DeviceSettings};
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
+use crate::window::OVERSKRIDE_PROPS;
+
+/// bumped whenever the backup file changes in a way older versions can't read
+const BACKUP_VERSION: i32 = 1;
+const BACKUP_GROUP: &str = "Overskride Backup";
+const DEVICE_GROUP_PREFIX: &str = "Device ";
+
+/// the helper that reads and writes bluetoothd's storage, run through pkexec so polkit asks for the password
+const KEYS_HELPER: &str = "overskride-keys-helper";
+
+/// everything backed up about one device
+#[derive(Debug, Clone, Default)]
+struct DeviceBackup {
+    address: bluer::Address,
+    name: String,
+    alias: String,
+    trusted: bool,
+    blocked: bool,
+    keep_connected: bool,
+    /// where it was in the audio priority list, if it was in it
+    audio_priority: Option<usize>,
+    /// its icon, notes and the rest of its own settings, without the [privileged](DeviceSettings::without_privileged) ones
+    settings: DeviceSettings,
+    /// bluetoothd's info file for the device (with the link keys in it), base64 encoded
+    keys: Option<String>,
+}
+
+/// Backs up the paired (and blocked) devices of the adapter to `path`, with their Overskride settings
+/// (leaving out the commands, unlocking and accepting files without asking). With `include_keys` the link keys are copied out of `/var/lib/bluetooth` too, which asks for the password.
+/// Returns how many devices were backed up.
+pub async fn export(path: PathBuf, include_keys: bool, adapter_name: String) -> Result<usize, OverskrideError> {
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let adapter_address = adapter.address().await?;
+
+    let (keep_connected, audio_priority) = {
+        let props = OVERSKRIDE_PROPS.lock().unwrap();
+        (props.keep_connected.clone(), props.audio_priority.clone())
+    };
+
+    let mut devices = vec![];
+    for address in adapter.device_addresses().await? {
+        let device = adapter.device(address)?;
+        let blocked = device.is_blocked().await?;
+
+        if !device.is_paired().await? && !blocked {
+            continue;
+        }
+
+        devices.push(DeviceBackup {
+            address,
+            name: device.name().await?.unwrap_or_default(),
+            alias: device.alias().await?,
+            trusted: device.is_trusted().await?,
+            blocked,
+            keep_connected: keep_connected.contains(&address),
+            audio_priority: audio_priority.iter().position(|other| *other == address),
+            settings: device_store::get(address).without_privileged(),
+            keys: None,
+        });
+    }
+
+    if include_keys {
+        let output = tokio::task::spawn_blocking(move || run_keys_helper(&["export", &adapter_address.to_string()], None)).await
+            .map_err(|_| OverskrideError::KeysHelperFailed)??;
+
+        for line in output.lines() {
+            let Some((address, keys)) = line.split_once(' ') else {
+                continue;
+            };
+
+            if let Some(device) = devices.iter_mut().find(|device| device.address.to_string() == address) {
+                device.keys = Some(keys.trim().to_string());
+            }
+        }
+    }
+
+    write_backup(&path, adapter_address, &devices)?;
+    event_log::info(Source::App, "devices backed up").field("devices", devices.len()).field("file", path.display()).record();
+
+    Ok(devices.len())
+}
+
+/// Restores the devices in the backup at `path` onto the adapter.
+/// With `include_keys` the link keys in it are written to `/var/lib/bluetooth` first (asking for the password) and bluetoothd restarted,
+/// so the devices come back paired. Without them only devices bluez knows already get their alias, trust and block state back.
+/// Keys backed up from a different adapter are never written, they would replace the ones this adapter pairs with.
+/// Overskride's own settings are put back in [OVERSKRIDE_PROPS](OVERSKRIDE_PROPS), saving them is up to the caller.
+/// Returns how many devices were restored, and whether keys were left out because the backup was made on a different adapter.
+pub async fn import(path: PathBuf, include_keys: bool, adapter_name: String) -> Result<(usize, bool), OverskrideError> {
+    let (backed_up_adapter, devices) = read_backup(&path)?;
+
+    let adapter_address = bluer::Session::new().await?.adapter(adapter_name.as_str())?.address().await?;
+    let other_adapter = backed_up_adapter.is_some_and(|backed_up_adapter| backed_up_adapter != adapter_address);
+
+    let keys = devices.iter()
+        .filter_map(|device| device.keys.as_ref().map(|keys| format!("{} {}\n", device.address, keys)))
+        .collect::<String>();
+    let keys_skipped = include_keys && !keys.is_empty() && other_adapter;
+
+    if other_adapter {
+        event_log::warning(Source::Adapter, "restoring a backup made on a different adapter")
+            .field("backup", backed_up_adapter.unwrap_or_default())
+            .field("adapter", adapter_address)
+            .field("skipped", keys_skipped)
+            .record();
+    }
+
+    if include_keys && !keys.is_empty() && !other_adapter {
+        tokio::task::spawn_blocking(move || run_keys_helper(&["import", &adapter_address.to_string()], Some(keys))).await
+            .map_err(|_| OverskrideError::KeysHelperFailed)??;
+
+        // the helper restarted bluetoothd so it loads the keys, everything else has to wait for it to be back
+        wait_for_adapter(&adapter_name).await;
+    }
+
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let known = adapter.device_addresses().await?;
+    let mut restored = 0;
+
+    for backup in devices.iter().filter(|backup| known.contains(&backup.address)) {
+        let device = adapter.device(backup.address)?;
+
+        if !backup.alias.is_empty() && backup.alias != backup.name {
+            device.set_alias(backup.alias.clone()).await?;
+        }
+        device.set_trusted(backup.trusted).await?;
+        device.set_blocked(backup.blocked).await?;
+
+        restored += 1;
+    }
+
+    // the devices' own settings are restored whether bluez knows them yet or not, they apply once they're paired again
+    let mut prioritized = devices.iter()
+        .filter_map(|device| device.audio_priority.map(|rank| (rank, device.address)))
+        .collect::<Vec<(usize, bluer::Address)>>();
+    prioritized.sort();
+
+    // the privileged settings were left out of the backup, the ones set here already stay as they are
+    for device in devices.iter().filter(|device| device.settings != DeviceSettings::default()) {
+        let current = device_store::get(device.address);
+        device_store::set(device.address, DeviceSettings {
+            auto_accept_files: current.auto_accept_files,
+            proximity_unlock: current.proximity_unlock,
+            proximity_away_command: current.proximity_away_command,
+            proximity_back_command: current.proximity_back_command,
+            ..device.settings.clone()
+        });
+    }
+
+    let mut props = OVERSKRIDE_PROPS.lock().unwrap();
+    for device in devices.iter().filter(|device| device.keep_connected) {
+        if !props.keep_connected.contains(&device.address) {
+            props.keep_connected.push(device.address);
+        }
+    }
+    for (_, address) in prioritized {
+        if !props.audio_priority.contains(&address) {
+            props.audio_priority.push(address);
+        }
+    }
+
+    event_log::info(Source::App, "devices restored").field("restored", restored).field("devices", devices.len()).field("file", path.display()).record();
+    Ok((restored, keys_skipped))
+}
+
+/// gives bluetoothd some time to come back after the keys helper restarted it, and the adapter to show up again
+pub async fn wait_for_adapter(adapter_name: &str) {
+    for _ in 0..15 {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        if let Ok(session) = bluer::Session::new().await {
+            if let Ok(adapter) = session.adapter(adapter_name) {
+                if adapter.address().await.is_ok() {
+                    return;
+                }
+            }
+        }
+    }
+
+    event_log::warning(Source::Adapter, "adapter didn't come back after restarting bluetoothd").field("adapter", adapter_name).record();
+}
+
+fn write_backup(path: &Path, adapter_address: bluer::Address, devices: &[DeviceBackup]) -> Result<(), OverskrideError> {
+    let key_file = glib::KeyFile::new();
+    key_file.set_integer(BACKUP_GROUP, "Version", BACKUP_VERSION);
+    key_file.set_string(BACKUP_GROUP, "Adapter", &adapter_address.to_string());
+
+    for device in devices {
+        let group = DEVICE_GROUP_PREFIX.to_string() + &device.address.to_string();
+
+        key_file.set_string(&group, "Name", &device.name);
+        key_file.set_string(&group, "Alias", &device.alias);
+        key_file.set_boolean(&group, "Trusted", device.trusted);
+        key_file.set_boolean(&group, "Blocked", device.blocked);
+        key_file.set_boolean(&group, "KeepConnected", device.keep_connected);
+        if let Some(rank) = device.audio_priority {
+            key_file.set_integer(&group, "AudioPriority", rank as i32);
+        }
+        device.settings.write_to(&key_file, &group);
+        if let Some(keys) = &device.keys {
+            key_file.set_string(&group, "Keys", keys);
+        }
+    }
+
+    // the keys in it are as good as the pairing itself, so only the user gets to read it
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .and_then(|mut file| file.write_all(key_file.to_data().as_bytes()))
+        .map_err(|err| {
+            event_log::warning(Source::App, "cannot write the backup").field("error", &err).record();
+            OverskrideError::BackupUnwritable
+        })
+}
+
+/// reads the devices in the backup, with the adapter it was made on
+fn read_backup(path: &Path) -> Result<(Option<bluer::Address>, Vec<DeviceBackup>), OverskrideError> {
+    let key_file = glib::KeyFile::new();
+    if let Err(err) = key_file.load_from_file(path, glib::KeyFileFlags::NONE) {
+        event_log::warning(Source::App, "cannot read the backup").field("error", &err).record();
+        return Err(OverskrideError::BackupUnreadable);
+    }
+
+    let Ok(version) = key_file.integer(BACKUP_GROUP, "Version") else {
+        return Err(OverskrideError::BackupUnreadable);
+    };
+    if version > BACKUP_VERSION {
+        return Err(OverskrideError::BackupNewerVersion);
+    }
+
+    let devices = key_file.groups().iter()
+        .filter_map(|group| {
+            let group = group.as_str();
+            let address = group.strip_prefix(DEVICE_GROUP_PREFIX)?.parse::<bluer::Address>().ok()?;
+
+            Some(DeviceBackup {
+                address,
+                name: key_file.string(group, "Name").map(|name| name.to_string()).unwrap_or_default(),
+                alias: key_file.string(group, "Alias").map(|alias| alias.to_string()).unwrap_or_default(),
+                trusted: key_file.boolean(group, "Trusted").unwrap_or(false),
+                blocked: key_file.boolean(group, "Blocked").unwrap_or(false),
+                keep_connected: key_file.boolean(group, "KeepConnected").unwrap_or(false),
+                audio_priority: key_file.integer(group, "AudioPriority").ok().and_then(|rank| usize::try_from(rank).ok()),
+                // a backup can come from anyone, so it doesn't get to run commands, unlock the session or take files unasked
+                settings: DeviceSettings::read_from(&key_file, group).without_privileged(),
+                keys: key_file.string(group, "Keys").ok().map(|keys| keys.to_string()).filter(|keys| !keys.is_empty()),
+            })
+        })
+        .collect();
+
+    let adapter = key_file.string(BACKUP_GROUP, "Adapter").ok().and_then(|adapter| adapter.parse::<bluer::Address>().ok());
+
+    Ok((adapter, devices))
+}
+
+/// Runs the keys helper through pkexec with `args`, feeding it `input`, returning what it printed.
+/// This waits for the user to answer the password prompt, so it blocks.
+pub fn run_keys_helper(args: &[&str], input: Option<String>) -> Result<String, OverskrideError> {
+    let helper = Path::new(LIBEXECDIR).join(KEYS_HELPER);
+
+    let mut child = Command::new("pkexec")
+        .arg(&helper)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            event_log::warning(Source::App, "cannot run the keys helper through pkexec").field("helper", helper.display()).field("error", &err).record();
+            OverskrideError::KeysHelperFailed
+        })?;
+
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        stdin.write_all(input.as_bytes()).map_err(|_| OverskrideError::KeysHelperFailed)?;
+    }
+
+    let output = child.wait_with_output().map_err(|_| OverskrideError::KeysHelperFailed)?;
+
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        // pkexec's way of saying the password prompt was dismissed or failed
+        Some(126) | Some(127) => Err(OverskrideError::KeysNotAuthorized),
+        code => {
+            event_log::warning(Source::App, "keys helper failed").field("status", format!("{:?}", code)).record();
+            Err(OverskrideError::KeysHelperFailed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("overskride-{}-{}.backup", name, std::process::id()))
+    }
+
+    #[test]
+    fn written_backups_read_back_and_stay_private() {
+        let path = temporary_path("round-trip");
+        let adapter_address = bluer::Address::new([0x00, 0x1a, 0x7d, 0xda, 0x71, 0x13]);
+        let device = DeviceBackup {
+            address: bluer::Address::new([0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]),
+            name: "Buds".to_string(),
+            alias: "My Buds".to_string(),
+            trusted: true,
+            blocked: false,
+            keep_connected: true,
+            audio_priority: Some(2),
+            settings: DeviceSettings { notes: "the white ones".to_string(), battery_alert: 15, ..DeviceSettings::default() },
+            keys: Some("W0xpbmtLZXld".to_string()),
+        };
+
+        write_backup(&path, adapter_address, &[device.clone()]).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        let (adapter, devices) = read_backup(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(adapter, Some(adapter_address));
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].address, device.address);
+        assert_eq!(devices[0].alias, device.alias);
+        assert!(devices[0].trusted && devices[0].keep_connected);
+        assert_eq!(devices[0].audio_priority, Some(2));
+        assert_eq!(devices[0].settings, device.settings);
+        assert_eq!(devices[0].keys, device.keys);
+    }
+
+    #[test]
+    fn backups_cant_bring_privileged_settings() {
+        let path = temporary_path("privileged");
+        std::fs::write(&path, "[Overskride Backup]\nVersion=1\nAdapter=00:1A:7D:DA:71:13\n\n\
+            [Device AA:BB:CC:DD:EE:FF]\nAlias=Phone\nNotes=kitchen\nAutoAcceptFiles=true\nProximity=true\n\
+            ProximityUnlock=true\nProximityAwayCommand=touch /tmp/away\nProximityBackCommand=touch /tmp/back\n").unwrap();
+
+        let (_, devices) = read_backup(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let settings = &devices[0].settings;
+
+        assert_eq!(settings.notes, "kitchen");
+        assert!(settings.proximity);
+        assert!(!settings.auto_accept_files);
+        assert!(!settings.proximity_unlock);
+        assert!(settings.proximity_away_command.is_empty());
+        assert!(settings.proximity_back_command.is_empty());
+    }
+
+    #[test]
+    fn unreadable_and_newer_backups_are_refused() {
+        let path = temporary_path("refused");
+
+        std::fs::write(&path, "[Something Else]\nKey=value\n").unwrap();
+        assert_eq!(read_backup(&path).err(), Some(OverskrideError::BackupUnreadable));
+
+        std::fs::write(&path, format!("[Overskride Backup]\nVersion={}\n", BACKUP_VERSION + 1)).unwrap();
+        assert_eq!(read_backup(&path).err(), Some(OverskrideError::BackupNewerVersion));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
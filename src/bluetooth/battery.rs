@@ -6,6 +6,7 @@ use dbus::arg;
 use dbus::blocking;
 use futures::executor::block_on;
 use crate::message::Message;
+use crate::event_log::{self, Source};
 
 pub static mut CANCEL_BATTERY_CHECK: bool = false;
 static mut LAST_ADDRESS: String = String::new();
@@ -39,7 +40,7 @@ fn handle_properties_updated(sender: Sender<Message>) {
 pub fn get_battery_for_device(address: String, adapter: String, sender: Sender<Message>) {
     unsafe {
         if address == LAST_ADDRESS {
-            println!("stopped checking battery, last addressed matched");
+            event_log::debug(Source::Device, "already checking the battery").field("device", &address).record();
             return;
         }
         else {
@@ -54,7 +55,6 @@ pub fn get_battery_for_device(address: String, adapter: String, sender: Sender<M
 	unsafe {
 		PATH = path.clone();
 	}
-	// println!("full path to device is: {}", path);
 
 	let conn = Connection::new_system().unwrap();
     let proxy = conn.with_proxy("org.bluez", path, std::time::Duration::from_millis(5000));
@@ -83,7 +83,7 @@ pub fn get_battery_for_device(address: String, adapter: String, sender: Sender<M
         conn.process(std::time::Duration::from_millis(1000)).expect("cannot process battery check request");
         unsafe {
             if CANCEL_BATTERY_CHECK && address != LAST_ADDRESS {
-                println!("stopped checking battery");
+                event_log::debug(Source::Device, "stopped checking the battery").field("device", &address).record();
                 CANCEL_BATTERY_CHECK = false;
                 break;
             }
@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 use async_channel::Sender;
-use crate::{message::Message, window::ADAPTERS_LUT, agent::wait_for_dialog_exit};
+use bluer::{SessionEvent, UuidExt};
+use futures::StreamExt;
+use crate::{message::Message, window::ADAPTERS_LUT, agent::wait_for_dialog_exit, device};
 use crate::window::OVERSKRIDE_PROPS;
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
 
 /// sets the current adapter's powered state, updating the UI
 pub async fn set_adapter_powered(adapter_name: String, sender: Sender<Message>) -> bluer::Result<()> {
@@ -15,7 +19,7 @@ pub async fn set_adapter_powered(adapter_name: String, sender: Sender<Message>)
     
     if powered {
         sender.send(Message::RefreshDevicesList()).await.expect("cannot send message");
-        sender.send(Message::PopupError("br-adapter-refreshed".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+        sender.send(Message::PopupError(OverskrideError::DevicesRefreshed, adw::ToastPriority::Normal)).await.expect("cannot send message");
     }
     else {
     	sender.send(Message::SwitchActive(false, bluer::Address::any(), true)).await.expect("cannot send message");
@@ -36,8 +40,6 @@ pub async fn set_adapter_discoverable(adapter_name: String, sender: Sender<Messa
     let discoverable = adapter.is_discoverable().await?;
     sender.send(Message::SwitchAdapterDiscoverable(discoverable)).await.expect("cannot send message");
 
-    // println!("discoverable is: {}", discoverable);
-
     Ok(())
 }
 
@@ -51,7 +53,6 @@ pub async fn get_adapter_properties(adapters_hashmap: HashMap<String, String>, s
     let timeout = adapter.discoverable_timeout().await? / 60;
     
     sender.send(Message::PopulateAdapterExpander(adapters_hashmap)).await.expect("cannot send message {}");
-    //println!("sent populate adapters message");
     sender.send(Message::SwitchAdapterPowered(is_powered)).await.expect("cannot get adapter powered.");
     sender.send(Message::SwitchAdapterDiscoverable(is_discoverable)).await.expect("cannot get adapter discoverable.");
     sender.send(Message::SwitchAdapterName(alias.clone().to_string(), alias.to_string())).await.expect("cannot get adapter name.");
@@ -67,7 +68,6 @@ pub async fn set_adapter_name(alias: String, adapter_name: String, sender: Sende
     let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
 
     let old_alias = adapter.alias().await?;
-    //println!("old alias is: {}", old_alias.to_string());
 
     adapter.set_alias(alias.clone()).await?;
     
@@ -75,7 +75,7 @@ pub async fn set_adapter_name(alias: String, adapter_name: String, sender: Sende
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     
     let new_alias = adapter.alias().await?;
-    println!("new adapter alias is: {} compared to {}", new_alias, alias);
+    event_log::info(Source::Adapter, "adapter renamed").field("adapter", adapter.name()).field("alias", &new_alias).field("asked", &alias).record();
 
     // update the lut with the new info
     unsafe {
@@ -88,7 +88,6 @@ pub async fn set_adapter_name(alias: String, adapter_name: String, sender: Sende
     }
     sender.send(Message::SwitchAdapterName(new_alias, old_alias)).await.expect("cannot change adapter name.");
 
-    //println!("name is: {}", name.clone());
     Ok(())
 }
 
@@ -117,7 +116,6 @@ pub async fn populate_adapter_expander() -> bluer::Result<HashMap<String, String
        	let alias = adapter.alias().await?;
         
         alias_name_hashmap.insert(alias.clone().to_string(), name.clone().to_string());
-        //println!("adapter alias is: {}", alias)
     }
 
     unsafe {
@@ -127,6 +125,96 @@ pub async fn populate_adapter_expander() -> bluer::Result<HashMap<String, String
     Ok(alias_name_hashmap)
 }
 
+/// Watches for adapters being plugged in or removed, keeping the adapter list up to date.
+/// In "show all adapters" mode a newly plugged in adapter also starts searching for devices right away,
+/// otherwise searching resumes once the current adapter is plugged back in, or moves to another adapter when it's unplugged.
+#[tokio::main]
+pub async fn watch_adapters(sender: Sender<Message>) -> bluer::Result<()> {
+    let session = bluer::Session::new().await?;
+    let events = session.events().await?;
+    futures::pin_mut!(events);
+
+    while let Some(event) = events.next().await {
+        match event {
+            SessionEvent::AdapterAdded(name) => {
+                event_log::info(Source::Adapter, "adapter added").field("adapter", &name).record();
+                let show_all_adapters = OVERSKRIDE_PROPS.lock().unwrap().show_all_adapters;
+                let current_adapter = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+                let adapter_missing = OVERSKRIDE_PROPS.lock().unwrap().adapter_missing;
+
+                if adapter_missing {
+                    // nothing was plugged in before, so this one becomes the current adapter
+                    OVERSKRIDE_PROPS.lock().unwrap().current_adapter = name.clone();
+                    OVERSKRIDE_PROPS.lock().unwrap().adapter_missing = false;
+                    sender.send(Message::SetAdapterMissing(false)).await.expect("cannot send message");
+                }
+
+                if show_all_adapters && !adapter_missing {
+                    let sender_clone = sender.clone();
+                    let name_clone = name.clone();
+
+                    tokio::spawn(async move {
+                        if let Err(err) = device::get_devices_continuous(sender_clone, name_clone.clone()).await {
+                            event_log::warning(Source::Adapter, "stopped searching for devices").field("adapter", &name_clone).field("error", &err).record();
+                        }
+                    });
+                }
+                else if adapter_missing || name == current_adapter {
+                    // the adapter we were using came back, pick up where we left off
+                    let sender_clone = sender.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = resume_discovery(name, sender_clone).await {
+                            event_log::warning(Source::Adapter, "cannot resume discovery").field("error", &err).record();
+                        }
+                    });
+                }
+            }
+            SessionEvent::AdapterRemoved(name) => {
+                event_log::info(Source::Adapter, "adapter removed").field("adapter", &name).record();
+                sender.send(Message::RemoveAdapterDevices(name.clone())).await.expect("cannot send message");
+
+                let current_adapter = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+                if name == current_adapter {
+                    // fall back to any other adapter, or wait for one to be plugged in
+                    if let Some(other) = session.adapter_names().await?.into_iter().next() {
+                        OVERSKRIDE_PROPS.lock().unwrap().current_adapter = other;
+                        sender.send(Message::RefreshDevicesList()).await.expect("cannot send message");
+                    }
+                    else {
+                        OVERSKRIDE_PROPS.lock().unwrap().adapter_missing = true;
+                        sender.send(Message::SwitchActive(false, bluer::Address::any(), true)).await.expect("cannot send message");
+                        sender.send(Message::SetAdapterMissing(true)).await.expect("cannot send message");
+                    }
+                }
+            }
+        }
+
+        let adapters = populate_adapter_expander().await?;
+        sender.send(Message::PopulateAdapterExpander(adapters)).await.expect("cannot send message");
+    }
+
+    Ok(())
+}
+
+/// Waits for the adapter to power back on after being unblocked or plugged back in, then starts searching again.
+/// bluez powers the adapter back on by itself if it was on before, so if it stays off the user turned it off and we leave it be.
+pub async fn resume_discovery(adapter_name: String, sender: Sender<Message>) -> bluer::Result<()> {
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+
+    for _ in 0..10 {
+        if adapter.is_powered().await.unwrap_or(false) {
+            sender.send(Message::SwitchAdapterPowered(true)).await.expect("cannot send message");
+            sender.send(Message::RefreshDevicesList()).await.expect("cannot send message");
+            return Ok(());
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+
+    sender.send(Message::SwitchAdapterPowered(false)).await.expect("cannot send message");
+    Ok(())
+}
+
 /// wrapper to get the file save location from a file picker
 pub async fn get_store_location_from_dialog(sender: Sender<Message>) {
     
@@ -140,3 +228,68 @@ pub async fn get_store_location_from_dialog(sender: Sender<Message>) {
 
     sender.send(Message::SetFileStorageLocation(path)).await.expect("cannot send message");
 }
+
+/// what discovery looks for, set by the user in the discovery settings and applied every time discovery starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoverySettings {
+    /// one of "auto", "bredr" or "le"
+    pub transport: String,
+    /// the weakest signal (in dBm) still reported, 0 reports everything
+    pub rssi: i16,
+    /// the highest path loss (in dB) still reported, 0 for no limit, bluez only takes one of rssi and path loss
+    pub pathloss: u16,
+    /// only report devices advertising one of these services
+    pub uuids: Vec<String>,
+    /// report every advertisement instead of only changes
+    pub duplicate_data: bool,
+    /// only report devices whose address or name starts with this
+    pub pattern: String,
+}
+
+impl Default for DiscoverySettings {
+    fn default() -> Self {
+        DiscoverySettings {
+            transport: "auto".to_string(),
+            rssi: 0,
+            pathloss: 0,
+            uuids: vec![],
+            duplicate_data: false,
+            pattern: String::new(),
+        }
+    }
+}
+
+impl DiscoverySettings {
+    pub fn to_filter(&self) -> bluer::DiscoveryFilter {
+        // a minimum signal wins over the path loss, since bluez refuses to take both
+        let rssi = (self.rssi != 0).then_some(self.rssi);
+        let pathloss = (rssi.is_none() && self.pathloss != 0).then_some(self.pathloss);
+
+        bluer::DiscoveryFilter {
+            transport: self.transport.parse().unwrap_or(bluer::DiscoveryTransport::Auto),
+            rssi,
+            pathloss,
+            uuids: parse_uuids(&self.uuids).unwrap_or_default().into_iter().collect(),
+            duplicate_data: self.duplicate_data,
+            pattern: Some(self.pattern.clone()).filter(|pattern| !pattern.is_empty()),
+            ..Default::default()
+        }
+    }
+}
+
+/// parses service UUIDs, either in full or in their 16 bit short form like "180d"
+pub fn parse_uuids(uuids: &[String]) -> Result<Vec<bluer::Uuid>, OverskrideError> {
+    uuids
+        .iter()
+        .map(|uuid| {
+            let uuid = uuid.trim();
+            let parsed = if uuid.len() <= 4 {
+                u16::from_str_radix(uuid, 16).ok().map(bluer::Uuid::from_u16)
+            } else {
+                bluer::Uuid::parse_str(uuid).ok()
+            };
+
+            parsed.ok_or(OverskrideError::InvalidServiceUuid)
+        })
+        .collect()
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use bluer::Uuid;
+use dbus::arg::{self, PropMap};
+use dbus::blocking::{Connection, stdintf::org_freedesktop_dbus::ObjectManager};
+use dbus::Path;
+use uuid::uuid;
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
+
+/// service data every broadcast audio source (auracast) advertises, holds the broadcast id
+pub const BROADCAST_AUDIO_ANNOUNCEMENT: Uuid = uuid!("00001852-0000-1000-8000-00805f9b34fb");
+/// optional service data of public broadcasts, holds the features and metadata (program, language, etc.)
+pub const PUBLIC_BROADCAST_ANNOUNCEMENT: Uuid = uuid!("00001856-0000-1000-8000-00805f9b34fb");
+/// the advertising data type of the broadcast's human readable name
+const BROADCAST_NAME: u8 = 0x30;
+/// the codec id of LC3 in a transport, the only codec LE Audio requires
+const LC3_CODEC: u8 = 0x06;
+
+const MEDIA_TRANSPORT_INTERFACE: &str = "org.bluez.MediaTransport1";
+
+/// what each broadcast last announced, with whether it was joined when its transports were looked at,
+/// so the transports are only looked up again when the announcement changes rather than on every advertisement
+static ANNOUNCED: Mutex<Option<HashMap<bluer::Address, Announced>>> = Mutex::new(None);
+
+/// the announcement's service data, and whether the broadcast was joined
+type Announced = (Vec<u8>, bool);
+
+/// a broadcast audio source found while scanning, with whatever metadata it shares
+#[derive(Debug, Clone, Default)]
+pub struct BroadcastSource {
+    pub address: bluer::Address,
+    pub name: String,
+    /// the 24 bit id every broadcast has, stays the same even if the address changes
+    pub broadcast_id: u32,
+    pub encrypted: bool,
+    pub standard_quality: bool,
+    pub high_quality: bool,
+    pub program_info: String,
+    pub language: String,
+    pub contexts: Vec<String>,
+    pub parental_rating: String,
+    pub rssi: Option<i16>,
+    /// whether we are currently listening to this broadcast
+    pub joined: bool,
+}
+
+impl BroadcastSource {
+    /// a short one line summary of this broadcast, for the row subtitle
+    pub fn summary(&self) -> String {
+        let mut parts = vec![];
+
+        if !self.program_info.is_empty() {
+            parts.push(self.program_info.clone());
+        }
+        if !self.language.is_empty() {
+            parts.push(self.language.clone());
+        }
+        if self.high_quality {
+            parts.push("High Quality".to_string());
+        }
+        else if self.standard_quality {
+            parts.push("Standard Quality".to_string());
+        }
+        if self.encrypted {
+            parts.push("Encrypted".to_string());
+        }
+
+        if parts.is_empty() {
+            "Audio Broadcast".to_string()
+        }
+        else {
+            parts.join(" · ")
+        }
+    }
+}
+
+/// the codec settings of an LC3 stream, any of these can be missing if the device didn't set them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lc3Configuration {
+    pub sampling_frequency: Option<u32>,
+    pub frame_duration: Option<f32>,
+    pub octets_per_frame: Option<u16>,
+    pub channel_allocation: Option<u32>,
+    pub frames_per_sdu: Option<u8>,
+}
+
+impl Lc3Configuration {
+    /// describes this configuration in a human readable way, like "48 kHz · 10 ms · 96 kbps · Front Left"
+    pub fn describe(&self) -> String {
+        let mut parts = vec![];
+
+        if let Some(frequency) = self.sampling_frequency {
+            parts.push(format!("{} kHz", frequency as f32 / 1000.0));
+        }
+        if let Some(duration) = self.frame_duration {
+            parts.push(format!("{} ms", duration));
+        }
+        // bitrate is just how many bytes are sent every frame
+        if let (Some(octets), Some(duration)) = (self.octets_per_frame, self.frame_duration) {
+            let blocks = self.frames_per_sdu.unwrap_or(1).max(1) as f32;
+            parts.push(format!("{:.0} kbps", octets as f32 * 8.0 * blocks / duration));
+        }
+        if let Some(allocation) = self.channel_allocation {
+            parts.push(describe_channel_allocation(allocation));
+        }
+
+        if parts.is_empty() {
+            "Unknown Configuration".to_string()
+        }
+        else {
+            parts.join(" · ")
+        }
+    }
+}
+
+/// splits data in the LTV (length, type, value) format used all over LE Audio into a type => value map
+fn parse_ltv(data: &[u8]) -> HashMap<u8, Vec<u8>> {
+    let mut map = HashMap::new();
+    let mut index = 0;
+
+    while index < data.len() {
+        let length = data[index] as usize;
+        // the length includes the type byte, zero means padding
+        if length == 0 || index + 1 + length > data.len() {
+            break;
+        }
+
+        map.insert(data[index + 1], data[index + 2..index + 1 + length].to_vec());
+        index += 1 + length;
+    }
+
+    map
+}
+
+/// parses the codec specific configuration of an LC3 transport
+fn parse_lc3_configuration(data: &[u8]) -> Lc3Configuration {
+    let ltv = parse_ltv(data);
+
+    let sampling_frequency = ltv.get(&0x01).and_then(|value| value.first()).and_then(|frequency| match frequency {
+        0x01 => Some(8000),
+        0x02 => Some(11025),
+        0x03 => Some(16000),
+        0x04 => Some(22050),
+        0x05 => Some(24000),
+        0x06 => Some(32000),
+        0x07 => Some(44100),
+        0x08 => Some(48000),
+        0x09 => Some(88200),
+        0x0a => Some(96000),
+        0x0b => Some(176400),
+        0x0c => Some(192000),
+        0x0d => Some(384000),
+        _ => None,
+    });
+
+    let frame_duration = ltv.get(&0x02).and_then(|value| value.first()).and_then(|duration| match duration {
+        0x00 => Some(7.5),
+        0x01 => Some(10.0),
+        _ => None,
+    });
+
+    let channel_allocation = ltv.get(&0x03)
+        .filter(|value| value.len() >= 4)
+        .map(|value| u32::from_le_bytes([value[0], value[1], value[2], value[3]]));
+
+    let octets_per_frame = ltv.get(&0x04)
+        .filter(|value| value.len() >= 2)
+        .map(|value| u16::from_le_bytes([value[0], value[1]]));
+
+    let frames_per_sdu = ltv.get(&0x05).and_then(|value| value.first()).copied();
+
+    Lc3Configuration { sampling_frequency, frame_duration, octets_per_frame, channel_allocation, frames_per_sdu }
+}
+
+/// turns the audio location bitfield into readable channel names
+fn describe_channel_allocation(allocation: u32) -> String {
+    if allocation == 0 {
+        return "Mono".to_string();
+    }
+
+    let names = [
+        "Front Left", "Front Right", "Front Center", "Low Frequency", "Back Left", "Back Right",
+        "Front Left Center", "Front Right Center", "Back Center", "Low Frequency 2", "Side Left", "Side Right",
+    ];
+
+    let channels: Vec<&str> = names.iter()
+        .enumerate()
+        .filter(|(bit, _)| allocation & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+
+    if channels.is_empty() {
+        "Other Channels".to_string()
+    }
+    else {
+        channels.join(", ")
+    }
+}
+
+/// turns the streaming audio contexts bitfield into readable names
+fn describe_contexts(contexts: u16) -> Vec<String> {
+    let names = [
+        "Unspecified", "Conversational", "Media", "Game", "Instructional", "Voice Assistant",
+        "Live", "Sound Effects", "Notifications", "Ringtone", "Alerts", "Emergency Alarm",
+    ];
+
+    names.iter()
+        .enumerate()
+        .filter(|(bit, _)| contexts & (1 << bit) != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// fills in the features and metadata of a public broadcast announcement
+fn parse_public_broadcast(data: &[u8], source: &mut BroadcastSource) {
+    if data.len() < 2 {
+        return;
+    }
+
+    let features = data[0];
+    source.encrypted = features & 0b001 != 0;
+    source.standard_quality = features & 0b010 != 0;
+    source.high_quality = features & 0b100 != 0;
+
+    let metadata_length = (data[1] as usize).min(data.len() - 2);
+    let metadata = parse_ltv(&data[2..2 + metadata_length]);
+
+    if let Some(contexts) = metadata.get(&0x02).filter(|value| value.len() >= 2) {
+        source.contexts = describe_contexts(u16::from_le_bytes([contexts[0], contexts[1]]));
+    }
+    if let Some(program_info) = metadata.get(&0x03) {
+        source.program_info = String::from_utf8_lossy(program_info).to_string();
+    }
+    if let Some(language) = metadata.get(&0x04) {
+        source.language = String::from_utf8_lossy(language).to_string();
+    }
+    if let Some(rating) = metadata.get(&0x06).and_then(|value| value.first()) {
+        // 0x01 is "any age", otherwise the minimum age is the value plus 3
+        source.parental_rating = match rating & 0x0f {
+            0x00 => "Not Rated".to_string(),
+            0x01 => "Any Age".to_string(),
+            age => format!("{}+", age + 3),
+        };
+    }
+}
+
+/// checks if a device is a broadcast audio source, returning everything it tells us about the broadcast if it is
+pub async fn get_broadcast_source(device: &bluer::Device) -> bluer::Result<Option<BroadcastSource>> {
+    let service_data = device.service_data().await?.unwrap_or_default();
+
+    let Some(announcement) = service_data.get(&BROADCAST_AUDIO_ANNOUNCEMENT) else {
+        return Ok(None);
+    };
+
+    let mut source = BroadcastSource {
+        address: device.address(),
+        rssi: device.rssi().await?,
+        ..Default::default()
+    };
+
+    if announcement.len() >= 3 {
+        source.broadcast_id = u32::from_le_bytes([announcement[0], announcement[1], announcement[2], 0]);
+    }
+
+    if let Some(public) = service_data.get(&PUBLIC_BROADCAST_ANNOUNCEMENT) {
+        parse_public_broadcast(public, &mut source);
+    }
+
+    // the broadcast name is optional, fall back to whatever bluez calls the device
+    let advertising_data = device.advertising_data().await?.unwrap_or_default();
+    source.name = match advertising_data.get(&BROADCAST_NAME) {
+        Some(name) if !name.is_empty() => String::from_utf8_lossy(name).to_string(),
+        _ => device.alias().await?,
+    };
+
+    let address = device.address();
+    let mut announced = announcement.clone();
+    announced.extend(service_data.get(&PUBLIC_BROADCAST_ANNOUNCEMENT).cloned().unwrap_or_default());
+
+    let known = ANNOUNCED.lock().unwrap()
+        .as_ref()
+        .and_then(|sources| sources.get(&address))
+        .filter(|(data, _)| *data == announced)
+        .map(|(_, joined)| *joined);
+
+    source.joined = match known {
+        Some(joined) => joined,
+        None => {
+            let adapter_name = device.adapter_name().to_string();
+            let joined = tokio::task::spawn_blocking(move || {
+                get_transports(address, &adapter_name)
+                    .iter()
+                    .any(|(_, properties)| {
+                        let state = arg::prop_cast::<String>(properties, "State").cloned().unwrap_or_default();
+                        state == "active" || state == "pending"
+                    })
+            }).await.unwrap_or(false);
+
+            ANNOUNCED.lock().unwrap().get_or_insert_with(HashMap::new).insert(address, (announced, joined));
+            joined
+        }
+    };
+
+    Ok(Some(source))
+}
+
+/// forgets what a broadcast announced, for when it went away
+pub fn forget(address: bluer::Address) {
+    if let Some(sources) = ANNOUNCED.lock().unwrap().as_mut() {
+        sources.remove(&address);
+    }
+}
+
+/// gets every media transport belonging to a device, bluez makes one per stream (or BIS in case of broadcasts)
+pub fn get_transports(address: bluer::Address, adapter_name: &str) -> Vec<(Path<'static>, PropMap)> {
+    let Ok(conn) = Connection::new_system() else {
+        return vec![];
+    };
+
+    let device_path = "/org/bluez/".to_string() + adapter_name + "/dev_" + &address.to_string().replace(':', "_");
+    let proxy = conn.with_proxy("org.bluez", "/", Duration::from_millis(5000));
+
+    let Ok(objects) = proxy.get_managed_objects() else {
+        return vec![];
+    };
+
+    objects.into_iter()
+        .filter_map(|(path, mut interfaces)| interfaces.remove(MEDIA_TRANSPORT_INTERFACE).map(|properties| (path, properties)))
+        .filter(|(_, properties)| {
+            arg::prop_cast::<Path<'static>>(properties, "Device").map(|device| device.to_string()) == Some(device_path.clone())
+        })
+        .collect()
+}
+
+/// starts or stops listening to a broadcast, selecting its streams lets the sound server pick them up
+pub fn set_broadcast_joined(address: bluer::Address, adapter_name: String, join: bool) -> Result<(), OverskrideError> {
+    let transports = get_transports(address, &adapter_name);
+
+    // bluez only creates the transports once it has read the broadcast's stream info, which takes a bit after it's found
+    if transports.is_empty() {
+        return Err(OverskrideError::BroadcastNoStreams);
+    }
+
+    let conn = Connection::new_system().map_err(|_| OverskrideError::BroadcastJoinFailed)?;
+    let method = if join { "Select" } else { "Unselect" };
+
+    for (path, _) in transports {
+        let proxy = conn.with_proxy("org.bluez", path.clone(), Duration::from_millis(5000));
+        let result: Result<(), dbus::Error> = proxy.method_call(MEDIA_TRANSPORT_INTERFACE, method, ());
+
+        if let Err(err) = result {
+            event_log::warning(Source::Device, "cannot join or leave the broadcast").field("method", method).field("transport", &path).field("error", &err).record();
+            return Err(OverskrideError::BroadcastJoinFailed);
+        }
+    }
+
+    if let Some((_, joined)) = ANNOUNCED.lock().unwrap().as_mut().and_then(|sources| sources.get_mut(&address)) {
+        *joined = join;
+    }
+
+    Ok(())
+}
+
+/// gets the LC3 configuration of every LE Audio stream a connected device has, empty if it isn't using LE Audio
+pub fn get_lc3_configurations(address: bluer::Address, adapter_name: String) -> Vec<Lc3Configuration> {
+    let mut configurations: Vec<Lc3Configuration> = get_transports(address, &adapter_name)
+        .iter()
+        .filter(|(_, properties)| arg::prop_cast::<u8>(properties, "Codec") == Some(&LC3_CODEC))
+        .filter_map(|(_, properties)| arg::prop_cast::<Vec<u8>>(properties, "Configuration"))
+        .map(|configuration| parse_lc3_configuration(configuration))
+        .collect();
+
+    // a sink and source usually share the same settings, no need to show them twice
+    configurations.dedup();
+    configurations
+}
+
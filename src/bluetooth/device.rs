@@ -1,10 +1,12 @@
 use async_channel::Sender;
 use bluer::{AdapterEvent, AdapterProperty, DeviceEvent, DeviceProperty};
 use futures::{pin_mut, stream::SelectAll, StreamExt};
+use std::collections::HashSet;
 use tokio_util::sync::CancellationToken;
 use uuid::uuid;
 
-use crate::{message::Message, window::{DEVICES_LUT}, agent::wait_for_dialog_exit, audio_profiles::AudioProfiles, battery::CANCEL_BATTERY_CHECK, services};
+use crate::{message::Message, window::{self, listed_name}, agent::wait_for_dialog_exit, audio_profiles::{self, AudioProfiles}, audio_sink, battery::CANCEL_BATTERY_CHECK, broadcast, services, identity::{self, DeviceIdentity}, advertisement, reconnect, audio_priority, profiles, device_store, proximity::{self, ProximityChange}, rssi::{self, DistanceEstimate}, event_log::{self, Source}};
+use crate::error::OverskrideError;
 use crate::window::OVERSKRIDE_PROPS;
 
 static mut CANCELLATION_TOKEN: Option<CancellationToken> = None;
@@ -16,34 +18,53 @@ pub async fn set_device_active(address: bluer::Address, sender: Sender<Message>,
     let address_string = address.clone().to_string();
     let adapter_string = adapter_name.clone();
 
-    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
-	let device = adapter.device(address)?;
+    let session = bluer::Session::new().await?;
+    let adapter = session.adapter(adapter_name.as_str())?;
 
 	sender.send(Message::SwitchActiveSpinner(true)).await.expect("cannot set spinner to show.");
 
+    // the chosen adapter may have never seen this device (when using "connect using"), in which case it connects to it directly,
+    // trusting it if the adapter that found it does, and then pairs like any other device
+    let known = adapter.device_addresses().await?.contains(&address);
+	let device = if known {
+        adapter.device(address)?
+    }
+    else {
+        let (device, trusted) = connect_using_adapter(&session, &adapter, address).await?;
+        if trusted {
+            device.set_trusted(true).await?;
+        }
+
+        event_log::info(Source::App, "connected using another adapter").device(address).field("adapter", &adapter_name).record();
+        device
+    };
+
     let state = device.is_connected().await?;
 
-    if state {
+    if known && state {
+        reconnect::user_disconnected(address);
         device.disconnect().await?;
     }
     else if !device.is_paired().await? {
 		// let agent = register_agent(&current_session, true, true).await?;
-		// println!("agent is: {:?}\n", agent);
 
    		device.pair().await?;
 
-   		device.connect().await?;
-        device.connect().await?;
+        // connecting using another adapter connected it already
+        if known {
+   		    device.connect().await?;
+            device.connect().await?;
+        }
 		// drop(agent);
    	}
-   	else {
+   	else if known {
         device.connect().await?;
    	}
 
        let updated_state = device.is_connected().await?;
 
 
-    println!("set state {} for device {}\n", updated_state, device.address());
+    event_log::info(Source::App, "connected switch toggled").device(address).field("connected", updated_state).record();
 	sender.send(Message::SwitchActiveSpinner(false)).await.expect("cannot set spinner to show.");
     sender.send(Message::SwitchActive(updated_state, address, true)).await.expect("cannot send message");
 	sender.send(Message::InvalidateSort()).await.expect("cannot set device name.");
@@ -82,6 +103,9 @@ pub async fn set_device_active(address: bluer::Address, sender: Sender<Message>,
         sender.send(Message::SwitchAudioProfileExpanded(false)).await.expect("cannot send message");
     }
 
+    send_lc3_configuration(address, adapter_name.clone(), sender.clone()).await;
+    send_profiles(address, adapter_name.clone(), sender.clone()).await;
+
 	if let Ok(()) = has_service(uuid!("00001105-0000-1000-8000-00805f9b34fb"), device).await {
     	sender.send(Message::SwitchHasObexService(true)).await.expect("cannot send message");
         sender.send(Message::SwitchSendFileActive(updated_state)).await.expect("cannot send message");
@@ -108,7 +132,6 @@ pub async fn set_device_blocked(address: bluer::Address, sender: Sender<Message>
 
 	sender.send(Message::SwitchBlocked(blocked)).await.expect("cannot set device blocked.");
 
-    // println!("setting blocked {} for device {}", new_blocked, device.address());
     Ok(())
 }
 
@@ -123,39 +146,26 @@ pub async fn set_device_trusted(address: bluer::Address, sender: Sender<Message>
     device.set_trusted(trusted).await?;
 
     sender.send(Message::SwitchTrusted(trusted)).await.expect("cannot set device trusted.");
-    // println!("setting trusted {} for device {}", new_trusted, device.address());
 
     Ok(())
 }
 
 /// Sets the currently selected device's name, updating the entry and listboxrow accordingly.
-pub async fn set_device_name(address: bluer::Address, name: String, sender: Sender<Message>, adapter_name: String) -> bluer::Result<()> {
+pub async fn set_device_name(address: bluer::Address, name: String, sender: Sender<Message>, adapter_name: String) -> Result<(), OverskrideError> {
 	let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
 	let device = adapter.device(address)?;
-    let mut lut = unsafe {
-    	DEVICES_LUT.clone().unwrap()
-    };
-
     let set_name = name.trim().to_string();
 
-    for key in lut.keys() {
-		if let Some(pair) = lut.get_key_value(key) {
-			if pair.1.trim() == set_name && pair.0 != &address {
-                sender.send(Message::SetNameValid(false)).await.expect("cannot send message");
-				return Err(bluer::Error { kind: bluer::ErrorKind::AlreadyExists, message: "device-name-exists".to_string() });
-			}
-		}
-	}
+    if window::is_name_taken(&adapter_name, address, &set_name) {
+        sender.send(Message::SetNameValid(false)).await.expect("cannot send message");
+        return Err(OverskrideError::DeviceNameExists);
+    }
 
 
     device.set_alias(set_name).await?;
     let current_alias = device.alias().await?;
 
-    unsafe {
-        lut.remove(&address);
-        lut.insert(address, current_alias.clone());
-        DEVICES_LUT = Some(lut);
-    }
+    window::remember_device(&adapter_name, address, current_alias.clone());
 
 	sender.send(Message::SwitchName(current_alias, None, address)).await.expect("cannot set device name.");
  	sender.send(Message::SetNameValid(true)).await.expect("cannot send message");
@@ -177,19 +187,16 @@ pub async fn get_device_properties(address: bluer::Address, sender: Sender<Messa
     let is_blocked = device.is_blocked().await?;
     let is_trusted = device.is_trusted().await?;
     let alias = device.alias().await?;
-    let icon_name = match device.icon().await? {
-        Some(icon) => {
-            icon
-        },
-        None => {
-            "image-missing-symbolic".to_string()
-        },
-    };
+    let icon_name = get_device_icon(&device).await?;
 
     sender.send(Message::SwitchPage(Some(alias), Some(icon_name))).await.expect("cannot set device alias and icon in page.");
     sender.send(Message::SwitchActive(is_active, address, true)).await.expect("cannot set device active in page.");
     sender.send(Message::SwitchBlocked(is_blocked)).await.expect("cannot set device blocked in page.");
     sender.send(Message::SwitchTrusted(is_trusted)).await.expect("cannot set device trusted in page.");
+    sender.send(Message::SwitchKeepConnected(reconnect::is_kept_connected(address))).await.expect("cannot send message");
+    let is_audio = audio_priority::is_audio_device(&device.uuids().await?.unwrap_or_default());
+    sender.send(Message::SwitchAudioPriority(is_audio, audio_priority::is_prioritized(address))).await.expect("cannot send message");
+    sender.send(Message::SwitchDeviceSettings(device_store::get(address))).await.expect("cannot send message");
    	sender.send(Message::SetNameValid(true)).await.expect("cannot send message");
     sender.send(Message::SwitchAudioProfileExpanded(false)).await.expect("cannot send message");
     sender.send(Message::SwitchAudioProfilesList(false)).await.expect("cannot send message");
@@ -223,6 +230,13 @@ pub async fn get_device_properties(address: bluer::Address, sender: Sender<Messa
         sender.send(Message::SwitchAudioProfileExpanded(false)).await.expect("cannot send message");
     }
 
+    if let Ok(adapters) = crate::bluetooth_settings::populate_adapter_expander().await {
+        sender.send(Message::SetConnectUsing(adapters, adapter_name.clone())).await.expect("cannot send message");
+    }
+
+    send_lc3_configuration(address, adapter_name.clone(), sender.clone()).await;
+    send_profiles(address, adapter_name.clone(), sender.clone()).await;
+
 	if let Ok(()) = has_service(uuid!("00001105-0000-1000-8000-00805f9b34fb"), device).await {
         sender.send(Message::SwitchHasObexService(true)).await.expect("cannot send message");
         sender.send(Message::SwitchSendFileActive(is_active)).await.expect("cannot send message");
@@ -232,11 +246,80 @@ pub async fn get_device_properties(address: bluer::Address, sender: Sender<Messa
         sender.send(Message::SwitchSendFileActive(false)).await.expect("cannot send message");
     }
 
-    // println!("the devices properties have been gotten with state: {}", is_active);
-
     Ok(())
 }
 
+/// the icon of the device, the one it was given in its settings if there is one
+pub async fn get_device_icon(device: &bluer::Device) -> bluer::Result<String> {
+    let icon_name = match device.icon().await? {
+        Some(icon) => {
+            icon
+        },
+        // bluez only knows a few classes and appearances, we may know this one
+        None => {
+            identity::get_device_identity(device).await?.icon.unwrap_or("image-missing".to_string())
+        },
+    };
+
+    Ok(device_store::icon_for(device.address(), icon_name))
+}
+
+/// sends the LC3 configuration of every LE Audio stream this device has to the UI, nothing is shown for classic audio devices
+async fn send_lc3_configuration(address: bluer::Address, adapter_name: String, sender: Sender<Message>) {
+    let configurations = tokio::task::spawn_blocking(move || broadcast::get_lc3_configurations(address, adapter_name))
+        .await
+        .unwrap_or_default();
+
+    let descriptions = configurations.iter().map(|configuration| configuration.describe()).collect();
+    sender.send(Message::SetLc3Configuration(descriptions)).await.expect("cannot send message");
+}
+
+/// sends the profiles this device supports to the UI, with which of them are connected
+async fn send_profiles(address: bluer::Address, adapter_name: String, sender: Sender<Message>) {
+    let device_info = async {
+        let device = bluer::Session::new().await?.adapter(adapter_name.as_str())?.device(address)?;
+        bluer::Result::Ok((device.uuids().await?.unwrap_or_default(), device.is_connected().await?))
+    };
+
+    let Ok((uuids, connected)) = device_info.await else {
+        sender.send(Message::PopulateProfilesList(vec![])).await.expect("cannot send message");
+        return;
+    };
+
+    let states = tokio::task::spawn_blocking(move || profiles::get_profile_states(address, adapter_name, &uuids, connected))
+        .await
+        .unwrap_or_default();
+
+    sender.send(Message::PopulateProfilesList(states)).await.expect("cannot send message");
+}
+
+/// Connects or disconnects a single profile of the device, leaving its other profiles alone.
+/// Connecting a profile of a disconnected device connects the device too.
+pub async fn set_profile_active(address: bluer::Address, uuid: bluer::Uuid, active: bool, sender: Sender<Message>, adapter_name: String) -> bluer::Result<()> {
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let device = adapter.device(address)?;
+
+    let result = if active {
+        device.connect_profile(&uuid).await
+    }
+    else {
+        let result = device.disconnect_profile(&uuid).await;
+
+        // disconnecting the last profile drops the device, which was wanted
+        if !device.is_connected().await? {
+            reconnect::user_disconnected(address);
+        }
+
+        result
+    };
+
+    // the sound server takes a moment to notice audio profiles coming and going
+    tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
+    send_profiles(address, adapter_name, sender).await;
+
+    result
+}
+
 pub async fn remove_device(address: bluer::Address, sender: Sender<Message>, adapter_name: String) -> bluer::Result<()> {
 	let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
 	let device = adapter.device(address)?;
@@ -255,18 +338,14 @@ pub async fn remove_device(address: bluer::Address, sender: Sender<Message>, ada
     let confirmed = OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization;
 
     if confirmed {
-        println!("removing device...");
+        event_log::info(Source::App, "removing device").device(address).record();
         let name = device.alias().await?;
+        // removing disconnects it, which isn't something to undo
+        reconnect::user_disconnected(address);
         adapter.remove_device(address).await?;
-        unsafe {
-            let mut devices_lut = DEVICES_LUT.clone().unwrap();
-            if devices_lut.contains_key(&address) {
-                devices_lut.remove(&address);
-                DEVICES_LUT = Some(devices_lut);
-            }
-        }
+        window::forget_device(&adapter_name, address);
 
-        sender.send(Message::RemoveDevice(name, address)).await.expect("can't send message");
+        sender.send(Message::RemoveDevice(name, address, adapter_name)).await.expect("can't send message");
         sender.send(Message::UpdateListBoxImage()).await.expect("can't send message");
     }
 
@@ -281,6 +360,117 @@ pub async fn has_service(service: bluer::Uuid, device: bluer::Device) -> bluer::
     Err(bluer::Error { kind: bluer::ErrorKind::DoesNotExist, message: "wanted service doesn't exist.".to_string()})
 }
 
+/// connects to a device through an adapter that doesn't know about it yet, using the address type
+/// whichever adapter found it knows it by (LE devices can't be reached as BR/EDR and vice versa).
+/// also returns whether that adapter trusts it
+async fn connect_using_adapter(session: &bluer::Session, adapter: &bluer::Adapter, address: bluer::Address) -> bluer::Result<(bluer::Device, bool)> {
+    let mut address_type = bluer::AddressType::BrEdr;
+    let mut trusted = false;
+
+    for name in session.adapter_names().await? {
+        let other_adapter = session.adapter(&name)?;
+
+        if other_adapter.device_addresses().await?.contains(&address) {
+            let other_device = other_adapter.device(address)?;
+            address_type = other_device.address_type().await?;
+            trusted = other_device.is_trusted().await?;
+            break;
+        }
+    }
+
+    Ok((adapter.connect_device(address, address_type).await?, trusted))
+}
+
+/// reconnects a device in the background if it's one to keep connected
+fn spawn_reconnect(address: bluer::Address, adapter_name: String, sender: Sender<Message>) {
+    if !reconnect::is_kept_connected(address) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(err) = reconnect::keep_connected(address, adapter_name, sender).await {
+            event_log::warning(Source::App, "cannot reconnect").device(address).field("error", format!("{:?}", err)).record();
+        }
+    });
+}
+
+/// switches audio over to a prioritized device that just came in range, if it's more important than the connected one
+fn spawn_audio_switch(address: bluer::Address, adapter_name: String, sender: Sender<Message>) {
+    if !audio_priority::is_prioritized(address) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(err) = audio_priority::on_device_available(address, adapter_name, sender).await {
+            event_log::warning(Source::App, "cannot switch audio").device(address).field("error", format!("{:?}", err)).record();
+        }
+    });
+}
+
+/// connects a device that's set to auto connect, once per time it shows up
+fn spawn_auto_connect(address: bluer::Address, adapter_name: String, sender: Sender<Message>) {
+    if !device_store::should_auto_connect(address) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let current_address = OVERSKRIDE_PROPS.lock().unwrap().address;
+
+        // the shown device goes through the connected switch so the page follows along
+        let result = if address == current_address {
+            set_device_active(address, sender, adapter_name).await
+        }
+        else {
+            let connect = async {
+                let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+                adapter.device(address)?.connect().await
+            };
+            connect.await
+        };
+
+        if let Err(err) = result {
+            event_log::warning(Source::App, "cannot auto connect").device(address).field("error", format!("{:?}", err)).record();
+        }
+    });
+}
+
+/// switches a device that just connected to its preferred audio profile, if it has one
+fn spawn_audio_profile(address: bluer::Address) {
+    let profile = device_store::get(address).audio_profile;
+    if profile.is_empty() {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        // the sound server needs a moment to add the device's card
+        std::thread::sleep(std::time::Duration::from_secs(2));
+        audio_profiles::device_set_profile(address.to_string(), profile);
+    });
+}
+
+/// records an event of the adapter in the event log
+fn log_adapter_event(event: &AdapterEvent) {
+    let entry = match event {
+        AdapterEvent::DeviceAdded(address) => event_log::info(Source::Adapter, "device added").device(*address),
+        AdapterEvent::DeviceRemoved(address) => event_log::info(Source::Adapter, "device removed").device(*address),
+        AdapterEvent::PropertyChanged(property) => event_log::info(Source::Adapter, "property changed").field("property", format!("{:?}", property)),
+    };
+
+    entry.record();
+}
+
+/// records an event of a device in the event log, the ones coming in with every advertisement as debug
+fn log_device_event(address: bluer::Address, property: &DeviceProperty) {
+    let entry = match property {
+        DeviceProperty::Rssi(_) | DeviceProperty::TxPower(_) | DeviceProperty::ManufacturerData(_) | DeviceProperty::ServiceData(_) | DeviceProperty::AdvertisingData(_) => {
+            event_log::debug(Source::Device, "property changed")
+        },
+        _ => event_log::info(Source::Device, "property changed"),
+    };
+
+    entry.device(address).field("property", format!("{:?}", property)).record();
+}
+
 pub async fn stop_searching() {
     unsafe {
         if let Some(token) = CANCELLATION_TOKEN.clone() {
@@ -289,117 +479,208 @@ pub async fn stop_searching() {
     }
 }
 
-pub async fn get_devices_continuous(sender: Sender<Message>, adapter_name: String) -> bluer::Result<()> {
+pub async fn get_devices_continuous(sender: Sender<Message>, adapter_name: String) -> Result<(), OverskrideError> {
 	let session = bluer::Session::new().await?;
 	let adapter = &session.adapter(adapter_name.as_str())?;
 
-	let filter = bluer::DiscoveryFilter {
-        transport: bluer::DiscoveryTransport::Auto,
-        ..Default::default()
+    // broadcasts are LE only, and they keep updating their metadata so duplicate advertisements are wanted
+    let broadcast_scanning = OVERSKRIDE_PROPS.lock().unwrap().broadcast_scanning;
+	let filter = if broadcast_scanning {
+        bluer::DiscoveryFilter {
+            transport: bluer::DiscoveryTransport::Le,
+            uuids: HashSet::from([broadcast::BROADCAST_AUDIO_ANNOUNCEMENT]),
+            duplicate_data: true,
+            ..Default::default()
+        }
+    }
+    else {
+        OVERSKRIDE_PROPS.lock().unwrap().discovery_settings.to_filter()
     };
-    adapter.set_discovery_filter(filter).await?;
 
-    let device_events = adapter.discover_devices().await?;
+    // outside of a scan session discovery stays off, and only the devices that are ours get listed and tracked
+    let discovering = OVERSKRIDE_PROPS.lock().unwrap().scanning || broadcast_scanning;
+    let device_events = if discovering {
+        adapter.set_discovery_filter(filter).await?;
+        adapter.discover_devices().await?.boxed()
+    }
+    else {
+        // discovery would report the devices bluez already knows, without it they're added by hand
+        let mut own_devices = vec![];
+        for address in adapter.device_addresses().await? {
+            let device = adapter.device(address)?;
+            if device.is_paired().await? || device.is_connected().await? {
+                own_devices.push(AdapterEvent::DeviceAdded(address));
+            }
+        }
+
+        futures::stream::iter(own_devices).chain(adapter.events().await?).boxed()
+    };
     pin_mut!(device_events);
 
     let mut all_change_events = SelectAll::new();
+    // rows outlive this loop when it's restarted, but their change events don't, so they're tracked again
+    let mut tracked_devices = HashSet::new();
+    // broadcasts go in their own list, so they need to be told apart from normal devices
+    let mut broadcast_sources = HashSet::new();
+    let mut broadcast_change_events = SelectAll::new();
 
 	let sender_clone = sender.clone();
 
-    let cancellation_token = CancellationToken::new();
-    unsafe {
-        CANCELLATION_TOKEN = Some(cancellation_token.clone());
-    }
+    // when searching on every adapter at once all the loops share one token, so stopping the search stops all of them
+    let cancellation_token = unsafe {
+        match CANCELLATION_TOKEN.clone() {
+            Some(token) if !token.is_cancelled() => token,
+            _ => {
+                let token = CancellationToken::new();
+                CANCELLATION_TOKEN = Some(token.clone());
+                token
+            }
+        }
+    };
+
+    let mut proximity_check = tokio::time::interval(std::time::Duration::from_secs(5));
 
     while adapter.is_powered().await? {
         tokio::select! {
             Some(device_event) = device_events.next() => {
+                log_adapter_event(&device_event);
+
                 match device_event {
-                    AdapterEvent::DeviceAdded(addr) => {
-		                if adapter.is_powered().await? {
+                    AdapterEvent::DeviceAdded(addr) if adapter.is_powered().await? => {
 	                        let supposed_device = adapter.device(addr);
 
-                            let devices_lut = unsafe {
-                                DEVICES_LUT.clone().unwrap()
+                            let broadcast_source = match &supposed_device {
+                                Ok(device) => broadcast::get_broadcast_source(device).await.unwrap_or(None),
+                                Err(_) => None,
                             };
 
-                            if !devices_lut.contains_key(&addr) {
+                            if let Some(source) = broadcast_source {
+                                if broadcast_sources.insert(addr) {
+                                    let change_events = adapter.device(addr)?.events().await?.map(move |evt| (addr, evt));
+                                    broadcast_change_events.push(change_events);
+                                }
+
+                                sender.send(Message::AddBroadcast(source)).await.expect("cannot send message");
+                            }
+                            else if !window::is_listed(&adapter_name, addr) {
                                 if let Ok(added_device) = supposed_device {
 	                                sender.send(Message::AddRow(added_device)).await.expect("cannot send message {}");
 	                                sender.send(Message::UpdateListBoxImage()).await.expect("cannot send message {}");
-	                                //println!("supposedly sent");
-
-	                                let device = adapter.device(addr)?;
-	                                let change_events = device.events().await?.map(move |evt| (addr, evt));
-	                                all_change_events.push(change_events);
                                 }
                                 else {
-                                	println!("device isn't present, something went wrong.");
+                                	event_log::warning(Source::Adapter, "added device isn't there anymore").device(addr).record();
                                 }
                             }
                             else {
-                                println!("device already exists, not adding again.");
+                                event_log::debug(Source::Adapter, "device is listed already, not adding it again").device(addr).record();
                             }
-		                }
-                    }
-                    AdapterEvent::DeviceRemoved(addr) => {
-   		                if adapter.is_powered().await? {
-                            let mut devices_lut = unsafe {
-                                DEVICES_LUT.clone().unwrap()
-                            };
 
-                            let device_name = if devices_lut.contains_key(&addr) {
-                                let lut = devices_lut.get(&addr).unwrap().clone();
-                                unsafe {
-                                    devices_lut.remove(&addr);
-                                    DEVICES_LUT = Some(devices_lut);
+                            if !broadcast_sources.contains(&addr) && tracked_devices.insert(addr) {
+                                if let Ok(device) = adapter.device(addr) {
+                                    let change_events = device.events().await?.map(move |evt| (addr, evt));
+                                    all_change_events.push(change_events);
+
+                                    // a device we keep connected showed up, so connect to it
+                                    if device.is_paired().await? && !device.is_connected().await? {
+                                        spawn_reconnect(addr, adapter_name.clone(), sender_clone.clone());
+                                        spawn_auto_connect(addr, adapter_name.clone(), sender_clone.clone());
+
+                                        // only discovery tells us it's actually in range
+                                        if discovering && device.rssi().await?.is_some() {
+                                            spawn_audio_switch(addr, adapter_name.clone(), sender_clone.clone());
+                                        }
+                                    }
                                 }
-
-                                lut
                             }
-                            else {
-                                String::new()
-                            };
+                    }
+                    AdapterEvent::DeviceRemoved(addr) => {
+                        tracked_devices.remove(&addr);
+                        device_store::forget_auto_connect(addr);
+                        rssi::forget(addr);
+                        if let Some(change) = proximity::lost(addr) {
+                            proximity::run_actions(addr, change);
+                        }
+
+   		                if broadcast_sources.remove(&addr) {
+                            broadcast::forget(addr);
+                            sender_clone.send(Message::RemoveBroadcast(addr)).await.expect("cannot send message");
+                        }
+   		                else if adapter.is_powered().await? {
+                            let device_name = listed_name(&adapter_name, addr).unwrap_or_default();
+                            window::forget_device(&adapter_name, addr);
 
-                            sender_clone.send(Message::RemoveDevice(device_name.clone(), addr)).await.expect("cannot send message");
+                            sender_clone.send(Message::RemoveDevice(device_name.clone(), addr, adapter_name.clone())).await.expect("cannot send message");
                             sender_clone.send(Message::UpdateListBoxImage()).await.expect("cannot send message");
-                            println!("Device removed: {:?} {}\n", addr, device_name.clone());
 						}
                     },
-                    AdapterEvent::PropertyChanged(AdapterProperty::Powered(powered)) => {
+                    // when searching on every adapter, only the one the settings show updates them
+                    AdapterEvent::PropertyChanged(AdapterProperty::Powered(powered)) if is_current_adapter(&adapter_name) => {
                         tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
                         sender_clone.send(Message::SwitchAdapterPowered(powered)).await.expect("cannot send message {}");
-                        println!("powered switch to {}", powered);
                     },
-                    AdapterEvent::PropertyChanged(AdapterProperty::Discoverable(discoverable)) => {
+                    AdapterEvent::PropertyChanged(AdapterProperty::Discoverable(discoverable)) if is_current_adapter(&adapter_name) => {
                         tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
                         sender_clone.send(Message::SwitchAdapterDiscoverable(discoverable)).await.expect("cannot send message {}");
-                        println!("discoverable switch to {}", discoverable);
                     },
-                    AdapterEvent::PropertyChanged(AdapterProperty::Alias(alias)) => {
+                    AdapterEvent::PropertyChanged(AdapterProperty::Alias(alias)) if is_current_adapter(&adapter_name) => {
                     	tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
                     	sender_clone.send(Message::SwitchAdapterName(alias.clone(), alias.clone())).await.expect("cannot send message {}");
                     },
-                    event => {
-                        println!("unhandled adapter event: {:?}", event);
-                    }
+                    // already in the log, nothing else to do with it
+                    _ => {}
                 }
             }
             Some((addr, DeviceEvent::PropertyChanged(property))) = all_change_events.next() => {
+                log_device_event(addr, &property);
+
                 let current_address = OVERSKRIDE_PROPS.lock().unwrap().address;
                 match property {
                     DeviceProperty::Connected(connected) => {
                         tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
                         sender_clone.send(Message::SwitchActive(connected, addr, addr == current_address)).await.expect("cannot send message");
+
+                        if connected {
+                            reconnect::connected(addr);
+                            spawn_audio_profile(addr);
+
+                            if audio_priority::is_prioritized(addr) {
+                                let adapter_name = adapter_name.clone();
+                                tokio::spawn(async move {
+                                    if let Err(err) = audio_priority::on_device_connected(addr, adapter_name).await {
+                                        event_log::warning(Source::App, "cannot disconnect lower priority audio devices").device(addr).field("error", format!("{:?}", err)).record();
+                                    }
+                                });
+                            }
+                        }
+                        else {
+                            spawn_reconnect(addr, adapter_name.clone(), sender_clone.clone());
+                            device_store::forget_auto_connect(addr);
+                        }
+
+                        let audio_sink_mode = OVERSKRIDE_PROPS.lock().unwrap().audio_sink_mode;
+                        if audio_sink_mode {
+                            let sender = sender_clone.clone();
+                            let adapter_name = adapter_name.clone();
+
+                            tokio::spawn(async move {
+                                if let Err(err) = audio_sink::update_streaming_device(addr, connected, adapter_name, sender).await {
+                                    event_log::warning(Source::App, "cannot update the streaming device").device(addr).field("error", format!("{:?}", err)).record();
+                                }
+                            });
+                        }
                     },
                     DeviceProperty::Trusted(trusted) => {
                         let current_address = OVERSKRIDE_PROPS.lock().unwrap().address;
+                        sender_clone.send(Message::SwitchRowTrusted(addr, trusted)).await.expect("cannot send message");
 
                         if addr == current_address {
                             tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
                             sender_clone.send(Message::SwitchTrusted(trusted)).await.expect("cannot send message");
                         }
                     },
+                    DeviceProperty::Paired(paired) => {
+                        sender_clone.send(Message::SwitchRowPaired(addr, paired)).await.expect("cannot send message");
+                    },
                     DeviceProperty::Blocked(blocked) => {
                         let current_address = OVERSKRIDE_PROPS.lock().unwrap().address;
 
@@ -417,14 +698,10 @@ pub async fn get_devices_continuous(sender: Sender<Message>, adapter_name: Strin
                             sender_clone.send(Message::SwitchPage(Some(name.clone()), None)).await.expect("cannot send message");
                         }
                         else {
-                            let hashmap = unsafe {
-                            	DEVICES_LUT.clone().unwrap()
-                            };
-
-                            let empty = String::new();
-                            let old_alias = hashmap.get(&addr).unwrap_or(&empty);
+                            let old_alias = listed_name(&adapter_name, addr).unwrap_or_default();
+                            window::remember_device(&adapter_name, addr, name.clone());
 
-                            sender_clone.send(Message::SwitchName(name.clone(), Some(old_alias.to_string()), addr)).await.expect("cannot send message");
+                            sender_clone.send(Message::SwitchName(name.clone(), Some(old_alias), addr)).await.expect("cannot send message");
                         }
                     },
                     DeviceProperty::Icon(icon) => {
@@ -435,20 +712,63 @@ pub async fn get_devices_continuous(sender: Sender<Message>, adapter_name: Strin
                             sender_clone.send(Message::SwitchPage(None, Some(icon))).await.expect("cannot send message");
                         }
                     },
+                    DeviceProperty::ManufacturerData(_) | DeviceProperty::ServiceData(_) | DeviceProperty::AdvertisingData(_) => {
+                        // nameless devices can send what they are a bit after being found
+                        let device = adapter.device(addr)?;
+                        if advertisement::is_nameless(&device.alias().await?) {
+                            if let Some(advertised) = advertisement::resolve_name(&device).await? {
+                                sender_clone.send(Message::SetAdvertisedName(device, advertised)).await.expect("cannot send message");
+                            }
+                        }
+                    },
+                    DeviceProperty::BatteryPercentage(percentage) if device_store::is_battery_low(addr, percentage) => {
+                        let name = adapter.device(addr)?.alias().await?;
+                        sender_clone.send(Message::BatteryLow(name, percentage)).await.expect("cannot send message");
+                    },
                     DeviceProperty::Rssi(rssi) => {
-                       	let device = unsafe {
-                            DEVICES_LUT.clone().unwrap().get(&addr).unwrap_or(&"Unknown Device".to_string()).to_string()
-                        };
-                        sender_clone.send(Message::SwitchRssi(device, rssi as i32)).await.expect("cannot send message");
+                       	let device = listed_name(&adapter_name, addr).unwrap_or("Unknown Device".to_string());
+                        // a single sample jumps around too much, so everything goes by the filtered signal
+                        let smoothed_rssi = rssi::add_sample(addr, rssi);
+                        sender_clone.send(Message::SwitchRssi(device, smoothed_rssi as i32)).await.expect("cannot send message");
                         sender_clone.send(Message::InvalidateSort()).await.expect("cannot send message");
+
+                        if let Some(change) = proximity::update_rssi(addr, smoothed_rssi) {
+                            proximity::run_actions(addr, change);
+                        }
                     },
-                    event => {
-                        println!("unhandled device event: {:?}", event);
-                    },
+                    // already in the log, nothing else to do with it
+                    _ => {},
+                }
+            }
+            Some((addr, DeviceEvent::PropertyChanged(property))) = broadcast_change_events.next() => {
+                log_device_event(addr, &property);
+
+                // broadcasts only need their metadata and signal strength kept up to date
+                if matches!(property, DeviceProperty::ServiceData(_) | DeviceProperty::AdvertisingData(_) | DeviceProperty::Rssi(_) | DeviceProperty::Alias(_)) {
+                    if let Ok(Some(source)) = broadcast::get_broadcast_source(&adapter.device(addr)?).await {
+                        sender_clone.send(Message::AddBroadcast(source)).await.expect("cannot send message");
+                    }
+                }
+            }
+            _ = proximity_check.tick() => {
+                let mut connected = vec![];
+                for address in device_store::with_proximity() {
+                    if let Ok(device) = adapter.device(address) {
+                        if device.is_connected().await.unwrap_or(false) {
+                            connected.push((address, device.is_paired().await.unwrap_or(false)));
+                        }
+                    }
+                }
+
+                for (address, change) in proximity::sample_connected(&adapter_name, connected.clone()).await {
+                    proximity::run_actions(address, change);
+                }
+                let connected = connected.into_iter().map(|(address, _)| address).collect::<Vec<bluer::Address>>();
+                for address in proximity::check_lost(discovering, &connected) {
+                    proximity::run_actions(address, ProximityChange::Away);
                 }
             }
             _ = cancellation_token.cancelled() => {
-                // println!("exited loop from refresh");
                 break;
             }
             else => break
@@ -459,58 +779,76 @@ pub async fn get_devices_continuous(sender: Sender<Message>, adapter_name: Strin
         // }
     }
 
-    println!("exited loop");
+    event_log::debug(Source::Adapter, "stopped following events").field("adapter", &adapter_name).record();
     // drop(agent);
     if cancellation_token.is_cancelled() {
         Ok(())
     }
     else {
-        Err(bluer::Error { kind: bluer::ErrorKind::Failed, message: "Stopped searching for devices".to_string() })
+        Err(OverskrideError::SearchStopped)
     }
 }
 
-pub async fn get_more_info(address: bluer::Address, adapter_name: String) -> bluer::Result<(String, String, String, String, String, Vec<String>)> {
+/// whether the adapter is the one the settings page shows
+fn is_current_adapter(adapter_name: &str) -> bool {
+    OVERSKRIDE_PROPS.lock().unwrap().current_adapter == adapter_name
+}
+
+/// searches for devices on every adapter at once, for the "show all adapters" mode.
+/// one adapter going away (or failing) doesn't stop the others, only all of them failing counts as an error
+pub async fn get_devices_all_adapters(sender: Sender<Message>) -> Result<(), OverskrideError> {
     let session = bluer::Session::new().await?;
-	let adapter = &session.adapter(&adapter_name)?;
+    let adapter_names = session.adapter_names().await?;
 
-    let device = adapter.device(address)?;
+    let searches = adapter_names.into_iter().map(|name| get_devices_continuous(sender.clone(), name));
+    let results = futures::future::join_all(searches).await;
 
-    let name = device.alias().await?;
-    let device_type = device.icon().await?.unwrap_or("Unknown".to_string());
-    let mut services_list = vec![];
+    if results.iter().all(|result| result.is_err()) {
+        if let Some(first) = results.into_iter().next() {
+            return first;
+        }
+    }
+
+    Ok(())
+}
+
+/// gets the adapter's own address and its paired devices as (address, name, connected), connected ones first.
+/// used for picking where to send files that were opened with Overskride
+pub async fn get_paired_devices(adapter_name: String) -> bluer::Result<(bluer::Address, Vec<(bluer::Address, String, bool)>)> {
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let mut devices = vec![];
 
-    let distance = async {
-        /* factor for if indoors outside etc., between 2 and 4 */
-    	let n = 3;
-    	let measured = device.tx_power().await?;
-		let rssi  = device.rssi().await?;
+    for address in adapter.device_addresses().await? {
+        let device = adapter.device(address)?;
 
-		println!("{:?} {:?}", measured, rssi);
+        if device.is_paired().await? {
+            devices.push((address, device.alias().await?, device.is_connected().await?));
+        }
+    }
 
-		if rssi.is_none() {
-			return bluer::Result::from(Ok("Unknown".to_string()));
-		}
+    devices.sort_by(|one, two| two.2.cmp(&one.2).then(one.1.cmp(&two.1)));
 
-		// the -59 is an average fallback case (closest to current device)
-		let ratio = (measured.unwrap_or(-59) - rssi.unwrap()) as f32;
+    Ok((adapter.address().await?, devices))
+}
 
-		// basically reverse the logarithmic way or calculate TX power to get the distance
-		// it is absolute fuckery and I have no idea how the hell anyone would come up with this, but it works fairly well
-		let dist = 10f32.powf(ratio / (10.0 * n as f32));
+/// what the more info page shows: the name, address, advertised TX power, distance estimate, services and who made the device
+pub async fn get_more_info(address: bluer::Address, adapter_name: String) -> bluer::Result<(String, String, Option<i16>, Option<DistanceEstimate>, Vec<String>, DeviceIdentity)> {
+    let session = bluer::Session::new().await?;
+	let adapter = &session.adapter(&adapter_name)?;
 
-		Ok(format!("≈ {:.1$} meters", dist, 2))
+    let device = adapter.device(address)?;
 
+    let name = device.alias().await?;
+    let mut services_list = vec![];
 
-		// needs testing but this may be more accurate????
-		// var ratio = rssi*1.0/txPower;
-		// if (ratio < 1.0) {
-		// 	return Math.pow(ratio,10);
-		// }
-		// else {
-		// 	var distance =	(0.89976)*Math.pow(ratio,7.7095) + 0.111;
-		// 	return distance;
-		// }
-    }.await?;
+    // the filter only has samples of devices seen while scanning, bluez may still remember one from before
+    let tx_power = device.tx_power().await?;
+    if rssi::smoothed(address).is_none() {
+        if let Some(rssi) = device.rssi().await? {
+            rssi::add_sample(address, rssi);
+        }
+    }
+    let distance = rssi::estimate_distance(address, tx_power);
 
     for uuid in device.uuids().await?.unwrap() {
         let service = services::get_name_from_service(uuid).unwrap_or("".to_string());
@@ -520,22 +858,7 @@ pub async fn get_more_info(address: bluer::Address, adapter_name: String) -> blu
         }
     }
 
-    let mut manufacturer = String::from("Unknown");
-
-    if let Some(info) = device.manufacturer_data().await? {
-    	println!("{:?}", info);
-
-		for key in info.keys() {
-			manufacturer = match bluer::id::Manufacturer::try_from(*key) {
-				Ok(val) => {
-					val.to_string()
-				},
-				Err(_) => {
-					"Unknown".to_string()
-				}
-			};
-		}
-    }
+    let identity = identity::get_device_identity(&device).await?;
 
-    Ok((name, address.to_string(), manufacturer, device_type, distance, services_list))
+    Ok((name, address.to_string(), tx_power, distance, services_list, identity))
 }
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use gtk::glib;
+
+use crate::event_log::{self, Source};
+
+/// the icons a device can be given instead of its own, as (name shown, icon name), the first one keeping its own
+pub const ICONS: [(&str, &str); 14] = [
+    ("Its Own", ""),
+    ("Headphones", "audio-headphones"),
+    ("Headset", "audio-headset"),
+    ("Speakers", "audio-speakers"),
+    ("Phone", "phone"),
+    ("Computer", "computer"),
+    ("Keyboard", "input-keyboard"),
+    ("Mouse", "input-mouse"),
+    ("Game Controller", "input-gaming"),
+    ("Tablet", "input-tablet"),
+    ("Media Player", "multimedia-player"),
+    ("Camera", "camera-photo"),
+    ("Display", "video-display"),
+    ("Printer", "printer"),
+];
+
+/// what Overskride remembers about a device on top of what bluez does, everything off or empty by default
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceSettings {
+    /// an icon name used instead of the device's own, empty to keep its own
+    pub icon: String,
+    pub notes: String,
+    /// the sound server profile (like "a2dp-sink") switched to whenever it connects, empty to leave it be
+    pub audio_profile: String,
+    /// the battery percentage under which a notification is sent, 0 for never
+    pub battery_alert: u8,
+    /// connect to it whenever Overskride finds it, once per time it shows up
+    pub auto_connect: bool,
+    /// accept files from it without asking, trusted or not
+    pub auto_accept_files: bool,
+    /// a folder in the received files location its files go to, empty for the location itself
+    pub download_folder: String,
+    /// run the proximity actions when it goes away or comes back
+    pub proximity: bool,
+    /// the smoothed RSSI under which it counts as gone
+    pub proximity_away_rssi: i16,
+    /// the smoothed RSSI over which it counts as back
+    pub proximity_back_rssi: i16,
+    /// lock the session through logind when it goes away
+    pub proximity_lock: bool,
+    /// unlock the session through logind when it comes back
+    pub proximity_unlock: bool,
+    /// a shell command run when it goes away, empty for none
+    pub proximity_away_command: String,
+    /// a shell command run when it comes back, empty for none
+    pub proximity_back_command: String,
+    /// its smoothed RSSI at 1 m away, used to estimate its distance, 0 if it wasn't calibrated
+    pub measured_power: i16,
+}
+
+impl Default for DeviceSettings {
+    fn default() -> Self {
+        DeviceSettings {
+            icon: String::new(),
+            notes: String::new(),
+            audio_profile: String::new(),
+            battery_alert: 0,
+            auto_connect: false,
+            auto_accept_files: false,
+            download_folder: String::new(),
+            proximity: false,
+            proximity_away_rssi: -80,
+            proximity_back_rssi: -65,
+            proximity_lock: true,
+            proximity_unlock: false,
+            proximity_away_command: String::new(),
+            proximity_back_command: String::new(),
+            measured_power: 0,
+        }
+    }
+}
+
+impl DeviceSettings {
+    /// writes the settings into the `group` of a key file, shared by the store and backups
+    pub fn write_to(&self, key_file: &glib::KeyFile, group: &str) {
+        key_file.set_string(group, "Icon", &self.icon);
+        key_file.set_string(group, "Notes", &self.notes);
+        key_file.set_string(group, "AudioProfile", &self.audio_profile);
+        key_file.set_integer(group, "BatteryAlert", self.battery_alert as i32);
+        key_file.set_boolean(group, "AutoConnect", self.auto_connect);
+        key_file.set_boolean(group, "AutoAcceptFiles", self.auto_accept_files);
+        key_file.set_string(group, "DownloadFolder", &self.download_folder);
+        key_file.set_boolean(group, "Proximity", self.proximity);
+        key_file.set_integer(group, "ProximityAwayRssi", self.proximity_away_rssi as i32);
+        key_file.set_integer(group, "ProximityBackRssi", self.proximity_back_rssi as i32);
+        key_file.set_boolean(group, "ProximityLock", self.proximity_lock);
+        key_file.set_boolean(group, "ProximityUnlock", self.proximity_unlock);
+        key_file.set_string(group, "ProximityAwayCommand", &self.proximity_away_command);
+        key_file.set_string(group, "ProximityBackCommand", &self.proximity_back_command);
+        key_file.set_integer(group, "MeasuredPower", self.measured_power as i32);
+    }
+
+    /// A copy without the settings that run shell commands, unlock the session or accept files without asking,
+    /// which are left at their defaults. Backups only carry this part, so restoring one can't set those up.
+    pub fn without_privileged(&self) -> Self {
+        let defaults = DeviceSettings::default();
+
+        DeviceSettings {
+            auto_accept_files: defaults.auto_accept_files,
+            proximity_unlock: defaults.proximity_unlock,
+            proximity_away_command: defaults.proximity_away_command,
+            proximity_back_command: defaults.proximity_back_command,
+            ..self.clone()
+        }
+    }
+
+    /// reads the settings from the `group` of a key file, anything missing being left at its default
+    pub fn read_from(key_file: &glib::KeyFile, group: &str) -> Self {
+        let string = |key: &str| key_file.string(group, key).map(|value| value.to_string()).unwrap_or_default();
+        let rssi = |key: &str, default: i16| key_file.integer(group, key).map(|value| value.clamp(-127, 20) as i16).unwrap_or(default);
+        let defaults = DeviceSettings::default();
+
+        DeviceSettings {
+            icon: string("Icon"),
+            notes: string("Notes"),
+            audio_profile: string("AudioProfile"),
+            battery_alert: key_file.integer(group, "BatteryAlert").unwrap_or(0).clamp(0, 100) as u8,
+            auto_connect: key_file.boolean(group, "AutoConnect").unwrap_or(false),
+            auto_accept_files: key_file.boolean(group, "AutoAcceptFiles").unwrap_or(false),
+            download_folder: string("DownloadFolder"),
+            proximity: key_file.boolean(group, "Proximity").unwrap_or(false),
+            proximity_away_rssi: rssi("ProximityAwayRssi", defaults.proximity_away_rssi),
+            proximity_back_rssi: rssi("ProximityBackRssi", defaults.proximity_back_rssi),
+            proximity_lock: key_file.boolean(group, "ProximityLock").unwrap_or(defaults.proximity_lock),
+            proximity_unlock: key_file.boolean(group, "ProximityUnlock").unwrap_or(false),
+            proximity_away_command: string("ProximityAwayCommand"),
+            proximity_back_command: string("ProximityBackCommand"),
+            measured_power: rssi("MeasuredPower", 0),
+        }
+    }
+}
+
+/// every device's settings, loaded from disk the first time they're needed
+static STORE: Mutex<Option<HashMap<bluer::Address, DeviceSettings>>> = Mutex::new(None);
+/// devices whose battery alert was sent already, so it's only sent again after they've been charged
+static BATTERY_ALERTED: Mutex<Vec<bluer::Address>> = Mutex::new(Vec::new());
+/// devices auto connect was tried with since they showed up, so a failed attempt isn't repeated over and over
+static AUTO_CONNECTED: Mutex<Vec<bluer::Address>> = Mutex::new(Vec::new());
+
+fn store_path() -> PathBuf {
+    glib::user_config_dir().join("overskride").join("devices.conf")
+}
+
+fn load() -> HashMap<bluer::Address, DeviceSettings> {
+    let key_file = glib::KeyFile::new();
+    if key_file.load_from_file(store_path(), glib::KeyFileFlags::NONE).is_err() {
+        return HashMap::new();
+    }
+
+    key_file.groups().iter()
+        .filter_map(|group| {
+            let address = group.as_str().parse::<bluer::Address>().ok()?;
+            Some((address, DeviceSettings::read_from(&key_file, group.as_str())))
+        })
+        .collect()
+}
+
+fn save(store: &HashMap<bluer::Address, DeviceSettings>) {
+    let key_file = glib::KeyFile::new();
+    for (address, settings) in store {
+        settings.write_to(&key_file, &address.to_string());
+    }
+
+    let path = store_path();
+    if let Some(folder) = path.parent() {
+        let _ = std::fs::create_dir_all(folder);
+    }
+    if let Err(err) = key_file.save_to_file(&path) {
+        event_log::warning(Source::App, "cannot save device settings").field("error", &err).record();
+    }
+}
+
+/// the settings of the device, the defaults if it has none
+pub fn get(address: bluer::Address) -> DeviceSettings {
+    let mut store = STORE.lock().unwrap();
+    store.get_or_insert_with(load).get(&address).cloned().unwrap_or_default()
+}
+
+/// Saves the settings of the device, devices left with only defaults are forgotten.
+/// Returns whether anything changed.
+pub fn set(address: bluer::Address, settings: DeviceSettings) -> bool {
+    let mut store = STORE.lock().unwrap();
+    let store = store.get_or_insert_with(load);
+
+    if store.get(&address).cloned().unwrap_or_default() == settings {
+        return false;
+    }
+
+    if settings == DeviceSettings::default() {
+        store.remove(&address);
+    } else {
+        store.insert(address, settings);
+    }
+    save(store);
+
+    true
+}
+
+/// changes one thing about the device's settings, saving them
+pub fn update(address: bluer::Address, change: impl FnOnce(&mut DeviceSettings)) -> bool {
+    let mut settings = get(address);
+    change(&mut settings);
+    set(address, settings)
+}
+
+/// the devices with proximity actions turned on
+pub fn with_proximity() -> Vec<bluer::Address> {
+    let mut store = STORE.lock().unwrap();
+    store.get_or_insert_with(load).iter()
+        .filter(|(_, settings)| settings.proximity)
+        .map(|(address, _)| *address)
+        .collect()
+}
+
+/// the device's own icon, unless it was given another one
+pub fn icon_for(address: bluer::Address, own_icon: String) -> String {
+    let icon = get(address).icon;
+    if icon.is_empty() { own_icon } else { icon }
+}
+
+/// Whether the battery of the device just dropped under its alert threshold.
+/// True only once until it's charged above the threshold again.
+pub fn is_battery_low(address: bluer::Address, percentage: u8) -> bool {
+    let threshold = get(address).battery_alert;
+    let mut alerted = BATTERY_ALERTED.lock().unwrap();
+
+    if threshold == 0 || percentage > threshold {
+        alerted.retain(|other| *other != address);
+        return false;
+    }
+
+    if alerted.contains(&address) {
+        return false;
+    }
+    alerted.push(address);
+
+    true
+}
+
+/// Whether the device should be connected now that it showed up, true once per time it shows up.
+/// [forget_auto_connect](forget_auto_connect) lets it be tried again.
+pub fn should_auto_connect(address: bluer::Address) -> bool {
+    if !get(address).auto_connect {
+        return false;
+    }
+
+    let mut auto_connected = AUTO_CONNECTED.lock().unwrap();
+    if auto_connected.contains(&address) {
+        return false;
+    }
+    auto_connected.push(address);
+
+    true
+}
+
+/// the device went away, so it's auto connected again when it comes back
+pub fn forget_auto_connect(address: bluer::Address) {
+    AUTO_CONNECTED.lock().unwrap().retain(|other| *other != address);
+}
@@ -0,0 +1,436 @@
+use std::fmt;
+
+use bluer::{ErrorKind, InternalErrorKind};
+
+/// Everything that can go wrong and gets shown to the user, with what it means and what to do about it,
+/// along with the app's own messages that are shown the same way.
+/// Backend code returns these directly, only raw [bluer::Error](bluer::Error)s and D-Bus errors get worked out from their message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OverskrideError {
+    /// bluetoothd isn't on the system bus
+    BluezNotRunning,
+    /// obexd isn't on the session bus, so files can't be sent or received
+    ObexdMissing,
+    /// the bus or bluez refused to let us do something
+    PermissionDenied,
+    /// there is no adapter, or the one we were using is gone
+    AdapterAbsent,
+    AdapterNotPowered,
+    /// the radio is blocked by rfkill and couldn't be unblocked
+    RadioBlocked,
+    /// this connection registered a pairing agent already, bluez takes one per connection
+    AgentAlreadyRegistered,
+    /// the device (or the user on it) said no to pairing
+    PairingRejected,
+    /// wrong pin or passkey, or the keys the device has for us don't match anymore
+    AuthenticationFailed,
+    /// nobody confirmed the pairing in time
+    AuthenticationTimeout,
+    /// the device didn't answer, it's off or out of range
+    PageTimeout,
+    ConnectionRefused,
+    /// the connection dropped while it was being set up
+    ConnectionAborted,
+    AlreadyConnected,
+    ProfileUnavailable,
+    NotSupported,
+    /// bluez is still busy with something else
+    Busy,
+    /// the device or adapter went away while we were using it
+    NotFound,
+    /// bluez couldn't open a socket for the connection
+    SocketFailed,
+    BadSocket,
+    OutOfMemory,
+    /// the adapter has as many connections as it can take
+    ConnectionLimit,
+    LmpProtocolError,
+    LayerProtocolError,
+    InvalidArguments,
+    /// the connection was made, but reading the device's services failed
+    GattBrowsingFailed,
+    /// bluez couldn't say why the connection failed
+    ConnectionUnknown,
+    ConnectionFailed,
+    /// the devices list couldn't be refreshed after switching adapters
+    AdapterRefreshFailed,
+    DevicesRefreshed,
+    /// following the adapter's events stopped without being asked to
+    SearchStopped,
+    SendingStarted,
+    ReceivingStarted,
+    FileSent,
+    FileReceived,
+    SendingFailed,
+    ReceivingFailed,
+    /// the transfer session with the device couldn't be created
+    TransferConnectionFailed,
+    TransferNotConnected,
+    /// the received file couldn't be moved out of obexd's cache
+    TransferCantMove,
+    /// the folder chosen for received files isn't usable
+    StorageLocationInvalid,
+    CacheLocationInvalid,
+    DeviceNameExists,
+    AudioSinkUnsupported,
+    AudioSinkRouteFailed,
+    /// oFono isn't running
+    TelephonyUnavailable,
+    CallAnswerFailed,
+    CallHangupFailed,
+    /// the call was answered, but its audio couldn't be moved to this computer
+    CallAudioFailed,
+    /// the broadcast has no streams to join yet
+    BroadcastNoStreams,
+    BroadcastJoinFailed,
+    NoPairedDevices,
+    /// a device asked for over the app's D-Bus API isn't known
+    DeviceUnknown,
+    InvalidServiceUuid,
+    BackupExported,
+    BackupImported,
+    /// the backup was made on another adapter, so its pairing keys were left out
+    BackupImportedWithoutKeys,
+    BackupUnreadable,
+    BackupUnwritable,
+    BackupNewerVersion,
+    /// polkit said no to the keys helper
+    KeysNotAuthorized,
+    /// the keys helper couldn't be run, or it failed
+    KeysHelperFailed,
+    ConnectionParametersNotAuthorized,
+    ConnectionParametersFailed,
+    WindowsHiveUnreadable,
+    WindowsHiveNoKeys,
+    WindowsKeysOtherAdapter,
+    WindowsKeysNoMatches,
+    WindowsKeysSynced,
+    /// anything we don't know, with the message it came with
+    Unknown(String),
+}
+
+impl OverskrideError {
+    /// what happened, short enough for a toast
+    pub fn explanation(&self) -> String {
+        match self {
+            OverskrideError::BluezNotRunning => "The Bluetooth service isn't running",
+            OverskrideError::ObexdMissing => "The file transfer service isn't running",
+            OverskrideError::PermissionDenied => "Not allowed to use Bluetooth",
+            OverskrideError::AdapterAbsent => "No Bluetooth adapter found",
+            OverskrideError::AdapterNotPowered => "Adapter is not powered",
+            OverskrideError::RadioBlocked => "Bluetooth is blocked",
+            OverskrideError::AgentAlreadyRegistered => "The pairing agent is registered already",
+            OverskrideError::PairingRejected => "Pairing was rejected",
+            OverskrideError::AuthenticationFailed => "Authentication failed",
+            OverskrideError::AuthenticationTimeout => "Pairing timed out",
+            OverskrideError::PageTimeout => "Failed to connect to device, connection timed out",
+            OverskrideError::ConnectionRefused => "Connection was refused by target device",
+            OverskrideError::ConnectionAborted => "Connection has been aborted",
+            OverskrideError::AlreadyConnected => "Device is already connected",
+            OverskrideError::ProfileUnavailable => "Failed to find the target profile",
+            OverskrideError::NotSupported => "Connection failed, requested features are not supported",
+            OverskrideError::Busy => "Other operations pending, please try again in a bit",
+            OverskrideError::NotFound => "The device or adapter isn't there anymore",
+            OverskrideError::SocketFailed => "Failed to connect to Bluetooth socket, this is bad",
+            OverskrideError::BadSocket => "Bad socket for connection, this is bad",
+            OverskrideError::OutOfMemory => "Failed to allocate memory",
+            OverskrideError::ConnectionLimit => "Reached limit, cannot connect to anymore devices",
+            OverskrideError::LmpProtocolError => "Connection failed, lmp protocol error",
+            OverskrideError::LayerProtocolError => "Connection failed, layer protocol error",
+            OverskrideError::InvalidArguments => "Invalid arguments provided",
+            OverskrideError::GattBrowsingFailed => "Failed to complete GATT service browsing",
+            OverskrideError::ConnectionUnknown => "Connection unknown, please try again",
+            OverskrideError::ConnectionFailed => "Connection failed, no idea why tho",
+            OverskrideError::AdapterRefreshFailed => "Unable to refresh devices list after adapter change",
+            OverskrideError::DevicesRefreshed => "Refreshed devices list",
+            OverskrideError::SearchStopped => "Stopped Searching for devices",
+            OverskrideError::SendingStarted => "Started transferring file",
+            OverskrideError::ReceivingStarted => "Started receiving file",
+            OverskrideError::FileSent => "File has been transferred",
+            OverskrideError::FileReceived => "File has been received",
+            OverskrideError::SendingFailed => "Sending file stopped, error occurred",
+            OverskrideError::ReceivingFailed => "Receiving file stopped, error occurred",
+            OverskrideError::TransferConnectionFailed => "Unable to send file, connection is not possible",
+            OverskrideError::TransferNotConnected => "Connect to the device before sending files to it",
+            OverskrideError::TransferCantMove => "Unable to move the received file to its location",
+            OverskrideError::StorageLocationInvalid => "Location is not valid, please try again",
+            OverskrideError::CacheLocationInvalid => "File cache location is invalid, are you sure ~/.cache (or equivalent) exists?",
+            OverskrideError::DeviceNameExists => "Error, device with name already exists",
+            OverskrideError::AudioSinkUnsupported => "The sound server doesn't let this device act as a speaker",
+            OverskrideError::AudioSinkRouteFailed => "Unable to play incoming audio on the default output",
+            OverskrideError::TelephonyUnavailable => "oFono is not running, phone calls can't be handled",
+            OverskrideError::CallAnswerFailed => "Unable to answer call",
+            OverskrideError::CallHangupFailed => "Unable to end call",
+            OverskrideError::CallAudioFailed => "Call answered, but its audio stayed on the phone",
+            OverskrideError::BroadcastNoStreams => "Broadcast isn't ready yet, try again in a moment",
+            OverskrideError::BroadcastJoinFailed => "Unable to join or leave broadcast",
+            OverskrideError::NoPairedDevices => "No paired devices to send files to",
+            OverskrideError::DeviceUnknown => "Device isn't in range or isn't known",
+            OverskrideError::InvalidServiceUuid => "Not a valid service UUID, use the full form or a 16 bit one like 180d",
+            OverskrideError::BackupExported => "Devices have been backed up",
+            OverskrideError::BackupImported => "Devices have been restored",
+            OverskrideError::BackupImportedWithoutKeys => "Devices have been restored without their pairing keys",
+            OverskrideError::BackupUnreadable => "Not a valid Overskride backup",
+            OverskrideError::BackupUnwritable => "Unable to save the backup there",
+            OverskrideError::BackupNewerVersion => "This backup was made by a newer version of Overskride",
+            OverskrideError::KeysNotAuthorized => "Not allowed to access the pairing keys",
+            OverskrideError::KeysHelperFailed => "Unable to copy the pairing keys",
+            OverskrideError::ConnectionParametersNotAuthorized => "Not allowed to read the stored connection parameters",
+            OverskrideError::ConnectionParametersFailed => "Unable to read the stored connection parameters",
+            OverskrideError::WindowsHiveUnreadable => "Not a readable Windows SYSTEM registry file",
+            OverskrideError::WindowsHiveNoKeys => "Windows has no Bluetooth pairing keys in this registry file",
+            OverskrideError::WindowsKeysOtherAdapter => "Windows paired its devices with a different adapter",
+            OverskrideError::WindowsKeysNoMatches => "No device is paired on both Windows and here",
+            OverskrideError::WindowsKeysSynced => "Pairing keys copied from Windows",
+            OverskrideError::Unknown(_) => "Unknown error occurred",
+        }
+        .to_string()
+    }
+
+    /// what the user could do to fix it, `None` if there's nothing to do but try again
+    pub fn suggestion(&self) -> Option<&'static str> {
+        match self {
+            OverskrideError::BluezNotRunning => Some("Install the bluez package for your distro, then start the service with `sudo systemctl enable --now bluetooth` and restart Overskride."),
+            OverskrideError::ObexdMissing => Some("Install obexd (part of bluez, sometimes packaged as bluez-obex or bluez-tools), then log out and back in so it starts with your session."),
+            OverskrideError::PermissionDenied => Some("Make sure your user is allowed to use bluez (some distros need the `bluetooth` or `lp` group), and when using flatpak that Overskride may talk to the system bus."),
+            OverskrideError::AdapterAbsent => Some("Plug in a Bluetooth adapter, or check that it isn't turned off in your firmware settings or by a hardware switch."),
+            OverskrideError::AdapterNotPowered => Some("Turn the adapter on with the Powered switch in Bluetooth Settings."),
+            OverskrideError::RadioBlocked => Some("Turn off airplane mode, or run `rfkill unblock bluetooth`."),
+            OverskrideError::AgentAlreadyRegistered => Some("Restart Overskride if pairing requests stop showing up."),
+            OverskrideError::PairingRejected => Some("Put the device in pairing mode and accept the request on it, then try again."),
+            OverskrideError::AuthenticationFailed => Some("Check the pin or passkey. If the device was paired with this computer before, remove it and pair it again."),
+            OverskrideError::AuthenticationTimeout => Some("Confirm the pairing request on both devices quickly, then try again."),
+            OverskrideError::PageTimeout => Some("Make sure the device is turned on, in range, and not connected to something else."),
+            OverskrideError::ConnectionRefused => Some("The device may only accept its paired devices, or be busy with another one. Disconnect it there, or remove and pair it again."),
+            OverskrideError::ProfileUnavailable => Some("The sound server or a bluez plugin for this profile may be missing, or the device may need to be paired again."),
+            OverskrideError::NotFound => Some("It may have been removed or unplugged, refresh the devices list."),
+            OverskrideError::BackupImportedWithoutKeys => Some("The backup was made on a different adapter, and its keys would have replaced the ones this adapter pairs with. Pair the devices again, or restore the backup on the adapter it was made on."),
+            _ => None,
+        }
+    }
+
+    /// Works out the error from a raw bluez or D-Bus message, like "br-connection-page-timeout" or
+    /// "org.freedesktop.DBus.Error.AccessDenied ...". Messages it doesn't know become [Unknown](OverskrideError::Unknown).
+    pub fn from_key(key: &str) -> Self {
+        let key_lowercase = key.to_lowercase();
+        let has = |part: &str| key_lowercase.contains(part);
+
+        match key_lowercase.as_str() {
+            _ if has("org.bluez.obex") && (has("not provided") || has("serviceunknown")) => OverskrideError::ObexdMissing,
+            _ if has("org.bluez") && (has("not provided") || has("serviceunknown") || has("namehasnoowner")) => OverskrideError::BluezNotRunning,
+            _ if has("accessdenied") || has("access denied") || has("permission denied") || has("not permitted") => OverskrideError::PermissionDenied,
+            _ if has("no such adapter") => OverskrideError::AdapterAbsent,
+            _ if has("authentication rejected") || has("authentication canceled") => OverskrideError::PairingRejected,
+            _ if has("authentication failed") || has("auth-failed") => OverskrideError::AuthenticationFailed,
+            _ if has("authentication timeout") => OverskrideError::AuthenticationTimeout,
+            _ if has("page-timeout") || has("connection-timeout") => OverskrideError::PageTimeout,
+            _ if has("already-connected") => OverskrideError::AlreadyConnected,
+            _ if has("profile-unavailable") => OverskrideError::ProfileUnavailable,
+            _ if has("refused") => OverskrideError::ConnectionRefused,
+            _ if has("aborted-by-remote") || has("aborted-by-local") || has("canceled") => OverskrideError::ConnectionAborted,
+            _ if has("busy") || has("inprogress") || has("in progress") => OverskrideError::Busy,
+            _ if has("not-powered") || has("resource not ready") => OverskrideError::AdapterNotPowered,
+            _ if has("not-supported") => OverskrideError::NotSupported,
+            _ if has("create-socket") => OverskrideError::SocketFailed,
+            _ if has("bad-socket") => OverskrideError::BadSocket,
+            _ if has("memory-allocation") => OverskrideError::OutOfMemory,
+            _ if has("limit") => OverskrideError::ConnectionLimit,
+            _ if has("lmp-protocol-error") => OverskrideError::LmpProtocolError,
+            _ if has("layer-protocol-error") => OverskrideError::LayerProtocolError,
+            _ if has("invalid-arguments") || has("invalid arguments") => OverskrideError::InvalidArguments,
+            _ if has("gatt-browsing") => OverskrideError::GattBrowsingFailed,
+            _ if has("unknown-error") => OverskrideError::ConnectionFailed,
+            _ if has("connection-unknown") => OverskrideError::ConnectionUnknown,
+            _ => OverskrideError::Unknown(key.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for OverskrideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverskrideError::Unknown(message) => write!(f, "{}", message),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl From<bluer::Error> for OverskrideError {
+    /// the kind says more than the message when there is one that fits, otherwise it's down to the message
+    fn from(err: bluer::Error) -> Self {
+        match &err.kind {
+            ErrorKind::AuthenticationRejected | ErrorKind::AuthenticationCanceled => OverskrideError::PairingRejected,
+            ErrorKind::AuthenticationFailed => OverskrideError::AuthenticationFailed,
+            ErrorKind::AuthenticationTimeout => OverskrideError::AuthenticationTimeout,
+            ErrorKind::AlreadyConnected => OverskrideError::AlreadyConnected,
+            ErrorKind::NotPermitted => OverskrideError::PermissionDenied,
+            ErrorKind::InvalidName(_) => OverskrideError::AdapterAbsent,
+            ErrorKind::NotFound | ErrorKind::DoesNotExist if err.message.is_empty() => OverskrideError::NotFound,
+            ErrorKind::Internal(InternalErrorKind::DBusConnectionLost) => OverskrideError::BluezNotRunning,
+            ErrorKind::Internal(InternalErrorKind::DBus(name)) => OverskrideError::from_key(&format!("{} {}", name, err.message)),
+            _ if err.message.is_empty() => OverskrideError::from_key(&err.kind.to_string()),
+            _ => OverskrideError::from_key(&err.message),
+        }
+    }
+}
+
+impl From<dbus::Error> for OverskrideError {
+    fn from(err: dbus::Error) -> Self {
+        OverskrideError::from_key(&format!("{} {}", err.name().unwrap_or_default(), err.message().unwrap_or_default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bluez_connection_messages() {
+        let cases = [
+            ("br-connection-page-timeout", OverskrideError::PageTimeout),
+            ("le-connection-timeout", OverskrideError::PageTimeout),
+            ("br-connection-refused", OverskrideError::ConnectionRefused),
+            ("br-connection-aborted-by-remote", OverskrideError::ConnectionAborted),
+            ("br-connection-aborted-by-local", OverskrideError::ConnectionAborted),
+            ("br-connection-already-connected", OverskrideError::AlreadyConnected),
+            ("br-connection-profile-unavailable", OverskrideError::ProfileUnavailable),
+            ("br-connection-adapter-not-powered", OverskrideError::AdapterNotPowered),
+            ("br-connection-not-supported", OverskrideError::NotSupported),
+            ("br-connection-busy", OverskrideError::Busy),
+            ("br-connection-create-socket", OverskrideError::SocketFailed),
+            ("br-connection-bad-socket", OverskrideError::BadSocket),
+            ("br-connection-memory-allocation", OverskrideError::OutOfMemory),
+            ("le-connection-limit", OverskrideError::ConnectionLimit),
+            ("br-connection-lmp-protocol-error", OverskrideError::LmpProtocolError),
+            ("le-connection-layer-protocol-error", OverskrideError::LayerProtocolError),
+            ("br-connection-invalid-arguments", OverskrideError::InvalidArguments),
+            ("le-connection-gatt-browsing", OverskrideError::GattBrowsingFailed),
+            ("br-connection-unknown", OverskrideError::ConnectionUnknown),
+            ("le-connection-unknown", OverskrideError::ConnectionUnknown),
+            ("unknown-error", OverskrideError::ConnectionFailed),
+        ];
+
+        for (message, expected) in cases {
+            assert_eq!(OverskrideError::from_key(message), expected, "{}", message);
+        }
+    }
+
+    #[test]
+    fn bluez_and_dbus_errors() {
+        let cases = [
+            ("org.freedesktop.DBus.Error.ServiceUnknown The name org.bluez was not provided by any .service files", OverskrideError::BluezNotRunning),
+            ("org.freedesktop.DBus.Error.ServiceUnknown The name org.bluez.obex was not provided by any .service files", OverskrideError::ObexdMissing),
+            ("org.freedesktop.DBus.Error.NameHasNoOwner Could not get owner of name 'org.bluez'", OverskrideError::BluezNotRunning),
+            ("org.freedesktop.DBus.Error.AccessDenied Rejected send message", OverskrideError::PermissionDenied),
+            ("org.bluez.Error.InProgress Operation already in progress", OverskrideError::Busy),
+            ("org.bluez.Error.AuthenticationRejected Authentication Rejected", OverskrideError::PairingRejected),
+            ("org.bluez.Error.AuthenticationCanceled Authentication Canceled", OverskrideError::PairingRejected),
+            ("org.bluez.Error.AuthenticationFailed Authentication Failed", OverskrideError::AuthenticationFailed),
+            ("org.bluez.Error.AuthenticationTimeout Authentication Timeout", OverskrideError::AuthenticationTimeout),
+            ("org.bluez.Error.NotReady Resource Not Ready", OverskrideError::AdapterNotPowered),
+        ];
+
+        for (message, expected) in cases {
+            assert_eq!(OverskrideError::from_key(message), expected, "{}", message);
+        }
+    }
+
+    #[test]
+    fn unknown_messages_are_kept() {
+        assert_eq!(OverskrideError::from_key("queued"), OverskrideError::Unknown("queued".to_string()));
+        assert_eq!(OverskrideError::from_key("Something Odd"), OverskrideError::Unknown("Something Odd".to_string()));
+    }
+
+    #[test]
+    fn bluer_kinds_win_over_messages() {
+        let rejected = bluer::Error { kind: ErrorKind::AuthenticationRejected, message: "br-connection-refused".to_string() };
+        assert_eq!(OverskrideError::from(rejected), OverskrideError::PairingRejected);
+
+        let gone = bluer::Error { kind: ErrorKind::DoesNotExist, message: String::new() };
+        assert_eq!(OverskrideError::from(gone), OverskrideError::NotFound);
+
+        let refused = bluer::Error { kind: ErrorKind::Failed, message: "br-connection-refused".to_string() };
+        assert_eq!(OverskrideError::from(refused), OverskrideError::ConnectionRefused);
+    }
+
+    #[test]
+    fn every_toast_has_its_own_explanation() {
+        let errors = [
+            OverskrideError::BluezNotRunning,
+            OverskrideError::ObexdMissing,
+            OverskrideError::PermissionDenied,
+            OverskrideError::AdapterAbsent,
+            OverskrideError::AdapterNotPowered,
+            OverskrideError::RadioBlocked,
+            OverskrideError::AgentAlreadyRegistered,
+            OverskrideError::PairingRejected,
+            OverskrideError::AuthenticationFailed,
+            OverskrideError::AuthenticationTimeout,
+            OverskrideError::PageTimeout,
+            OverskrideError::ConnectionRefused,
+            OverskrideError::ConnectionAborted,
+            OverskrideError::AlreadyConnected,
+            OverskrideError::ProfileUnavailable,
+            OverskrideError::NotSupported,
+            OverskrideError::Busy,
+            OverskrideError::NotFound,
+            OverskrideError::SocketFailed,
+            OverskrideError::BadSocket,
+            OverskrideError::OutOfMemory,
+            OverskrideError::ConnectionLimit,
+            OverskrideError::LmpProtocolError,
+            OverskrideError::LayerProtocolError,
+            OverskrideError::InvalidArguments,
+            OverskrideError::GattBrowsingFailed,
+            OverskrideError::ConnectionUnknown,
+            OverskrideError::ConnectionFailed,
+            OverskrideError::AdapterRefreshFailed,
+            OverskrideError::DevicesRefreshed,
+            OverskrideError::SearchStopped,
+            OverskrideError::SendingStarted,
+            OverskrideError::ReceivingStarted,
+            OverskrideError::FileSent,
+            OverskrideError::FileReceived,
+            OverskrideError::SendingFailed,
+            OverskrideError::ReceivingFailed,
+            OverskrideError::TransferConnectionFailed,
+            OverskrideError::TransferNotConnected,
+            OverskrideError::TransferCantMove,
+            OverskrideError::StorageLocationInvalid,
+            OverskrideError::CacheLocationInvalid,
+            OverskrideError::DeviceNameExists,
+            OverskrideError::AudioSinkUnsupported,
+            OverskrideError::AudioSinkRouteFailed,
+            OverskrideError::TelephonyUnavailable,
+            OverskrideError::CallAnswerFailed,
+            OverskrideError::CallHangupFailed,
+            OverskrideError::CallAudioFailed,
+            OverskrideError::BroadcastNoStreams,
+            OverskrideError::BroadcastJoinFailed,
+            OverskrideError::NoPairedDevices,
+            OverskrideError::DeviceUnknown,
+            OverskrideError::InvalidServiceUuid,
+            OverskrideError::BackupExported,
+            OverskrideError::BackupImported,
+            OverskrideError::BackupImportedWithoutKeys,
+            OverskrideError::BackupUnreadable,
+            OverskrideError::BackupUnwritable,
+            OverskrideError::BackupNewerVersion,
+            OverskrideError::KeysNotAuthorized,
+            OverskrideError::KeysHelperFailed,
+            OverskrideError::ConnectionParametersNotAuthorized,
+            OverskrideError::ConnectionParametersFailed,
+            OverskrideError::WindowsHiveUnreadable,
+            OverskrideError::WindowsHiveNoKeys,
+            OverskrideError::WindowsKeysOtherAdapter,
+            OverskrideError::WindowsKeysNoMatches,
+            OverskrideError::WindowsKeysSynced,
+        ];
+
+        for (index, error) in errors.iter().enumerate() {
+            for other in &errors[index + 1..] {
+                assert_ne!(error.explanation(), other.explanation(), "{:?} and {:?}", error, other);
+            }
+        }
+    }
+}
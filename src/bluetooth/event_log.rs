@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::sync::Mutex;
+use async_channel::{Receiver, Sender};
+use gtk::glib;
+
+/// how many entries are kept, the oldest ones are dropped after that
+pub const MAX_ENTRIES: usize = 5000;
+
+/// how much an entry matters, signal strength updates and the like are debug so they can be hidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+/// what an entry is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// events of the adapter, from bluez
+    Adapter,
+    /// events of a device, from bluez
+    Device,
+    /// the pairing agent
+    Agent,
+    /// file transfers and their agent
+    Obex,
+    /// everything else Overskride does on its own
+    App,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+impl Source {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Source::Adapter => "adapter",
+            Source::Device => "device",
+            Source::Agent => "agent",
+            Source::Obex => "obex",
+            Source::App => "app",
+        }
+    }
+}
+
+/// One thing that happened, made with [debug](debug), [info](info), [warning](warning) or [error](error)
+/// and kept with [record](LogEntry::record).
+#[derive(Debug, Clone)]
+#[must_use = "entries are only kept once recorded"]
+pub struct LogEntry {
+    pub time: glib::DateTime,
+    pub level: Level,
+    pub source: Source,
+    pub device: Option<bluer::Address>,
+    pub message: String,
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl LogEntry {
+    fn new(level: Level, source: Source, message: impl Into<String>) -> Self {
+        LogEntry {
+            time: glib::DateTime::now_local().or_else(|_| glib::DateTime::now_utc()).expect("cannot get the time"),
+            level,
+            source,
+            device: None,
+            message: message.into(),
+            fields: vec![],
+        }
+    }
+
+    /// the device the entry is about, entries can be filtered by it
+    pub fn device(mut self, address: bluer::Address) -> Self {
+        self.device = Some(address);
+        self
+    }
+
+    /// adds a named value to the entry
+    pub fn field(mut self, name: &'static str, value: impl Display) -> Self {
+        self.fields.push((name, value.to_string()));
+        self
+    }
+
+    /// the fields as `name=value`, values with spaces quoted
+    pub fn describe_fields(&self) -> String {
+        self.fields.iter()
+            .map(|(name, value)| {
+                if value.contains(char::is_whitespace) || value.is_empty() {
+                    format!("{}={:?}", name, value)
+                }
+                else {
+                    format!("{}={}", name, value)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// the whole entry on one line, as printed and exported
+    pub fn describe(&self) -> String {
+        let time = self.time.format_iso8601().map(|time| time.to_string()).unwrap_or_default();
+        let device = self.device.map(|address| address.to_string()).unwrap_or("-".to_string());
+
+        let mut line = format!("{} {:<7} {:<7} {} {}", time, self.level.name(), self.source.name(), device, self.message);
+        if !self.fields.is_empty() {
+            line += " ";
+            line += &self.describe_fields();
+        }
+        line
+    }
+
+    /// Keeps the entry, prints it, and passes it to whoever [subscribed](subscribe).
+    pub fn record(self) {
+        if self.level > Level::Debug {
+            println!("{}", self.describe());
+        }
+
+        let mut log = LOG.lock().unwrap();
+        log.entries.push_back(self.clone());
+        while log.entries.len() > MAX_ENTRIES {
+            log.entries.pop_front();
+        }
+
+        log.subscribers.retain(|subscriber| subscriber.try_send(self.clone()).is_ok());
+    }
+}
+
+struct Log {
+    entries: VecDeque<LogEntry>,
+    subscribers: Vec<Sender<LogEntry>>,
+}
+
+static LOG: Mutex<Log> = Mutex::new(Log {
+    entries: VecDeque::new(),
+    subscribers: Vec::new(),
+});
+
+pub fn debug(source: Source, message: impl Into<String>) -> LogEntry {
+    LogEntry::new(Level::Debug, source, message)
+}
+
+pub fn info(source: Source, message: impl Into<String>) -> LogEntry {
+    LogEntry::new(Level::Info, source, message)
+}
+
+pub fn warning(source: Source, message: impl Into<String>) -> LogEntry {
+    LogEntry::new(Level::Warning, source, message)
+}
+
+pub fn error(source: Source, message: impl Into<String>) -> LogEntry {
+    LogEntry::new(Level::Error, source, message)
+}
+
+/// Returns the entries kept so far, and a receiver getting every entry recorded after them until it's closed.
+pub fn subscribe() -> (Vec<LogEntry>, Receiver<LogEntry>) {
+    let (sender, receiver) = async_channel::unbounded();
+
+    let mut log = LOG.lock().unwrap();
+    log.subscribers.push(sender);
+
+    (log.entries.iter().cloned().collect(), receiver)
+}
+
+/// forgets every entry kept so far
+pub fn clear() {
+    LOG.lock().unwrap().entries.clear();
+}
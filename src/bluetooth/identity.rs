@@ -0,0 +1,409 @@
+use bluer::id::Manufacturer;
+
+/// everything a device tells us about what it is and who made it, decoded into something readable
+#[derive(Debug, Clone, Default)]
+pub struct DeviceIdentity {
+    /// what kind of device this is, e.g. "Headphones" or "Keyboard"
+    pub kind: Option<String>,
+    /// the major and minor class of device, e.g. "Audio/Video: Headphones"
+    pub class: Option<String>,
+    /// the service classes from the class of device, e.g. "Audio" or "Telephony"
+    pub service_classes: Vec<String>,
+    /// the LE appearance, e.g. "Wearable Audio Device: Earbud"
+    pub appearance: Option<String>,
+    /// the vendor from the Device ID profile (modalias)
+    pub vendor: Option<String>,
+    /// the product id and version from the Device ID profile (modalias)
+    pub product: Option<String>,
+    /// every company that sent manufacturer data, in order of their company id
+    pub manufacturers: Vec<String>,
+    /// a freedesktop icon name (without "-symbolic") that fits this device
+    pub icon: Option<String>,
+}
+
+impl DeviceIdentity {
+    /// who made this device, the Device ID vendor is preferred since manufacturer data can come from chip makers
+    pub fn maker(&self) -> Option<String> {
+        self.vendor.clone().or(self.manufacturers.first().cloned())
+    }
+
+    /// a short description for device rows, like "Headphones · Sony Group Corporation"
+    pub fn summary(&self) -> String {
+        [self.kind.clone(), self.maker()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>()
+            .join(" · ")
+    }
+}
+
+/// Reads and decodes the class of device, LE appearance, modalias and manufacturer data of `device`.
+/// Any of these can be missing, so every field of the result is optional.
+pub async fn get_device_identity(device: &bluer::Device) -> bluer::Result<DeviceIdentity> {
+    let mut identity = DeviceIdentity::default();
+
+    if let Some(class) = device.class().await? {
+        let (kind, description, icon) = decode_class(class);
+        identity.kind = kind;
+        identity.class = Some(description);
+        identity.icon = icon;
+        identity.service_classes = decode_service_classes(class);
+    }
+
+    // LE devices usually don't have a class, but their appearance tells us the same thing
+    if let Some(appearance) = device.appearance().await? {
+        let (kind, description, icon) = decode_appearance(appearance);
+        identity.kind = identity.kind.or(kind);
+        identity.appearance = Some(description);
+        identity.icon = identity.icon.or(icon);
+    }
+
+    if let Some(modalias) = device.modalias().await? {
+        identity.vendor = Some(vendor_name(&modalias.source, modalias.vendor));
+        identity.product = Some(format!("{:#06x}, version {:#06x}", modalias.product, modalias.device));
+    }
+
+    identity.manufacturers = get_manufacturers(device).await?;
+
+    Ok(identity)
+}
+
+/// every company that sent manufacturer data, ids without a known name are shown in hex
+pub async fn get_manufacturers(device: &bluer::Device) -> bluer::Result<Vec<String>> {
+    let mut ids = match device.manufacturer_data().await? {
+        Some(data) => data.into_keys().collect::<Vec<u16>>(),
+        None => vec![],
+    };
+    ids.sort();
+
+    Ok(ids.into_iter().map(company_name).collect())
+}
+
+fn company_name(id: u16) -> String {
+    Manufacturer::try_from(id)
+        .map(|manufacturer| manufacturer.to_string())
+        .unwrap_or_else(|_| format!("Unknown company ({:#06x})", id))
+}
+
+/// bluetooth vendor ids are company ids, usb ones aren't something we can look up
+fn vendor_name(source: &str, vendor: u32) -> String {
+    match source {
+        "bluetooth" => company_name(vendor as u16),
+        "usb" => format!("USB vendor {:#06x}", vendor),
+        other => format!("{} vendor {:#06x}", other, vendor),
+    }
+}
+
+/// Decodes the major and minor device class bits (2 to 12) of a class of device.
+/// Returns the kind of device, the full description, and an icon if there's one that fits.
+fn decode_class(class: u32) -> (Option<String>, String, Option<String>) {
+    let major = (class >> 8) & 0x1f;
+    let minor = (class >> 2) & 0x3f;
+
+    let (major_name, major_icon) = match major {
+        0x00 => ("Miscellaneous", None),
+        0x01 => ("Computer", Some("computer")),
+        0x02 => ("Phone", Some("phone")),
+        0x03 => ("Network Access Point", Some("network-wireless")),
+        0x04 => ("Audio/Video", Some("audio-card")),
+        0x05 => ("Peripheral", Some("input-keyboard")),
+        0x06 => ("Imaging", Some("camera-photo")),
+        0x07 => ("Wearable", None),
+        0x08 => ("Toy", None),
+        0x09 => ("Health", None),
+        _ => ("Uncategorized", None),
+    };
+
+    let (minor_name, minor_icon) = match (major, minor) {
+        (0x01, 0x01) => (Some("Desktop"), None),
+        (0x01, 0x02) => (Some("Server"), None),
+        (0x01, 0x03) => (Some("Laptop"), None),
+        (0x01, 0x04) => (Some("Handheld PC"), None),
+        (0x01, 0x05) => (Some("Palm-size PC"), None),
+        (0x01, 0x06) => (Some("Wearable Computer"), None),
+        (0x01, 0x07) => (Some("Tablet"), None),
+        (0x02, 0x01) => (Some("Cellular"), None),
+        (0x02, 0x02) => (Some("Cordless"), None),
+        (0x02, 0x03) => (Some("Smartphone"), None),
+        (0x02, 0x04) => (Some("Wired Modem"), Some("modem")),
+        (0x02, 0x05) => (Some("ISDN Access"), Some("modem")),
+        (0x04, 0x01) => (Some("Headset"), Some("audio-headset")),
+        (0x04, 0x02) => (Some("Hands-free Device"), Some("audio-headset")),
+        (0x04, 0x04) => (Some("Microphone"), Some("audio-input-microphone")),
+        (0x04, 0x05) => (Some("Loudspeaker"), Some("audio-speakers")),
+        (0x04, 0x06) => (Some("Headphones"), Some("audio-headphones")),
+        (0x04, 0x07) => (Some("Portable Audio"), Some("multimedia-player")),
+        (0x04, 0x08) => (Some("Car Audio"), Some("audio-card")),
+        (0x04, 0x09) => (Some("Set-top Box"), Some("video-display")),
+        (0x04, 0x0a) => (Some("HiFi Audio"), Some("audio-speakers")),
+        (0x04, 0x0b) => (Some("VCR"), Some("video-display")),
+        (0x04, 0x0c) => (Some("Video Camera"), Some("camera-video")),
+        (0x04, 0x0d) => (Some("Camcorder"), Some("camera-video")),
+        (0x04, 0x0e) => (Some("Video Monitor"), Some("video-display")),
+        (0x04, 0x0f) => (Some("Video Display and Loudspeaker"), Some("video-display")),
+        (0x04, 0x10) => (Some("Video Conferencing"), Some("camera-web")),
+        (0x04, 0x12) => (Some("Gaming Toy"), Some("input-gaming")),
+        (0x05, minor) => decode_peripheral(minor),
+        // imaging devices can be several things at once, the first one wins
+        (0x06, minor) if minor & 0x20 != 0 => (Some("Printer"), Some("printer")),
+        (0x06, minor) if minor & 0x10 != 0 => (Some("Scanner"), Some("scanner")),
+        (0x06, minor) if minor & 0x08 != 0 => (Some("Camera"), Some("camera-photo")),
+        (0x06, minor) if minor & 0x04 != 0 => (Some("Display"), Some("video-display")),
+        (0x07, 0x01) => (Some("Wristwatch"), None),
+        (0x07, 0x02) => (Some("Pager"), None),
+        (0x07, 0x03) => (Some("Jacket"), None),
+        (0x07, 0x04) => (Some("Helmet"), None),
+        (0x07, 0x05) => (Some("Glasses"), None),
+        (0x08, 0x01) => (Some("Robot"), None),
+        (0x08, 0x02) => (Some("Vehicle"), None),
+        (0x08, 0x03) => (Some("Doll"), None),
+        (0x08, 0x04) => (Some("Controller"), Some("input-gaming")),
+        (0x08, 0x05) => (Some("Game"), Some("input-gaming")),
+        (0x09, 0x01) => (Some("Blood Pressure Monitor"), None),
+        (0x09, 0x02) => (Some("Thermometer"), None),
+        (0x09, 0x03) => (Some("Weighing Scale"), None),
+        (0x09, 0x04) => (Some("Glucose Meter"), None),
+        (0x09, 0x05) => (Some("Pulse Oximeter"), None),
+        (0x09, 0x06) => (Some("Heart Rate Monitor"), None),
+        (0x09, 0x07) => (Some("Health Data Display"), None),
+        _ => (None, None),
+    };
+
+    let description = match minor_name {
+        Some(minor_name) => format!("{}: {}", major_name, minor_name),
+        None => major_name.to_string(),
+    };
+    let kind = minor_name.or(Some(major_name).filter(|name| !["Miscellaneous", "Uncategorized"].contains(name)));
+
+    (kind.map(str::to_string), description, minor_icon.or(major_icon).map(str::to_string))
+}
+
+/// peripherals have a keyboard/pointer part (bits 6 and 7 of the minor class) and a type part (bits 2 to 5)
+fn decode_peripheral(minor: u32) -> (Option<&'static str>, Option<&'static str>) {
+    match (minor >> 4, minor & 0x0f) {
+        (_, 0x01) => (Some("Joystick"), Some("input-gaming")),
+        (_, 0x02) => (Some("Gamepad"), Some("input-gaming")),
+        (_, 0x03) => (Some("Remote Control"), None),
+        (_, 0x04) => (Some("Sensing Device"), None),
+        (_, 0x05) => (Some("Digitizer Tablet"), Some("input-tablet")),
+        (_, 0x06) => (Some("Card Reader"), None),
+        (_, 0x07) => (Some("Digital Pen"), Some("input-tablet")),
+        (_, 0x08) => (Some("Handheld Scanner"), Some("scanner")),
+        (_, 0x09) => (Some("Gesture Input Device"), None),
+        (0x01, _) => (Some("Keyboard"), Some("input-keyboard")),
+        (0x02, _) => (Some("Mouse"), Some("input-mouse")),
+        (0x03, _) => (Some("Keyboard and Mouse"), Some("input-keyboard")),
+        _ => (None, None),
+    }
+}
+
+/// the service class bits (13 to 23) of a class of device
+fn decode_service_classes(class: u32) -> Vec<String> {
+    [
+        (13, "Limited Discoverable"),
+        (16, "Positioning"),
+        (17, "Networking"),
+        (18, "Rendering"),
+        (19, "Capturing"),
+        (20, "Object Transfer"),
+        (21, "Audio"),
+        (22, "Telephony"),
+        (23, "Information"),
+    ]
+    .iter()
+    .filter(|(bit, _)| class & (1 << bit) != 0)
+    .map(|(_, name)| name.to_string())
+    .collect()
+}
+
+/// Decodes an LE appearance, the top 10 bits are the category and the bottom 6 the subcategory.
+/// Returns the kind of device, the full description, and an icon if there's one that fits.
+fn decode_appearance(appearance: u16) -> (Option<String>, String, Option<String>) {
+    let category = appearance >> 6;
+    let subcategory = appearance & 0x3f;
+
+    let (category_name, category_icon) = match category {
+        0x00 => ("Unknown", None),
+        0x01 => ("Phone", Some("phone")),
+        0x02 => ("Computer", Some("computer")),
+        0x03 => ("Watch", None),
+        0x04 => ("Clock", None),
+        0x05 => ("Display", Some("video-display")),
+        0x06 => ("Remote Control", None),
+        0x07 => ("Eyeglasses", None),
+        0x08 => ("Tag", None),
+        0x09 => ("Keyring", None),
+        0x0a => ("Media Player", Some("multimedia-player")),
+        0x0b => ("Barcode Scanner", Some("scanner")),
+        0x0c => ("Thermometer", None),
+        0x0d => ("Heart Rate Sensor", None),
+        0x0e => ("Blood Pressure Monitor", None),
+        0x0f => ("Human Interface Device", Some("input-keyboard")),
+        0x10 => ("Glucose Meter", None),
+        0x11 => ("Running Walking Sensor", None),
+        0x12 => ("Cycling Sensor", None),
+        0x13 => ("Control Device", None),
+        0x14 => ("Network Device", Some("network-wireless")),
+        0x15 => ("Sensor", None),
+        0x16 => ("Light Fixture", None),
+        0x17 => ("Fan", None),
+        0x18 => ("HVAC", None),
+        0x19 => ("Air Conditioning", None),
+        0x1a => ("Humidifier", None),
+        0x1b => ("Heating", None),
+        0x1c => ("Access Control", None),
+        0x1d => ("Motorized Device", None),
+        0x1e => ("Power Device", None),
+        0x1f => ("Light Source", None),
+        0x20 => ("Window Covering", None),
+        0x21 => ("Audio Sink", Some("audio-speakers")),
+        0x22 => ("Audio Source", Some("audio-card")),
+        0x23 => ("Motorized Vehicle", None),
+        0x24 => ("Domestic Appliance", None),
+        0x25 => ("Wearable Audio Device", Some("audio-headphones")),
+        0x26 => ("Aircraft", None),
+        0x27 => ("AV Equipment", Some("video-display")),
+        0x28 => ("Display Equipment", Some("video-display")),
+        0x29 => ("Hearing Aid", Some("audio-headphones")),
+        0x2a => ("Gaming", Some("input-gaming")),
+        0x2b => ("Signage", Some("video-display")),
+        0x31 => ("Pulse Oximeter", None),
+        0x32 => ("Weight Scale", None),
+        0x33 => ("Personal Mobility Device", None),
+        0x34 => ("Continuous Glucose Monitor", None),
+        0x35 => ("Insulin Pump", None),
+        0x36 => ("Medication Delivery", None),
+        0x37 => ("Spirometer", None),
+        0x51 => ("Outdoor Sports Activity", None),
+        _ => ("Reserved", None),
+    };
+
+    let (subcategory_name, subcategory_icon) = match (category, subcategory) {
+        (0x02, 0x01) => (Some("Desktop"), None),
+        (0x02, 0x02) => (Some("Server"), None),
+        (0x02, 0x03) => (Some("Laptop"), None),
+        (0x02, 0x04) => (Some("Handheld PC"), None),
+        (0x02, 0x05) => (Some("Palm-size PC"), None),
+        (0x02, 0x06) => (Some("Wearable Computer"), None),
+        (0x02, 0x07) => (Some("Tablet"), None),
+        (0x02, 0x08) => (Some("Docking Station"), None),
+        (0x02, 0x09) => (Some("All in One"), None),
+        (0x02, 0x0e) => (Some("Mini PC"), None),
+        (0x03, 0x01) => (Some("Sports Watch"), None),
+        (0x03, 0x02) => (Some("Smartwatch"), None),
+        (0x0f, 0x01) => (Some("Keyboard"), Some("input-keyboard")),
+        (0x0f, 0x02) => (Some("Mouse"), Some("input-mouse")),
+        (0x0f, 0x03) => (Some("Joystick"), Some("input-gaming")),
+        (0x0f, 0x04) => (Some("Gamepad"), Some("input-gaming")),
+        (0x0f, 0x05) => (Some("Digitizer Tablet"), Some("input-tablet")),
+        (0x0f, 0x06) => (Some("Card Reader"), None),
+        (0x0f, 0x07) => (Some("Digital Pen"), Some("input-tablet")),
+        (0x0f, 0x08) => (Some("Barcode Scanner"), Some("scanner")),
+        (0x0f, 0x09) => (Some("Touchpad"), Some("input-touchpad")),
+        (0x0f, 0x0a) => (Some("Presentation Remote"), None),
+        (0x21, 0x01) => (Some("Speaker"), None),
+        (0x21, 0x02) => (Some("Soundbar"), None),
+        (0x21, 0x03) => (Some("Bookshelf Speaker"), None),
+        (0x21, 0x04) => (Some("Standmounted Speaker"), None),
+        (0x21, 0x05) => (Some("Speakerphone"), None),
+        (0x22, 0x01) => (Some("Microphone"), Some("audio-input-microphone")),
+        (0x25, 0x01) => (Some("Earbuds"), None),
+        (0x25, 0x02) => (Some("Headset"), Some("audio-headset")),
+        (0x25, 0x03) => (Some("Headphones"), None),
+        (0x25, 0x04) => (Some("Neck Band"), None),
+        (0x2a, 0x01) => (Some("Game Console"), None),
+        (0x2a, 0x02) => (Some("Handheld Game Console"), None),
+        _ => (None, None),
+    };
+
+    let description = match subcategory_name {
+        Some(subcategory_name) => format!("{}: {}", category_name, subcategory_name),
+        None => category_name.to_string(),
+    };
+    let kind = subcategory_name.or(Some(category_name).filter(|name| !["Unknown", "Reserved"].contains(name)));
+
+    (kind.map(str::to_string), description, subcategory_icon.or(category_icon).map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoded(kind: Option<&str>, description: &str, icon: Option<&str>) -> (Option<String>, String, Option<String>) {
+        (kind.map(str::to_string), description.to_string(), icon.map(str::to_string))
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(decode_class(0x240404), decoded(Some("Headset"), "Audio/Video: Headset", Some("audio-headset")));
+        assert_eq!(decode_class(0x5a020c), decoded(Some("Smartphone"), "Phone: Smartphone", Some("phone")));
+        assert_eq!(decode_class(0x0c010c), decoded(Some("Laptop"), "Computer: Laptop", Some("computer")));
+        assert_eq!(decode_class(0x040680), decoded(Some("Printer"), "Imaging: Printer", Some("printer")));
+        assert_eq!(decode_class(0x000704), decoded(Some("Wristwatch"), "Wearable: Wristwatch", None));
+    }
+
+    #[test]
+    fn classes_without_a_minor() {
+        assert_eq!(decode_class(0x000100), decoded(Some("Computer"), "Computer", Some("computer")));
+        assert_eq!(decode_class(0x000000), decoded(None, "Miscellaneous", None));
+        assert_eq!(decode_class(0x001f00), decoded(None, "Uncategorized", None));
+    }
+
+    #[test]
+    fn peripherals() {
+        assert_eq!(decode_class(0x002540), decoded(Some("Keyboard"), "Peripheral: Keyboard", Some("input-keyboard")));
+        assert_eq!(decode_class(0x002580), decoded(Some("Mouse"), "Peripheral: Mouse", Some("input-mouse")));
+        assert_eq!(decode_class(0x0025c0), decoded(Some("Keyboard and Mouse"), "Peripheral: Keyboard and Mouse", Some("input-keyboard")));
+        // the type part wins over the keyboard/pointer part
+        assert_eq!(decode_class(0x002508), decoded(Some("Gamepad"), "Peripheral: Gamepad", Some("input-gaming")));
+        assert_eq!(decode_class(0x002514), decoded(Some("Digitizer Tablet"), "Peripheral: Digitizer Tablet", Some("input-tablet")));
+    }
+
+    #[test]
+    fn service_classes() {
+        assert_eq!(decode_service_classes(0x240404), vec!["Rendering", "Audio"]);
+        assert_eq!(decode_service_classes(0x5a020c), vec!["Networking", "Capturing", "Object Transfer", "Telephony"]);
+        assert_eq!(decode_service_classes(0x002000 | 0x000100), vec!["Limited Discoverable"]);
+        assert!(decode_service_classes(0x000100).is_empty());
+    }
+
+    #[test]
+    fn appearances() {
+        assert_eq!(decode_appearance(0x03c1), decoded(Some("Keyboard"), "Human Interface Device: Keyboard", Some("input-keyboard")));
+        assert_eq!(decode_appearance(0x03c9), decoded(Some("Touchpad"), "Human Interface Device: Touchpad", Some("input-touchpad")));
+        assert_eq!(decode_appearance(0x0941), decoded(Some("Earbuds"), "Wearable Audio Device: Earbuds", Some("audio-headphones")));
+        assert_eq!(decode_appearance(0x00c2), decoded(Some("Smartwatch"), "Watch: Smartwatch", None));
+        assert_eq!(decode_appearance(0x0040), decoded(Some("Phone"), "Phone", Some("phone")));
+    }
+
+    #[test]
+    fn unknown_appearances() {
+        assert_eq!(decode_appearance(0x0000), decoded(None, "Unknown", None));
+        assert_eq!(decode_appearance(0xffc0), decoded(None, "Reserved", None));
+        // a subcategory that isn't known still gives the category
+        assert_eq!(decode_appearance(0x097f), decoded(Some("Wearable Audio Device"), "Wearable Audio Device", Some("audio-headphones")));
+    }
+
+    #[test]
+    fn vendors() {
+        assert_eq!(vendor_name("usb", 0x054c), "USB vendor 0x054c");
+        assert_eq!(vendor_name("mystery", 0x1234), "mystery vendor 0x1234");
+        assert_eq!(vendor_name("bluetooth", 0x004c), company_name(0x004c));
+        assert!(!company_name(0x004c).starts_with("Unknown company"));
+    }
+
+    #[test]
+    fn summaries() {
+        let identity = DeviceIdentity {
+            kind: Some("Headphones".to_string()),
+            vendor: Some("Sony Group Corporation".to_string()),
+            manufacturers: vec!["Apple, Inc.".to_string()],
+            ..DeviceIdentity::default()
+        };
+        assert_eq!(identity.summary(), "Headphones · Sony Group Corporation");
+
+        let identity = DeviceIdentity { vendor: None, ..identity };
+        assert_eq!(identity.maker().as_deref(), Some("Apple, Inc."));
+        assert_eq!(DeviceIdentity::default().summary(), "");
+    }
+}
@@ -0,0 +1,290 @@
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::{Duration, Instant};
+use gtk::glib;
+
+use crate::backup;
+use crate::error::OverskrideError;
+
+const BTPROTO_HCI: libc::c_int = 1;
+const SOL_HCI: libc::c_int = 0;
+const HCI_FILTER: libc::c_int = 2;
+const HCI_CHANNEL_RAW: u16 = 0;
+
+const HCI_COMMAND_PKT: u8 = 0x01;
+const HCI_EVENT_PKT: u8 = 0x04;
+const EVT_CMD_COMPLETE: u8 = 0x0e;
+const EVT_CMD_STATUS: u8 = 0x0f;
+
+/// `_IOR('H', 212, int)`, lists the adapter's connections
+const HCIGETCONNLIST: libc::c_ulong = 0x800448d4;
+const MAX_CONNECTIONS: usize = 16;
+
+const LE_LINK: u8 = 0x80;
+const HCI_LM_MASTER: u32 = 0x0001;
+const HCI_LM_AUTH: u32 = 0x0002;
+const HCI_LM_ENCRYPT: u32 = 0x0004;
+const HCI_LM_SECURE: u32 = 0x0020;
+
+const READ_RSSI: u16 = 0x1405;
+const READ_LINK_QUALITY: u16 = 0x1403;
+const READ_ENCRYPTION_KEY_SIZE: u16 = 0x1408;
+const READ_TRANSMIT_POWER_LEVEL: u16 = 0x0c2d;
+
+/// how long to wait for the controller to answer a command
+const COMMAND_TIMEOUT: Duration = Duration::from_millis(1000);
+
+#[repr(C)]
+struct SockaddrHci {
+    hci_family: libc::sa_family_t,
+    hci_dev: u16,
+    hci_channel: u16,
+}
+
+#[repr(C)]
+struct HciFilter {
+    type_mask: u32,
+    event_mask: [u32; 2],
+    opcode: u16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct HciConnInfo {
+    handle: u16,
+    bdaddr: [u8; 6],
+    link_type: u8,
+    out: u8,
+    state: u16,
+    link_mode: u32,
+}
+
+#[repr(C)]
+struct HciConnListReq {
+    dev_id: u16,
+    conn_num: u16,
+    conn_info: [HciConnInfo; MAX_CONNECTIONS],
+}
+
+/// what the controller says about a connection, anything it wouldn't answer being `None`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkInfo {
+    pub le: bool,
+    /// whether this adapter is the central (master) of the connection
+    pub central: bool,
+    pub encrypted: bool,
+    pub authenticated: bool,
+    /// secure connections, or at least a 16 byte key on older controllers
+    pub secure: bool,
+    /// in dBm for low energy, and for classic the distance from the controller's golden receive range (0 being in it)
+    pub rssi: Option<i8>,
+    /// 0 to 255, classic only
+    pub link_quality: Option<u8>,
+    /// the current transmit power of this adapter on the connection, in dBm
+    pub tx_power: Option<i8>,
+    /// in bytes, classic only, usually needs more privileges than Overskride has
+    pub key_size: Option<u8>,
+}
+
+/// the hci device number of an adapter, like 0 for "hci0"
+pub fn adapter_index(adapter_name: &str) -> Option<u16> {
+    adapter_name.strip_prefix("hci")?.parse::<u16>().ok()
+}
+
+/// Asks the controller about the connection to the device, `None` if it isn't connected.
+/// Uses a raw hci socket, which can send the few read-only commands the kernel lets anyone send.
+/// Blocks for up to a second per command, so run it off the main thread.
+pub fn read(adapter_index: u16, address: bluer::Address) -> io::Result<Option<LinkInfo>> {
+    let socket = open_socket(adapter_index)?;
+
+    let Some(connection) = find_connection(&socket, adapter_index, address)? else {
+        return Ok(None);
+    };
+
+    let handle = connection.handle.to_le_bytes();
+    let mut info = LinkInfo {
+        le: connection.link_type == LE_LINK,
+        central: connection.link_mode & HCI_LM_MASTER != 0,
+        encrypted: connection.link_mode & HCI_LM_ENCRYPT != 0,
+        authenticated: connection.link_mode & HCI_LM_AUTH != 0,
+        secure: connection.link_mode & HCI_LM_SECURE != 0,
+        ..LinkInfo::default()
+    };
+
+    // every answer starts with the status and the handle, then the value
+    info.rssi = send_command(&socket, READ_RSSI, &handle).ok().and_then(|answer| answer.get(3).map(|rssi| *rssi as i8));
+    info.tx_power = send_command(&socket, READ_TRANSMIT_POWER_LEVEL, &[handle[0], handle[1], 0]).ok().and_then(|answer| answer.get(3).map(|power| *power as i8));
+
+    if !info.le {
+        info.link_quality = send_command(&socket, READ_LINK_QUALITY, &handle).ok().and_then(|answer| answer.get(3).copied());
+        if info.encrypted {
+            info.key_size = send_command(&socket, READ_ENCRYPTION_KEY_SIZE, &handle).ok().and_then(|answer| answer.get(3).copied());
+        }
+    }
+
+    Ok(Some(info))
+}
+
+fn open_socket(adapter_index: u16) -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::socket(libc::AF_BLUETOOTH, libc::SOCK_RAW | libc::SOCK_CLOEXEC, BTPROTO_HCI) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let address = SockaddrHci {
+        hci_family: libc::AF_BLUETOOTH as libc::sa_family_t,
+        hci_dev: adapter_index,
+        hci_channel: HCI_CHANNEL_RAW,
+    };
+    let bound = unsafe {
+        libc::bind(socket.as_raw_fd(), &address as *const SockaddrHci as *const libc::sockaddr, std::mem::size_of::<SockaddrHci>() as libc::socklen_t)
+    };
+    if bound < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // only the answers to commands are needed
+    let filter = HciFilter {
+        type_mask: 1 << HCI_EVENT_PKT,
+        event_mask: [(1 << EVT_CMD_COMPLETE) | (1 << EVT_CMD_STATUS), 0],
+        opcode: 0,
+    };
+    let filtered = unsafe {
+        libc::setsockopt(socket.as_raw_fd(), SOL_HCI, HCI_FILTER, &filter as *const HciFilter as *const libc::c_void, std::mem::size_of::<HciFilter>() as libc::socklen_t)
+    };
+    if filtered < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(socket)
+}
+
+fn find_connection(socket: &OwnedFd, adapter_index: u16, address: bluer::Address) -> io::Result<Option<HciConnInfo>> {
+    let mut request = HciConnListReq {
+        dev_id: adapter_index,
+        conn_num: MAX_CONNECTIONS as u16,
+        conn_info: [HciConnInfo::default(); MAX_CONNECTIONS],
+    };
+
+    if unsafe { libc::ioctl(socket.as_raw_fd(), HCIGETCONNLIST, &mut request as *mut HciConnListReq) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // the kernel keeps addresses the other way around
+    let mut bdaddr = address.0;
+    bdaddr.reverse();
+
+    let count = (request.conn_num as usize).min(MAX_CONNECTIONS);
+    Ok(request.conn_info[..count].iter().find(|connection| connection.bdaddr == bdaddr).copied())
+}
+
+/// Sends a command and waits for its answer, returning its parameters (starting with the status) if it succeeded.
+fn send_command(socket: &OwnedFd, opcode: u16, parameters: &[u8]) -> io::Result<Vec<u8>> {
+    let mut packet = vec![HCI_COMMAND_PKT];
+    packet.extend_from_slice(&opcode.to_le_bytes());
+    packet.push(parameters.len() as u8);
+    packet.extend_from_slice(parameters);
+
+    if unsafe { libc::write(socket.as_raw_fd(), packet.as_ptr() as *const libc::c_void, packet.len()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let mut buffer = [0u8; 260];
+
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let mut poll = libc::pollfd { fd: socket.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        let ready = unsafe { libc::poll(&mut poll, 1, left.as_millis() as libc::c_int) };
+        if ready < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if ready == 0 {
+            break;
+        }
+
+        let read = unsafe { libc::read(socket.as_raw_fd(), buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+        if read < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let event = &buffer[..read as usize];
+
+        // [packet type, event, length, ...]
+        if event.len() < 3 || event[0] != HCI_EVENT_PKT {
+            continue;
+        }
+
+        match event[1] {
+            // [commands allowed, opcode, status, return parameters]
+            EVT_CMD_COMPLETE if event.len() >= 7 && u16::from_le_bytes([event[4], event[5]]) == opcode => {
+                let answer = event[6..].to_vec();
+                return match answer[0] {
+                    0 => Ok(answer),
+                    status => Err(io::Error::other(format!("controller answered with status {:#04x}", status))),
+                };
+            }
+            // [status, commands allowed, opcode], only sent here if the command failed
+            EVT_CMD_STATUS if event.len() >= 7 && u16::from_le_bytes([event[5], event[6]]) == opcode => {
+                return Err(io::Error::other(format!("controller refused the command with status {:#04x}", event[3])));
+            }
+            _ => continue,
+        }
+    }
+
+    Err(io::Error::new(io::ErrorKind::TimedOut, "controller didn't answer"))
+}
+
+/// LE connection parameters bluetoothd stored for a device, as the device last asked for them.
+/// These are its preferences, the connection itself may be using others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionParameters {
+    /// in units of 1.25 ms
+    pub min_interval: i32,
+    pub max_interval: i32,
+    /// how many connection events the device may skip
+    pub latency: i32,
+    /// in units of 10 ms
+    pub timeout: i32,
+}
+
+impl ConnectionParameters {
+    /// the parameters as shown to the user, in milliseconds
+    pub fn describe(&self) -> String {
+        format!(
+            "interval {:.2} to {:.2} ms, latency {}, timeout {} ms",
+            self.min_interval as f32 * 1.25,
+            self.max_interval as f32 * 1.25,
+            self.latency,
+            self.timeout * 10
+        )
+    }
+
+    /// reads them from the device's info file in bluetoothd's storage, `None` if it has none
+    pub fn from_info_file(info: &str) -> Option<Self> {
+        let key_file = glib::KeyFile::new();
+        key_file.load_from_data(info, glib::KeyFileFlags::NONE).ok()?;
+
+        Some(ConnectionParameters {
+            min_interval: key_file.integer("ConnectionParameters", "MinInterval").ok()?,
+            max_interval: key_file.integer("ConnectionParameters", "MaxInterval").ok()?,
+            latency: key_file.integer("ConnectionParameters", "Latency").ok()?,
+            timeout: key_file.integer("ConnectionParameters", "Timeout").ok()?,
+        })
+    }
+}
+
+/// Reads the LE connection parameters bluetoothd stored for the device, through the keys helper since its storage is root only.
+/// Only that section of the device's info is read, not its keys. This asks for the password.
+pub async fn read_connection_parameters(adapter_name: String, address: bluer::Address) -> Result<Option<ConnectionParameters>, OverskrideError> {
+    let adapter_address = bluer::Session::new().await?.adapter(&adapter_name)?.address().await?;
+
+    let output = tokio::task::spawn_blocking(move || backup::run_keys_helper(&["connection-parameters", &adapter_address.to_string(), &address.to_string()], None)).await
+        .map_err(|_| OverskrideError::ConnectionParametersFailed)?
+        .map_err(|err| if err == OverskrideError::KeysNotAuthorized {
+            OverskrideError::ConnectionParametersNotAuthorized
+        } else {
+            OverskrideError::ConnectionParametersFailed
+        })?;
+
+    Ok(ConnectionParameters::from_info_file(&output))
+}
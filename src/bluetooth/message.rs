@@ -1,4 +1,16 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::broadcast::BroadcastSource;
+use crate::telephony::CallInfo;
+use crate::rfkill::RfkillState;
+use crate::advertisement::AdvertisedName;
+use crate::bluetooth_settings::DiscoverySettings;
+use crate::profiles::ProfileState;
+use crate::error::OverskrideError;
+use crate::supervisor::{Agent, AgentHealth};
+use crate::windows_keys::KeySync;
+use crate::device_store::DeviceSettings;
 
 pub enum Message {
     #[allow(dead_code)]
@@ -16,8 +28,8 @@ pub enum Message {
     SwitchRssi(String, i32),
     /// Moves between pages, ie changes the values of the rows and icons to `page: Option<String>` and `icon: Option<String>`
     SwitchPage(Option<String>, Option<String>),
-    /// Removes the device matching the supplied name (`name: String`)
-    RemoveDevice(String, bluer::Address),
+    /// Removes the device matching the supplied name (`name: String`) found by the adapter with the given [name](String)
+    RemoveDevice(String, bluer::Address, String),
     /// Adds a new device from the properties of [device](bluer::Device)
     AddRow(bluer::Device),
     /// Changes the adapter's powered state to `bool`
@@ -30,8 +42,9 @@ pub enum Message {
     SwitchAdapterName(String, String),
     /// Adds all currently discovered adapters from [adapters_lut](HashMap) to the adapter list in the settings
     PopulateAdapterExpander(HashMap<String, String>),
-    /// Displays an error (or a message) of [message](String) with a [priority](adw::ToastPriority) as a [toast](adw::Toast)
-    PopupError(String, adw::ToastPriority),
+    /// Displays an [error](OverskrideError) (or a message) with a [priority](adw::ToastPriority) as a [toast](adw::Toast),
+    /// with a way to see how to fix it if there is one
+    PopupError(OverskrideError, adw::ToastPriority),
     /// Checks if there are devices and changes the "no bluetooth devices found" image accordingly
     UpdateListBoxImage(),
     /// Requests a pairing pincode using [request](bluer::agent::RequestPinCode) as input
@@ -100,5 +113,94 @@ pub enum Message {
     /// Updates the `LeverBar` of the device to match the battery level reported
     UpdateBatteryLevel(i8),
     /// Sets the "hide unknown devices" settings according to the given `bool`
-    SetHideUnknownDevices(bool)
+    SetHideUnknownDevices(bool),
+    /// Sets the "act as a speaker" mode according to the given `bool`
+    SetAudioSinkMode(bool),
+    /// Shows the name of the phone currently streaming audio to us, or hides it if [None](Option::None)
+    SetStreamingDevice(Option<String>),
+    /// Sets the phone calls integration according to the given `bool`
+    SetCallIntegration(bool),
+    /// Shows a new call or updates an existing one, notifying about it if it's ringing
+    UpdateCall(CallInfo),
+    /// Hides the call with the given [path](String), withdrawing its notification
+    RemoveCall(String),
+    /// Adds a found broadcast to the broadcasts list, or updates it if it's already there
+    AddBroadcast(BroadcastSource),
+    /// Removes the broadcast with the given [address](bluer::Address) from the broadcasts list
+    RemoveBroadcast(bluer::Address),
+    /// Sets whether we're listening to the broadcast with the given [address](bluer::Address)
+    SetBroadcastJoined(bluer::Address, bool),
+    /// Switches between scanning for everything and scanning only for broadcasts according to the given `bool`
+    SetBroadcastScanning(bool),
+    /// Shows the LC3 configuration of each LE Audio stream of the current device, hiding it if there are none
+    SetLc3Configuration(Vec<String>),
+    /// Sets the "show all adapters" mode according to the given `bool`
+    SetShowAllAdapters(bool),
+    /// Removes every device found by the adapter with the given [name](String), used when an adapter is unplugged
+    RemoveAdapterDevices(String),
+    /// Fills the "connect using" list with the given adapters (alias => name), selecting the [adapter](String) the device was found with
+    SetConnectUsing(HashMap<String, String>, String),
+    /// Shows whether bluetooth is blocked by rfkill, offering to unblock it if it's only soft blocked
+    SetRfkillState(RfkillState),
+    /// Shows or hides the "no adapter" banner according to the given `bool`
+    SetAdapterMissing(bool),
+    /// Sets whether closing the window keeps Overskride running in the tray according to the given `bool`
+    SetRunInBackground(bool),
+    /// Shows the window again after it was closed to the tray
+    ShowWindow(),
+    /// Quits Overskride entirely, even if it's running in the background
+    QuitApplication(),
+    /// Shows the window with the device that has the given [address](bluer::Address) selected
+    ShowDevice(bluer::Address),
+    /// Asks which paired device the given files should be sent to, sending them from the [adapter](bluer::Address)
+    /// ### Arguments
+    /// * `files` - a [Vec](Vec) of the file paths to send
+    /// * `source` - the [address](bluer::Address) of the adapter to send from
+    /// * `devices` - a [Vec](Vec) of the paired devices as (address, name, connected)
+    PickDeviceForFiles(Vec<String>, bluer::Address, Vec<(bluer::Address, String, bool)>),
+    /// Sets whether the device with the given [address](bluer::Address) is paired, refiltering the device list
+    SwitchRowPaired(bluer::Address, bool),
+    /// Sets whether the device with the given [address](bluer::Address) is trusted, refiltering the device list
+    SwitchRowTrusted(bluer::Address, bool),
+    /// Renames a nameless [device](bluer::Device) with what its advertisements say it is, adding its row if it was hidden as unknown
+    SetAdvertisedName(bluer::Device, AdvertisedName),
+    /// Saves the new [discovery settings](DiscoverySettings) and restarts discovery with them
+    SetDiscoverySettings(DiscoverySettings),
+    /// Starts (`true`) or stops (`false`) searching for devices, the search stops by itself after the scan timeout
+    SetScanning(bool),
+    /// Changes the keep connected switch's active to `bool`
+    SwitchKeepConnected(bool),
+    /// Sets whether the device with the given [address](bluer::Address) is reconnected when it disconnects, saving it
+    SetKeepConnected(bluer::Address, bool),
+    /// Lets the user know that reconnecting to the device with the given name was given up on
+    ReconnectGaveUp(String),
+    /// Shows the audio priority switch if the first `bool` is true (the device plays audio), and sets its active to the second
+    SwitchAudioPriority(bool, bool),
+    /// Adds (`true`) or removes (`false`) the device with the given [address](bluer::Address) from the audio priority list, saving it
+    SetAudioPriority(bluer::Address, bool),
+    /// Moves the device with the given [address](bluer::Address) in the audio priority list by `i32` places, negative being more important
+    MoveAudioPriority(bluer::Address, i32),
+    /// Sets when the most important audio device is connected, one of "off", "startup" or "always", saving it
+    SetAudioSwitching(String),
+    /// Sets where distances are estimated, one of the [environments](crate::rssi::ENVIRONMENTS), saving it
+    SetDistanceEnvironment(String),
+    /// Fills the profiles expander with the given [profiles](ProfileState), hiding it if there are none
+    PopulateProfilesList(Vec<ProfileState>),
+    /// Shows how the given [agent](Agent) is doing in the settings
+    SetAgentHealth(Agent, AgentHealth),
+    /// Asks where to back the devices up to (`true`) or which backup to restore them from (`false`), then does it
+    ChooseBackupFile(bool),
+    /// Saves the keep connected and audio priority settings restored from a backup, refreshing the devices list,
+    /// `true` if its keys were left out because it was made on a different adapter
+    BackupImported(bool),
+    /// Asks for the SYSTEM registry file of a windows install to copy its pairing keys from
+    ChooseWindowsHive(),
+    /// Shows which devices would get windows' keys from the hive at the given [path](PathBuf), copying them if the user agrees
+    PreviewWindowsKeys(PathBuf, Vec<KeySync>),
+    /// Fills the "Your Settings" rows of the device page with the device's [settings](DeviceSettings)
+    SwitchDeviceSettings(DeviceSettings),
+    /// Changes the icon of the device with the given [address](bluer::Address) to the icon name, in the list and on its page
+    SwitchDeviceIcon(bluer::Address, String),
+    /// Lets the user know the battery of the device with the given name is down to `u8` percent
+    BatteryLow(String, u8),
 } 
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use bluer::Uuid;
+use dbus::blocking::{Connection, stdintf::org_freedesktop_dbus::Properties};
+use uuid::uuid;
+
+use crate::audio_profiles::AudioProfiles;
+use crate::broadcast;
+
+/// how bluez can tell whether a profile is connected, it doesn't have one property for all of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProfileCheck {
+    /// a media transport exists with this (our side's) uuid, or the sound server's card profile contains one of the words
+    Audio(Uuid, &'static [&'static str]),
+    /// a "Connected" property on this interface of the device
+    Interface(&'static str),
+    /// nothing to ask, it's up as long as the device is connected
+    Device,
+}
+
+/// a profile that can be connected on its own, named after what it's for
+struct Profile {
+    uuid: Uuid,
+    name: &'static str,
+    check: ProfileCheck,
+}
+
+const A2DP_WORDS: &[&str] = &["a2dp"];
+const HEADSET_WORDS: &[&str] = &["headset", "handsfree", "head-unit", "head_unit", "hfp", "hsp"];
+
+/// every profile bluez connects by itself, the uuids being the ones the other device advertises
+const PROFILES: [Profile; 12] = [
+    Profile { uuid: uuid!("0000110b-0000-1000-8000-00805f9b34fb"), name: "Audio Playback (A2DP)", check: ProfileCheck::Audio(uuid!("0000110a-0000-1000-8000-00805f9b34fb"), A2DP_WORDS) },
+    Profile { uuid: uuid!("0000110a-0000-1000-8000-00805f9b34fb"), name: "Audio From Device (A2DP)", check: ProfileCheck::Audio(uuid!("0000110b-0000-1000-8000-00805f9b34fb"), A2DP_WORDS) },
+    Profile { uuid: uuid!("0000111e-0000-1000-8000-00805f9b34fb"), name: "Hands-Free (HFP)", check: ProfileCheck::Audio(uuid!("0000111f-0000-1000-8000-00805f9b34fb"), HEADSET_WORDS) },
+    Profile { uuid: uuid!("00001108-0000-1000-8000-00805f9b34fb"), name: "Headset (HSP)", check: ProfileCheck::Audio(uuid!("00001112-0000-1000-8000-00805f9b34fb"), HEADSET_WORDS) },
+    Profile { uuid: uuid!("0000111f-0000-1000-8000-00805f9b34fb"), name: "Hands-Free Gateway (HFP)", check: ProfileCheck::Audio(uuid!("0000111e-0000-1000-8000-00805f9b34fb"), HEADSET_WORDS) },
+    Profile { uuid: uuid!("00001112-0000-1000-8000-00805f9b34fb"), name: "Headset Gateway (HSP)", check: ProfileCheck::Audio(uuid!("00001108-0000-1000-8000-00805f9b34fb"), HEADSET_WORDS) },
+    Profile { uuid: uuid!("0000110e-0000-1000-8000-00805f9b34fb"), name: "Media Controls (AVRCP)", check: ProfileCheck::Interface("org.bluez.MediaControl1") },
+    Profile { uuid: uuid!("00001124-0000-1000-8000-00805f9b34fb"), name: "Input Device (HID)", check: ProfileCheck::Device },
+    Profile { uuid: uuid!("00001812-0000-1000-8000-00805f9b34fb"), name: "Input Device (HID over GATT)", check: ProfileCheck::Device },
+    Profile { uuid: uuid!("00001116-0000-1000-8000-00805f9b34fb"), name: "Network Access (PAN)", check: ProfileCheck::Interface("org.bluez.Network1") },
+    Profile { uuid: uuid!("00001117-0000-1000-8000-00805f9b34fb"), name: "Group Network (PAN)", check: ProfileCheck::Interface("org.bluez.Network1") },
+    Profile { uuid: uuid!("00001115-0000-1000-8000-00805f9b34fb"), name: "Personal Network (PAN)", check: ProfileCheck::Interface("org.bluez.Network1") },
+];
+
+/// a profile a device supports, and whether it's connected right now
+#[derive(Debug, Clone)]
+pub struct ProfileState {
+    pub uuid: Uuid,
+    pub name: String,
+    pub active: bool,
+}
+
+/// Lists the profiles out of `uuids` that can be connected one by one, with which of them are active.
+/// This talks to bluez and the sound server directly, so it blocks.
+pub fn get_profile_states(address: bluer::Address, adapter_name: String, uuids: &HashSet<Uuid>, connected: bool) -> Vec<ProfileState> {
+    let supported = PROFILES.iter().filter(|profile| uuids.contains(&profile.uuid)).collect::<Vec<&Profile>>();
+    if supported.is_empty() {
+        return vec![];
+    }
+
+    let transport_uuids = broadcast::get_transports(address, &adapter_name).into_iter()
+        .filter_map(|(_, properties)| dbus::arg::prop_cast::<String>(&properties, "UUID").and_then(|uuid| uuid.parse::<Uuid>().ok()))
+        .collect::<Vec<Uuid>>();
+
+    // only asked for when it's needed, connecting to the sound server isn't free
+    let card_profile = if connected && supported.iter().any(|profile| matches!(profile.check, ProfileCheck::Audio(..))) {
+        AudioProfiles::new(address.to_string()).map(|profiles| profiles.active_profile.to_lowercase()).unwrap_or_default()
+    } else {
+        String::new()
+    };
+
+    let device_path = "/org/bluez/".to_string() + &adapter_name + "/dev_" + &address.to_string().replace(':', "_");
+
+    supported.into_iter().map(|profile| {
+        let active = connected && match profile.check {
+            ProfileCheck::Audio(local_uuid, words) => {
+                transport_uuids.contains(&local_uuid) || words.iter().any(|word| card_profile.contains(word))
+            }
+            ProfileCheck::Interface(interface) => is_interface_connected(&device_path, interface),
+            ProfileCheck::Device => true,
+        };
+
+        ProfileState { uuid: profile.uuid, name: profile.name.to_string(), active }
+    }).collect()
+}
+
+fn is_interface_connected(device_path: &str, interface: &str) -> bool {
+    let Ok(conn) = Connection::new_system() else {
+        return false;
+    };
+
+    let proxy = conn.with_proxy("org.bluez", device_path, Duration::from_millis(5000));
+    proxy.get::<bool>(interface, "Connected").unwrap_or(false)
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use dbus::blocking::Connection;
+
+use crate::device_store::{self, DeviceSettings};
+use crate::event_log::{self, Source};
+use crate::{link_info, rssi};
+
+/// how long a device has to stay under its away threshold before it counts as gone, so one bad sample doesn't lock
+const AWAY_DELAY: Duration = Duration::from_secs(10);
+/// while scanning, a disconnected device not heard from for this long is out of range
+const SIGNAL_LOST: Duration = Duration::from_secs(45);
+/// the least the back threshold is over the away one, so a device sitting right at the edge doesn't flip between them
+const MIN_HYSTERESIS: i16 = 5;
+
+/// a device going out of or coming back into range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProximityChange {
+    Away,
+    Back,
+}
+
+/// what's known about the signal of a device with proximity actions
+struct Tracked {
+    near: bool,
+    /// when the smoothed signal dropped under the away threshold, if it's still under it
+    below_since: Option<Instant>,
+    last_seen: Instant,
+    /// it came back, but the session is only unlocked once it's connected over an encrypted link
+    unlock_pending: bool,
+}
+
+impl Tracked {
+    fn new(now: Instant) -> Self {
+        Tracked {
+            near: true,
+            below_since: None,
+            last_seen: now,
+            unlock_pending: false,
+        }
+    }
+
+    fn go_away(&mut self) {
+        self.near = false;
+        self.below_since = None;
+        self.unlock_pending = false;
+    }
+
+    fn come_back(&mut self) {
+        self.near = true;
+        self.below_since = None;
+        self.unlock_pending = true;
+    }
+}
+
+static TRACKED: Mutex<Option<HashMap<bluer::Address, Tracked>>> = Mutex::new(None);
+
+/// the away and back thresholds of the device, with back kept far enough over away
+fn thresholds(settings: &DeviceSettings) -> (f32, f32) {
+    let away = settings.proximity_away_rssi;
+    let back = settings.proximity_back_rssi.max(away + MIN_HYSTERESIS);
+    (away as f32, back as f32)
+}
+
+/// Feeds the device's new smoothed RSSI (see [rssi](crate::rssi)) in, returning whether it just went away or came back.
+/// Devices start out near when they're first heard from, so nothing is unlocked when Overskride starts.
+pub fn update_rssi(address: bluer::Address, smoothed_rssi: i16) -> Option<ProximityChange> {
+    let settings = device_store::get(address);
+    let mut tracked = TRACKED.lock().unwrap();
+    let tracked = tracked.get_or_insert_with(HashMap::new);
+
+    if !settings.proximity {
+        tracked.remove(&address);
+        return None;
+    }
+
+    let (away, back) = thresholds(&settings);
+    let now = Instant::now();
+    let smoothed_rssi = smoothed_rssi as f32;
+    let device = tracked.entry(address).or_insert(Tracked::new(now));
+
+    device.last_seen = now;
+
+    if device.near {
+        if smoothed_rssi >= away {
+            device.below_since = None;
+            return None;
+        }
+
+        let since = *device.below_since.get_or_insert(now);
+        if now.duration_since(since) >= AWAY_DELAY {
+            device.go_away();
+            return Some(ProximityChange::Away);
+        }
+    }
+    else if smoothed_rssi >= back {
+        device.come_back();
+        return Some(ProximityChange::Back);
+    }
+
+    None
+}
+
+/// A connected device is in range whatever its signal says, so it's near again if it wasn't.
+fn update_connected(address: bluer::Address) -> Option<ProximityChange> {
+    if !device_store::get(address).proximity {
+        return None;
+    }
+
+    let mut tracked = TRACKED.lock().unwrap();
+    let device = tracked.get_or_insert_with(HashMap::new).entry(address).or_insert(Tracked::new(Instant::now()));
+
+    device.last_seen = Instant::now();
+    device.below_since = None;
+
+    if device.near {
+        return None;
+    }
+    device.come_back();
+
+    Some(ProximityChange::Back)
+}
+
+/// Asks the adapter's controller for the signal of the `connected` devices (with whether they're paired),
+/// which stop advertising so bluez has none for them.
+/// Low energy links answer in dBm and go through the filter like any other sample, the rest count as near.
+/// Devices that came back get the session unlocked here, once their link is encrypted with the keys from pairing.
+/// Returns the devices that went away or came back.
+pub async fn sample_connected(adapter_name: &str, connected: Vec<(bluer::Address, bool)>) -> Vec<(bluer::Address, ProximityChange)> {
+    let Some(adapter_index) = link_info::adapter_index(adapter_name) else {
+        return vec![];
+    };
+
+    let samples = tokio::task::spawn_blocking(move || {
+        connected.into_iter()
+            .map(|(address, paired)| {
+                let info = link_info::read(adapter_index, address).ok().flatten();
+                let rssi = info.as_ref().filter(|info| info.le).and_then(|info| info.rssi);
+                let verified = paired && info.is_some_and(|info| info.encrypted);
+                (address, rssi, verified)
+            })
+            .collect::<Vec<(bluer::Address, Option<i8>, bool)>>()
+    }).await.unwrap_or_default();
+
+    let mut changes = vec![];
+    for (address, rssi, verified) in samples {
+        let change = match rssi {
+            Some(rssi) => update_rssi(address, rssi::add_sample(address, rssi as i16)),
+            None => update_connected(address),
+        };
+        if let Some(change) = change {
+            changes.push((address, change));
+        }
+
+        if verified && take_pending_unlock(address) {
+            unlock(address);
+        }
+    }
+
+    changes
+}
+
+/// whether the device came back and is still near without the session being unlocked for it yet, so it's unlocked only once
+fn take_pending_unlock(address: bluer::Address) -> bool {
+    let mut tracked = TRACKED.lock().unwrap();
+    let Some(device) = tracked.as_mut().and_then(|tracked| tracked.get_mut(&address)) else {
+        return false;
+    };
+
+    let pending = device.near && device.unlock_pending;
+    device.unlock_pending = false;
+    pending
+}
+
+/// Looks for devices that went away without a sample saying so: ones under their threshold for long enough,
+/// and while `discovering`, disconnected ones that stopped being heard from. Returns the devices that went away.
+pub fn check_lost(discovering: bool, connected: &[bluer::Address]) -> Vec<bluer::Address> {
+    let mut tracked = TRACKED.lock().unwrap();
+    let Some(tracked) = tracked.as_mut() else {
+        return vec![];
+    };
+
+    // devices whose proximity actions were turned off since their last sample
+    tracked.retain(|address, _| device_store::get(*address).proximity);
+
+    let now = Instant::now();
+    let mut lost = vec![];
+
+    for (address, device) in tracked.iter_mut().filter(|(_, device)| device.near) {
+        // without discovery bluez doesn't update the signal at all, so silence doesn't mean anything,
+        // and connected devices don't advertise, their signal is sampled from the connection instead
+        if !discovering || connected.contains(address) {
+            device.last_seen = now;
+        }
+
+        let under_too_long = device.below_since.is_some_and(|since| now.duration_since(since) >= AWAY_DELAY);
+        if under_too_long || now.duration_since(device.last_seen) >= SIGNAL_LOST {
+            device.go_away();
+            lost.push(*address);
+        }
+    }
+
+    lost
+}
+
+/// the device was removed by bluez, which it does with devices it stopped seeing, so it went away if it was near
+pub fn lost(address: bluer::Address) -> Option<ProximityChange> {
+    let mut tracked = TRACKED.lock().unwrap();
+    let device = tracked.as_mut()?.get_mut(&address)?;
+
+    if !device.near {
+        return None;
+    }
+    device.go_away();
+
+    Some(ProximityChange::Away)
+}
+
+/// Runs the device's actions for it going away or coming back, in their own thread since commands may take a while.
+/// Unlocking isn't one of them, anyone can advertise with the device's address, see [sample_connected](sample_connected).
+pub fn run_actions(address: bluer::Address, change: ProximityChange) {
+    let settings = device_store::get(address);
+    let change_name = match change {
+        ProximityChange::Away => "away",
+        ProximityChange::Back => "back",
+    };
+    event_log::info(Source::Device, "proximity changed, running its actions").device(address).field("proximity", change_name).record();
+
+    std::thread::spawn(move || {
+        let command = match change {
+            ProximityChange::Away => settings.proximity_away_command,
+            ProximityChange::Back => settings.proximity_back_command,
+        };
+
+        if change == ProximityChange::Away && settings.proximity_lock {
+            call_session(address, "Lock");
+        }
+
+        if !command.trim().is_empty() {
+            let result = Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .env("OVERSKRIDE_DEVICE", address.to_string())
+                .env("OVERSKRIDE_PROXIMITY", change_name)
+                .status();
+
+            if let Err(err) = result {
+                event_log::warning(Source::App, "cannot run the proximity command").device(address).field("command", &command).field("error", err).record();
+            }
+        }
+    });
+}
+
+/// unlocks the session for the device if it's set to, in its own thread like the other actions
+fn unlock(address: bluer::Address) {
+    if !device_store::get(address).proximity_unlock {
+        return;
+    }
+
+    event_log::info(Source::Device, "connected over an encrypted link, unlocking the session").device(address).record();
+    std::thread::spawn(move || call_session(address, "Unlock"));
+}
+
+/// calls `method` (Lock or Unlock) on the logind session Overskride runs in
+fn call_session(address: bluer::Address, method: &str) {
+    let result = Connection::new_system().and_then(|conn| {
+        let proxy = conn.with_proxy("org.freedesktop.login1", "/org/freedesktop/login1/session/auto", Duration::from_millis(5000));
+        proxy.method_call::<(), _, _, _>("org.freedesktop.login1.Session", method, ())
+    });
+
+    if let Err(err) = result {
+        event_log::warning(Source::App, "cannot lock or unlock the session").device(address).field("method", method).field("error", err.message().unwrap_or_default()).record();
+    }
+}
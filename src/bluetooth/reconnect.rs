@@ -0,0 +1,95 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use async_channel::Sender;
+use crate::message::Message;
+use crate::event_log::{self, Source};
+use crate::window::OVERSKRIDE_PROPS;
+
+/// how many times reconnecting is tried before giving up
+const MAX_ATTEMPTS: u32 = 5;
+/// the wait before the first attempt, doubled after every attempt after it
+const FIRST_DELAY: Duration = Duration::from_secs(2);
+
+/// devices being reconnected right now, so each device only has one reconnection going at a time
+static RECONNECTING: Mutex<Vec<bluer::Address>> = Mutex::new(Vec::new());
+/// devices the user disconnected (or removed), which shouldn't be connected back behind their back
+static USER_DISCONNECTED: Mutex<Vec<bluer::Address>> = Mutex::new(Vec::new());
+
+pub fn is_kept_connected(address: bluer::Address) -> bool {
+    OVERSKRIDE_PROPS.lock().unwrap().keep_connected.contains(&address)
+}
+
+/// marks the next disconnect of this device as wanted, so it's left disconnected
+pub fn user_disconnected(address: bluer::Address) {
+    let mut user_disconnected = USER_DISCONNECTED.lock().unwrap();
+    if !user_disconnected.contains(&address) {
+        user_disconnected.push(address);
+    }
+}
+
+/// once a device is connected again, whatever connected it, losing it should be undone again
+pub fn connected(address: bluer::Address) {
+    USER_DISCONNECTED.lock().unwrap().retain(|other| *other != address);
+}
+
+fn should_reconnect(address: bluer::Address) -> bool {
+    is_kept_connected(address) && !USER_DISCONNECTED.lock().unwrap().contains(&address)
+}
+
+/// Connects a "keep connected" device back, retrying with a growing delay in between.
+/// Gives up after a few attempts, letting the user know with a notification.
+pub async fn keep_connected(address: bluer::Address, adapter_name: String, sender: Sender<Message>) -> bluer::Result<()> {
+    if !should_reconnect(address) {
+        return Ok(());
+    }
+
+    {
+        let mut reconnecting = RECONNECTING.lock().unwrap();
+        if reconnecting.contains(&address) {
+            return Ok(());
+        }
+        reconnecting.push(address);
+    }
+
+    let result = try_reconnecting(address, adapter_name, &sender).await;
+    RECONNECTING.lock().unwrap().retain(|other| *other != address);
+
+    result
+}
+
+async fn try_reconnecting(address: bluer::Address, adapter_name: String, sender: &Sender<Message>) -> bluer::Result<()> {
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let device = adapter.device(address)?;
+    let mut delay = FIRST_DELAY;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+
+        // stop if keeping it connected was turned off, or it's back already
+        if !should_reconnect(address) || !adapter.is_powered().await? || device.is_connected().await? {
+            return Ok(());
+        }
+
+        // while discovering, no signal means it's out of range and connecting would only time out
+        if adapter.is_discovering().await? && device.rssi().await?.is_none() {
+            event_log::debug(Source::Device, "out of range, not reconnecting yet").device(address).field("attempt", attempt).record();
+            continue;
+        }
+
+        match device.connect().await {
+            Ok(()) => {
+                event_log::info(Source::Device, "reconnected").device(address).field("attempts", attempt).record();
+                return Ok(());
+            }
+            Err(err) => {
+                event_log::warning(Source::Device, "cannot reconnect").device(address).field("attempt", attempt).field("error", &err).record();
+            }
+        }
+    }
+
+    let name = device.alias().await?;
+    sender.send(Message::ReconnectGaveUp(name)).await.expect("cannot send message");
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use async_channel::Sender;
+use futures::executor::block_on;
+use crate::message::Message;
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
+
+const RFKILL_DEVICE: &str = "/dev/rfkill";
+
+// from linux/rfkill.h
+const RFKILL_TYPE_BLUETOOTH: u8 = 2;
+const RFKILL_OP_ADD: u8 = 0;
+const RFKILL_OP_DEL: u8 = 1;
+const RFKILL_OP_CHANGE: u8 = 2;
+const RFKILL_OP_CHANGE_ALL: u8 = 3;
+/// size of `struct rfkill_event`, newer kernels append more fields which we don't need
+const RFKILL_EVENT_SIZE: usize = 8;
+
+/// whether the bluetooth radios are blocked, soft blocks can be lifted from software, hard blocks need a physical switch
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RfkillState {
+    pub soft_blocked: bool,
+    pub hard_blocked: bool,
+}
+
+impl RfkillState {
+    pub fn is_blocked(&self) -> bool {
+        self.soft_blocked || self.hard_blocked
+    }
+}
+
+/// one `struct rfkill_event`, an rfkill switch being added, removed, or changing state
+struct RfkillEvent {
+    index: u32,
+    kind: u8,
+    op: u8,
+    soft: bool,
+    hard: bool,
+}
+
+impl RfkillEvent {
+    fn from_bytes(bytes: &[u8; RFKILL_EVENT_SIZE]) -> Self {
+        RfkillEvent {
+            index: u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            kind: bytes[4],
+            op: bytes[5],
+            soft: bytes[6] != 0,
+            hard: bytes[7] != 0,
+        }
+    }
+}
+
+/// Watches `/dev/rfkill` for bluetooth radios getting blocked or unblocked, updating the UI on every change.
+/// The kernel sends an "add" event for every existing switch first, so the initial state is reported too.
+/// This blocks forever, so run it in its own thread.
+pub fn watch_rfkill(sender: Sender<Message>) -> std::io::Result<()> {
+    let mut file = File::open(RFKILL_DEVICE)?;
+    let mut switches: HashMap<u32, RfkillState> = HashMap::new();
+    let mut last_state = RfkillState::default();
+
+    loop {
+        let mut buffer = [0u8; RFKILL_EVENT_SIZE];
+        file.read_exact(&mut buffer)?;
+
+        let event = RfkillEvent::from_bytes(&buffer);
+        if event.kind != RFKILL_TYPE_BLUETOOTH {
+            continue;
+        }
+
+        match event.op {
+            RFKILL_OP_ADD | RFKILL_OP_CHANGE => {
+                switches.insert(event.index, RfkillState { soft_blocked: event.soft, hard_blocked: event.hard });
+            }
+            RFKILL_OP_DEL => {
+                switches.remove(&event.index);
+            }
+            _ => continue,
+        }
+
+        // any blocked switch is enough to take bluetooth down
+        let state = RfkillState {
+            soft_blocked: switches.values().any(|switch| switch.soft_blocked),
+            hard_blocked: switches.values().any(|switch| switch.hard_blocked),
+        };
+
+        if state != last_state {
+            event_log::info(Source::Adapter, "rfkill state changed").field("state", format!("{:?}", state)).record();
+            block_on(sender.send(Message::SetRfkillState(state))).expect("cannot send message");
+            last_state = state;
+        }
+    }
+}
+
+/// lifts the soft block off every bluetooth radio, hard blocks can't be lifted this way
+pub fn unblock_bluetooth() -> Result<(), OverskrideError> {
+    let mut event = [0u8; RFKILL_EVENT_SIZE];
+    event[4] = RFKILL_TYPE_BLUETOOTH;
+    event[5] = RFKILL_OP_CHANGE_ALL;
+
+    let result = OpenOptions::new()
+        .write(true)
+        .open(RFKILL_DEVICE)
+        .and_then(|mut file| file.write_all(&event));
+
+    if let Err(err) = result {
+        event_log::warning(Source::Adapter, "cannot unblock bluetooth").field("error", &err).record();
+        return Err(OverskrideError::RadioBlocked);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::device_store;
+use crate::window::OVERSKRIDE_PROPS;
+
+/// how much the real signal is expected to change between samples (in dB²), higher follows it faster
+const PROCESS_NOISE: f32 = 0.5;
+/// how noisy a single sample is (in dB²), higher smooths more
+const MEASUREMENT_NOISE: f32 = 8.0;
+/// how much each sample's surprise counts towards the spread, the rest being the spread so far
+const SPREAD_SMOOTHING: f32 = 0.1;
+/// the spread assumed before there are samples to measure it from, in dB
+const DEFAULT_SPREAD: f32 = 4.0;
+/// the signal at 1 m of a typical phone, used for devices that aren't calibrated and don't advertise their TX power
+pub const DEFAULT_MEASURED_POWER: i16 = -59;
+/// how much weaker the signal is at 1 m than the advertised TX power (free space loss at 2.4 GHz)
+const ONE_METER_LOSS: i16 = 41;
+/// the samples needed before calibrating, so one lucky sample isn't taken as the signal at 1 m
+pub const MIN_CALIBRATION_SAMPLES: u32 = 5;
+
+/// where the distance is estimated, as (setting, name shown, path loss exponent), the exponent saying how fast signal fades
+pub const ENVIRONMENTS: [(&str, &str, f32); 4] = [
+    ("outdoors", "Outdoors", 2.0),
+    ("open", "Open Room", 2.5),
+    ("indoors", "Home or Office", 3.0),
+    ("walls", "Through Walls", 4.0),
+];
+
+/// a one dimensional Kalman filter over a device's RSSI samples
+#[derive(Debug, Clone, Copy)]
+struct Filter {
+    estimate: f32,
+    covariance: f32,
+    /// how far samples usually land from the estimate, squared
+    variance: f32,
+    samples: u32,
+}
+
+/// a device's filtered signal strength
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothedRssi {
+    pub rssi: f32,
+    /// how far the real signal probably is from `rssi`, in dB
+    pub spread: f32,
+    pub samples: u32,
+}
+
+/// how far away a device probably is, in meters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceEstimate {
+    pub meters: f32,
+    pub nearest: f32,
+    pub farthest: f32,
+    pub samples: u32,
+    pub calibrated: bool,
+}
+
+impl DistanceEstimate {
+    /// the estimate as shown to the user, like "≈ 2.40 meters"
+    pub fn describe(&self) -> String {
+        format!("≈ {:.2} meters", self.meters)
+    }
+
+    /// the range it's probably in and what it's based on
+    pub fn describe_range(&self) -> String {
+        let samples = if self.samples == 1 { "1 sample".to_string() } else { format!("{} samples", self.samples) };
+        let calibrated = if self.calibrated { ", calibrated" } else { "" };
+
+        format!("between {:.2} and {:.2} meters, from {}{}", self.nearest, self.farthest, samples, calibrated)
+    }
+}
+
+static FILTERS: Mutex<Option<HashMap<bluer::Address, Filter>>> = Mutex::new(None);
+
+/// Feeds a new RSSI sample of the device into its filter, returning the smoothed RSSI.
+pub fn add_sample(address: bluer::Address, rssi: i16) -> i16 {
+    let mut filters = FILTERS.lock().unwrap();
+    let filters = filters.get_or_insert_with(HashMap::new);
+    let sample = rssi as f32;
+
+    let filter = filters.entry(address).or_insert(Filter {
+        estimate: sample,
+        covariance: MEASUREMENT_NOISE,
+        variance: DEFAULT_SPREAD * DEFAULT_SPREAD,
+        samples: 0,
+    });
+
+    if filter.samples > 0 {
+        let covariance = filter.covariance + PROCESS_NOISE;
+        let gain = covariance / (covariance + MEASUREMENT_NOISE);
+        let surprise = sample - filter.estimate;
+
+        filter.estimate += gain * surprise;
+        filter.covariance = (1.0 - gain) * covariance;
+        filter.variance = SPREAD_SMOOTHING * surprise * surprise + (1.0 - SPREAD_SMOOTHING) * filter.variance;
+    }
+    filter.samples += 1;
+
+    filter.estimate.round() as i16
+}
+
+/// the device's smoothed RSSI, if it was heard from since Overskride started
+pub fn smoothed(address: bluer::Address) -> Option<SmoothedRssi> {
+    let filters = FILTERS.lock().unwrap();
+    let filter = filters.as_ref()?.get(&address)?;
+
+    Some(SmoothedRssi {
+        rssi: filter.estimate,
+        spread: filter.variance.sqrt(),
+        samples: filter.samples,
+    })
+}
+
+/// starts the device's filter over, for when it went away
+pub fn forget(address: bluer::Address) {
+    if let Some(filters) = FILTERS.lock().unwrap().as_mut() {
+        filters.remove(&address);
+    }
+}
+
+/// the path loss exponent of the environment set in the settings
+pub fn path_loss_exponent() -> f32 {
+    let environment = OVERSKRIDE_PROPS.lock().unwrap().distance_environment.clone();
+    ENVIRONMENTS.iter()
+        .find(|(setting, _, _)| *setting == environment)
+        .map(|(_, _, exponent)| *exponent)
+        .unwrap_or(3.0)
+}
+
+/// the signal at 1 m going by the path loss model, reversed to get the distance a signal this strong comes from
+fn distance_for(rssi: f32, measured_power: f32, exponent: f32) -> f32 {
+    10f32.powf((measured_power - rssi) / (10.0 * exponent))
+}
+
+/// Estimates how far away the device is from its smoothed RSSI. The signal at 1 m is its calibration if it has one,
+/// then its advertised `tx_power`, then a typical phone's. The range covers the usual spread of its samples.
+pub fn estimate_distance(address: bluer::Address, tx_power: Option<i16>) -> Option<DistanceEstimate> {
+    let smoothed = smoothed(address)?;
+    let calibration = device_store::get(address).measured_power;
+
+    let measured_power = if calibration != 0 {
+        calibration
+    }
+    else {
+        tx_power.map(|power| power - ONE_METER_LOSS).unwrap_or(DEFAULT_MEASURED_POWER)
+    } as f32;
+
+    let exponent = path_loss_exponent();
+
+    Some(DistanceEstimate {
+        meters: distance_for(smoothed.rssi, measured_power, exponent),
+        nearest: distance_for(smoothed.rssi + smoothed.spread, measured_power, exponent),
+        farthest: distance_for(smoothed.rssi - smoothed.spread, measured_power, exponent),
+        samples: smoothed.samples,
+        calibrated: calibration != 0,
+    })
+}
+
+/// Takes the device's smoothed RSSI as its signal at 1 m, returning it, or `None` if there aren't enough samples yet.
+pub fn calibrate(address: bluer::Address) -> Option<i16> {
+    let smoothed = smoothed(address).filter(|smoothed| smoothed.samples >= MIN_CALIBRATION_SAMPLES)?;
+    let measured_power = (smoothed.rssi.round() as i16).min(-1);
+
+    device_store::update(address, |settings| settings.measured_power = measured_power);
+    Some(measured_power)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// noisy samples around -70 dBm, like a device sitting still gives
+    const NOISY_SAMPLES: [i16; 10] = [-62, -78, -65, -75, -70, -68, -72, -66, -74, -70];
+
+    #[test]
+    fn first_sample_is_taken_as_is() {
+        let address = bluer::Address::new([0x02, 0, 0, 0, 0, 0x01]);
+
+        assert_eq!(add_sample(address, -55), -55);
+        assert_eq!(smoothed(address).map(|smoothed| smoothed.samples), Some(1));
+
+        forget(address);
+        assert_eq!(smoothed(address), None);
+    }
+
+    #[test]
+    fn converges_on_a_steady_signal() {
+        let address = bluer::Address::new([0x02, 0, 0, 0, 0, 0x02]);
+
+        for _ in 0..9 {
+            for sample in NOISY_SAMPLES {
+                add_sample(address, sample);
+            }
+        }
+
+        // the raw samples jump 16 dB around, the smoothed ones stay close to the middle
+        for sample in NOISY_SAMPLES {
+            let smoothed_rssi = add_sample(address, sample);
+            assert!((smoothed_rssi + 70).abs() <= 2, "{} is too far from -70", smoothed_rssi);
+        }
+
+        let smoothed = smoothed(address).unwrap();
+        assert_eq!(smoothed.samples, 100);
+        assert!(smoothed.spread > 1.0 && smoothed.spread < 8.0, "spread of {}", smoothed.spread);
+
+        forget(address);
+    }
+
+    #[test]
+    fn follows_a_signal_that_moved() {
+        let address = bluer::Address::new([0x02, 0, 0, 0, 0, 0x03]);
+
+        for _ in 0..20 {
+            add_sample(address, -50);
+        }
+
+        let mut smoothed_rssi = 0;
+        for _ in 0..30 {
+            let last = smoothed_rssi;
+            smoothed_rssi = add_sample(address, -80);
+            assert!(last == 0 || smoothed_rssi <= last, "went back up from {} to {}", last, smoothed_rssi);
+        }
+        assert_eq!(smoothed_rssi, -80);
+
+        forget(address);
+    }
+
+    #[test]
+    fn estimated_distance_is_within_its_range() {
+        let address = bluer::Address::new([0x02, 0, 0, 0, 0, 0x04]);
+        assert_eq!(estimate_distance(address, None), None);
+
+        for sample in NOISY_SAMPLES {
+            add_sample(address, sample);
+        }
+
+        for tx_power in [None, Some(-20), Some(0), Some(10)] {
+            let estimate = estimate_distance(address, tx_power).unwrap();
+
+            assert!(estimate.nearest > 0.0);
+            assert!(estimate.nearest <= estimate.meters && estimate.meters <= estimate.farthest, "{:?} with TX power {:?}", estimate, tx_power);
+            assert_eq!(estimate.samples, NOISY_SAMPLES.len() as u32);
+        }
+
+        // a stronger advertised TX power means the same signal came from further away
+        let weak = estimate_distance(address, Some(-20)).unwrap();
+        let strong = estimate_distance(address, Some(10)).unwrap();
+        assert!(weak.meters < strong.meters);
+
+        forget(address);
+    }
+
+    #[test]
+    fn signal_at_measured_power_is_one_meter() {
+        for (_, _, exponent) in ENVIRONMENTS {
+            assert!((distance_for(-59.0, -59.0, exponent) - 1.0).abs() < 0.001);
+            assert!(distance_for(-70.0, -59.0, exponent) > 1.0);
+            assert!(distance_for(-50.0, -59.0, exponent) < 1.0);
+        }
+    }
+}
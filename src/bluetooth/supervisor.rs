@@ -0,0 +1,200 @@
+use std::time::Duration;
+use async_channel::{Receiver, Sender};
+use dbus::blocking::Connection;
+use dbus::message::MatchRule;
+use futures::executor::block_on;
+
+use crate::agent::register_agent;
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
+use crate::message::Message;
+use crate::obex::register_obex_agent;
+
+const BLUEZ: &str = "org.bluez";
+const OBEX: &str = "org.bluez.obex";
+
+/// how long to wait before trying to register an agent again after it failed, if its service doesn't restart before then
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// the agents kept running by the supervisor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Agent {
+    /// handles pairing requests, registered with bluetoothd
+    Bluetooth,
+    /// accepts incoming files, registered with obexd
+    Obex,
+}
+
+/// how an agent is doing, shown in the settings
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentHealth {
+    Running,
+    /// its service isn't on the bus, it's registered once the service shows up
+    Waiting,
+    /// registering failed, it's tried again after a while
+    Failed(OverskrideError),
+}
+
+#[derive(Clone, Copy)]
+enum Bus {
+    System,
+    Session,
+}
+
+/// Follows who owns `name` on the bus, sending the current owner first and then every change,
+/// `None` meaning nobody owns it (the service isn't running).
+fn watch_name_owner(bus: Bus, name: &'static str) -> Receiver<Option<String>> {
+    let (owner_sender, owner_receiver) = async_channel::unbounded();
+
+    std::thread::spawn(move || {
+        let conn = match bus {
+            Bus::System => Connection::new_system(),
+            Bus::Session => Connection::new_session(),
+        };
+        let Ok(conn) = conn else {
+            event_log::warning(Source::App, "cannot connect to the bus to watch a service").field("service", name).record();
+            return;
+        };
+
+        let proxy = conn.with_proxy("org.freedesktop.DBus", "/org/freedesktop/DBus", Duration::from_millis(5000));
+        let owner: Result<(String,), dbus::Error> = proxy.method_call("org.freedesktop.DBus", "GetNameOwner", (name,));
+        let _ = owner_sender.send_blocking(owner.ok().map(|(owner,)| owner));
+
+        let rule = MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged").with_sender("org.freedesktop.DBus");
+        let result = conn.add_match(rule, move |(changed_name, _, new_owner): (String, String, String), _: &Connection, _: &dbus::Message| {
+            if changed_name == name {
+                let _ = owner_sender.send_blocking(Some(new_owner).filter(|owner| !owner.is_empty()));
+            }
+            true
+        });
+        if let Err(err) = result {
+            event_log::warning(Source::App, "cannot watch a service").field("service", name).field("error", format!("{:?}", err)).record();
+            return;
+        }
+
+        loop {
+            if let Err(err) = conn.process(Duration::from_millis(1000)) {
+                event_log::warning(Source::App, "stopped watching a service").field("service", name).field("error", format!("{:?}", err)).record();
+                break;
+            }
+        }
+    });
+
+    owner_receiver
+}
+
+/// takes the newest owner out of the ones queued up, they're only looked at once the agent is done with the last one
+fn latest_owner(owners: &Receiver<Option<String>>, current: Option<String>) -> Option<String> {
+    let mut latest = current;
+    while let Ok(owner) = owners.try_recv() {
+        latest = owner;
+    }
+    latest
+}
+
+/// waits for the next owner, watching the name again if the watcher stopped, after the retry delay so a broken bus isn't hammered
+async fn next_owner(owners: &mut Receiver<Option<String>>, bus: Bus, name: &'static str) -> Option<String> {
+    loop {
+        if let Ok(owner) = owners.recv().await {
+            return owner;
+        }
+
+        tokio::time::sleep(RETRY_DELAY).await;
+        *owners = watch_name_owner(bus, name);
+    }
+}
+
+fn send_health(sender: &Sender<Message>, agent: Agent, health: AgentHealth) {
+    block_on(sender.send(Message::SetAgentHealth(agent, health))).expect("cannot send message");
+}
+
+/// reports a failure, only toasting the first one of a streak so retrying doesn't keep popping up
+fn send_failure(sender: &Sender<Message>, agent: Agent, error: OverskrideError, failing: &mut bool) {
+    if !*failing {
+        block_on(sender.send(Message::PopupError(error.clone(), adw::ToastPriority::High))).expect("cannot send message");
+    }
+    *failing = true;
+
+    send_health(sender, agent, AgentHealth::Failed(error));
+}
+
+/// Keeps the pairing agent registered for as long as bluetoothd runs, registering it again every time bluetoothd (re)starts.
+/// Blocks forever, so run it in its own thread.
+#[tokio::main]
+pub async fn supervise_bluetooth_agent(sender: Sender<Message>) {
+    let mut owners = watch_name_owner(Bus::System, BLUEZ);
+    let mut owner = next_owner(&mut owners, Bus::System, BLUEZ).await;
+    let mut failing = false;
+
+    loop {
+        owner = latest_owner(&owners, owner);
+
+        if owner.is_none() {
+            send_health(&sender, Agent::Bluetooth, AgentHealth::Waiting);
+            owner = next_owner(&mut owners, Bus::System, BLUEZ).await;
+            continue;
+        }
+
+        let registered = match bluer::Session::new().await {
+            Ok(session) => register_agent(&session, true, false, sender.clone()).await,
+            Err(err) => Err(err.into()),
+        };
+
+        match registered {
+            Ok(handle) => {
+                event_log::info(Source::Agent, "agent registered").field("owner", owner.clone().unwrap_or_default()).record();
+                failing = false;
+                send_health(&sender, Agent::Bluetooth, AgentHealth::Running);
+
+                // any change of owner means bluetoothd went away or restarted, either way the agent is gone with it
+                owner = next_owner(&mut owners, Bus::System, BLUEZ).await;
+                drop(handle);
+            }
+            Err(err) => {
+                event_log::error(Source::Agent, "cannot register the agent").field("error", &err).record();
+                send_failure(&sender, Agent::Bluetooth, err, &mut failing);
+
+                tokio::select! {
+                    changed = next_owner(&mut owners, Bus::System, BLUEZ) => owner = changed,
+                    _ = tokio::time::sleep(RETRY_DELAY) => {},
+                }
+            }
+        }
+    }
+}
+
+/// Keeps the file transfer agent registered for as long as obexd runs, registering it again when obexd restarts
+/// or when serving it stops. Blocks forever, so run it in its own thread.
+pub fn supervise_obex_agent(sender: Sender<Message>) {
+    let mut owners = watch_name_owner(Bus::Session, OBEX);
+    let mut failing = false;
+
+    loop {
+        // a watcher that stopped would never say obexd started again
+        if owners.is_closed() {
+            owners = watch_name_owner(Bus::Session, OBEX);
+        }
+
+        // obexd is usually started by D-Bus when something asks for it, so registering is tried even when nobody owns its name
+        while owners.try_recv().is_ok() {}
+
+        // this serves the agent until obexd goes away or a transfer breaks the loop, it says when it's running itself
+        match register_obex_agent(sender.clone()) {
+            Ok(()) => {
+                failing = false;
+                std::thread::sleep(Duration::from_secs(1));
+            }
+            Err(err) => {
+                event_log::error(Source::Obex, "cannot register the agent").field("error", format!("{:?}", err)).record();
+                send_failure(&sender, Agent::Obex, err.into(), &mut failing);
+
+                // waits for obexd to start, or for the retry delay if it doesn't
+                let mut waited = Duration::ZERO;
+                while waited < RETRY_DELAY && owners.is_empty() {
+                    std::thread::sleep(Duration::from_secs(1));
+                    waited += Duration::from_secs(1);
+                }
+            }
+        }
+    }
+}
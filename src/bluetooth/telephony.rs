@@ -0,0 +1,349 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+use async_channel::Sender;
+use bluer::Uuid;
+use dbus::arg::{self, PropMap, RefArg, Variant};
+use dbus::blocking::{self, Connection};
+use dbus::message::MatchRule;
+use dbus::Path;
+use futures::executor::block_on;
+use uuid::uuid;
+
+use crate::audio_profiles::{self, connect_context, disconnect_context, wait_for_operation, AudioProfiles};
+use crate::audio_sink::route_to_default_output;
+use crate::message::Message;
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
+use crate::window::OVERSKRIDE_PROPS;
+
+// calls are handled by oFono, which talks to the phone (the audio gateway) over HFP for us
+const OFONO: &str = "org.ofono";
+const OFONO_MANAGER: &str = "org.ofono.Manager";
+const OFONO_VOICE_CALL_MANAGER: &str = "org.ofono.VoiceCallManager";
+const OFONO_VOICE_CALL: &str = "org.ofono.VoiceCall";
+
+/// the local service a phone connects to when it wants to use us as a hands-free kit
+pub const HFP_HANDS_FREE: Uuid = uuid!("0000111e-0000-1000-8000-00805f9b34fb");
+/// the older headset profile, some phones still ask for it alongside hands-free
+pub const HSP_HEADSET: Uuid = uuid!("00001108-0000-1000-8000-00805f9b34fb");
+
+/// a single call going through a paired phone, as reported by oFono
+#[derive(Debug, Clone)]
+pub struct CallInfo {
+    /// the oFono object path of this call, like "/hfp/org/bluez/hci0/dev_XX_XX_XX_XX_XX_XX/voicecall01"
+    pub path: String,
+    /// the alias of the phone, falls back to its address
+    pub device_name: String,
+    /// the number of the other side, empty if it's withheld
+    pub number: String,
+    /// the contact name of the other side, if the phone shares it
+    pub name: String,
+    /// one of "incoming", "waiting", "dialing", "alerting", "active", "held" or "disconnected"
+    pub state: String,
+}
+
+impl CallInfo {
+    /// the best name we have for whoever is on the other side of the call
+    pub fn caller(&self) -> String {
+        if !self.name.is_empty() {
+            self.name.clone()
+        }
+        else if !self.number.is_empty() {
+            self.number.clone()
+        }
+        else {
+            "Unknown Caller".to_string()
+        }
+    }
+}
+
+pub trait OrgOfonoVoiceCall {
+    fn answer(&self) -> Result<(), dbus::Error>;
+    fn hangup(&self) -> Result<(), dbus::Error>;
+    fn get_properties(&self) -> Result<PropMap, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: std::ops::Deref<Target=T>> OrgOfonoVoiceCall for blocking::Proxy<'a, C> {
+    fn answer(&self) -> Result<(), dbus::Error> {
+        self.method_call(OFONO_VOICE_CALL, "Answer", ())
+    }
+
+    fn hangup(&self) -> Result<(), dbus::Error> {
+        self.method_call(OFONO_VOICE_CALL, "Hangup", ())
+    }
+
+    fn get_properties(&self) -> Result<PropMap, dbus::Error> {
+        self.method_call(OFONO_VOICE_CALL, "GetProperties", ())
+            .map(|r: (PropMap,)| r.0)
+    }
+}
+
+/// checks if the requested service is a phone trying to use us as a hands-free kit
+pub fn is_call_service(service: Uuid) -> bool {
+    service == HFP_HANDS_FREE || service == HSP_HEADSET
+}
+
+/// gets the address of the phone out of an oFono path, they contain the bluez device path (".../dev_XX_XX_XX_XX_XX_XX/...")
+fn address_from_path(path: &str) -> Option<bluer::Address> {
+    let start = path.find("dev_")? + "dev_".len();
+    let address = path.get(start..start + 17)?;
+
+    address.replace('_', ":").parse().ok()
+}
+
+/// gets the alias of the phone a call is going through, using the bluez device path hidden in the oFono one
+fn device_name_from_path(conn: &Connection, path: &str) -> String {
+    let address = address_from_path(path).unwrap_or(bluer::Address::any());
+
+    // "/hfp/org/bluez/hci0/dev_XX_XX_XX_XX_XX_XX/voicecall01" => "/org/bluez/hci0/dev_XX_XX_XX_XX_XX_XX"
+    let start = path.find("/org/bluez/").unwrap_or(0);
+    let end = path.find("dev_").map(|index| index + "dev_".len() + 17).unwrap_or(path.len()).min(path.len());
+    let device_path = &path[start..end];
+
+    let proxy = conn.with_proxy("org.bluez", device_path, Duration::from_millis(5000));
+    let alias: Result<String, dbus::Error> = <blocking::Proxy<&Connection> as blocking::stdintf::org_freedesktop_dbus::Properties>::get(&proxy, "org.bluez.Device1", "Alias");
+
+    alias.unwrap_or(address.to_string())
+}
+
+/// builds a [CallInfo](CallInfo) from the properties oFono gives us
+fn call_from_properties(conn: &Connection, path: &str, properties: &PropMap) -> CallInfo {
+    let get = |key: &str| arg::prop_cast::<String>(properties, key).cloned().unwrap_or_default();
+
+    CallInfo {
+        path: path.to_string(),
+        device_name: device_name_from_path(conn, path),
+        number: get("LineIdentification"),
+        name: get("Name"),
+        state: get("State"),
+    }
+}
+
+/// sends a call to the UI, but only if phone calls are enabled
+fn report_call(call: CallInfo, sender: &Sender<Message>) {
+    if !OVERSKRIDE_PROPS.lock().unwrap().call_integration {
+        return;
+    }
+
+    event_log::info(Source::App, "call changed").field("call", &call.path).field("state", &call.state).record();
+    block_on(sender.send(Message::UpdateCall(call))).expect("cannot send message");
+}
+
+/// checks if oFono is running, without it there is nothing to answer calls with
+fn is_telephony_available() -> bool {
+    let Ok(conn) = Connection::new_system() else {
+        return false;
+    };
+    let proxy = conn.with_proxy("org.freedesktop.DBus", "/org/freedesktop/DBus", Duration::from_millis(5000));
+    let reply: Result<(bool,), dbus::Error> = proxy.method_call("org.freedesktop.DBus", "NameHasOwner", (OFONO,));
+
+    matches!(reply, Ok((true,)))
+}
+
+/// turns phone call integration on or off, refusing to turn it on if oFono isn't there to handle the calls
+pub async fn set_call_integration(enabled: bool, sender: Sender<Message>) -> Result<(), OverskrideError> {
+    if enabled {
+        let available = tokio::task::spawn_blocking(is_telephony_available).await.unwrap_or(false);
+
+        if !available {
+            sender.send(Message::SetCallIntegration(false)).await.expect("cannot send message");
+            return Err(OverskrideError::TelephonyUnavailable);
+        }
+    }
+
+    OVERSKRIDE_PROPS.lock().unwrap().call_integration = enabled;
+    sender.send(Message::SetCallIntegration(enabled)).await.expect("cannot send message");
+
+    Ok(())
+}
+
+/// Watches oFono for calls coming in (or going out) through any paired phone, and keeps the UI updated about them.
+/// This blocks forever, so run it in its own thread.
+pub fn watch_calls(sender: Sender<Message>) -> Result<(), dbus::Error> {
+    let conn = Connection::new_system()?;
+
+    // if a call was already ringing when we started, show it too
+    let manager = conn.with_proxy(OFONO, "/", Duration::from_millis(5000));
+    let modems: Result<(Vec<(Path<'static>, PropMap)>,), dbus::Error> = manager.method_call(OFONO_MANAGER, "GetModems", ());
+    if let Ok((modems,)) = modems {
+        for (modem, _) in modems {
+            let proxy = conn.with_proxy(OFONO, modem, Duration::from_millis(5000));
+            let calls: Result<(Vec<(Path<'static>, PropMap)>,), dbus::Error> = proxy.method_call(OFONO_VOICE_CALL_MANAGER, "GetCalls", ());
+
+            for (path, properties) in calls.map(|r| r.0).unwrap_or_default() {
+                report_call(call_from_properties(&conn, &path, &properties), &sender);
+            }
+        }
+    }
+
+    let sender_added = sender.clone();
+    conn.add_match(MatchRule::new_signal(OFONO_VOICE_CALL_MANAGER, "CallAdded"), move |(path, properties): (Path<'static>, PropMap), conn: &Connection, _: &dbus::Message| {
+        report_call(call_from_properties(conn, &path, &properties), &sender_added);
+        true
+    })?;
+
+    // the state is the only thing we care about changing, oFono sends the rest in CallAdded
+    let sender_changed = sender.clone();
+    conn.add_match(MatchRule::new_signal(OFONO_VOICE_CALL, "PropertyChanged"), move |(name, _): (String, Variant<Box<dyn RefArg>>), conn: &Connection, message: &dbus::Message| {
+        if name != "State" {
+            return true;
+        }
+
+        if let Some(path) = message.path() {
+            let proxy = conn.with_proxy(OFONO, path.to_string(), Duration::from_millis(5000));
+
+            if let Ok(properties) = proxy.get_properties() {
+                report_call(call_from_properties(conn, &path, &properties), &sender_changed);
+            }
+        }
+        true
+    })?;
+
+    let sender_removed = sender.clone();
+    conn.add_match(MatchRule::new_signal(OFONO_VOICE_CALL_MANAGER, "CallRemoved"), move |(path,): (Path<'static>,), _: &Connection, _: &dbus::Message| {
+        // give the phone its audio back if we took it for this call
+        if let Some(address) = address_from_path(&path) {
+            restore_call_audio(address);
+        }
+
+        block_on(sender_removed.send(Message::RemoveCall(path.to_string()))).expect("cannot send message");
+        true
+    })?;
+
+    event_log::debug(Source::App, "watching for phone calls").record();
+    loop {
+        conn.process(Duration::from_millis(1000))?;
+    }
+}
+
+/// answers a call, then moves its audio over to our speakers and microphone
+pub fn answer_call(path: String) -> Result<(), OverskrideError> {
+    let conn = Connection::new_system().map_err(|_| OverskrideError::CallAnswerFailed)?;
+    let proxy = conn.with_proxy(OFONO, path.clone(), Duration::from_millis(5000));
+
+    if let Err(err) = proxy.answer() {
+        event_log::warning(Source::App, "cannot answer the call").field("call", &path).field("error", &err).record();
+        return Err(OverskrideError::CallAnswerFailed);
+    }
+
+    if let Some(address) = address_from_path(&path) {
+        if let Err(err) = take_call_audio(address) {
+            event_log::warning(Source::Device, "cannot move the call's audio to the device").device(address).field("error", &err).record();
+            return Err(OverskrideError::CallAudioFailed);
+        }
+    }
+
+    Ok(())
+}
+
+/// rejects an incoming call, or hangs up one that's already going
+pub fn hangup_call(path: String) -> Result<(), OverskrideError> {
+    let conn = Connection::new_system().map_err(|_| OverskrideError::CallHangupFailed)?;
+    let proxy = conn.with_proxy(OFONO, path.clone(), Duration::from_millis(5000));
+
+    if let Err(err) = proxy.hangup() {
+        event_log::warning(Source::App, "cannot hang up the call").field("call", &path).field("error", &err).record();
+        return Err(OverskrideError::CallHangupFailed);
+    }
+
+    Ok(())
+}
+
+/// Switches the phone's card over to the audio gateway profile (pulse calls it "headset_audio_gateway", pipewire "audio-gateway"),
+/// then plays the call on the default output and sends the default microphone to the phone.
+/// The profile it had before is remembered so it can be given back after the call.
+fn take_call_audio(address: bluer::Address) -> Result<(), OverskrideError> {
+    let audio_profiles = AudioProfiles::new(address.to_string())?;
+
+    let Some(gateway_profile) = audio_profiles.profiles.keys().find(|profile| profile.contains("audio_gateway") || profile.contains("audio-gateway")).cloned() else {
+        return Err(OverskrideError::CallAudioFailed);
+    };
+
+    if audio_profiles.active_profile != gateway_profile {
+        OVERSKRIDE_PROPS.lock().unwrap().call_audio_profile = audio_profiles.active_profile.clone();
+        audio_profiles::device_set_profile(address.to_string(), gateway_profile);
+    }
+
+    route_to_default_output(address.to_string())?;
+    route_default_input_to(address.to_string())
+}
+
+/// puts the phone's card back on the profile it had before the call, if we changed it
+fn restore_call_audio(address: bluer::Address) {
+    let previous_profile = std::mem::take(&mut OVERSKRIDE_PROPS.lock().unwrap().call_audio_profile);
+
+    // the loopbacks go away by themselves once the call's source and sink are removed by the profile switch
+    if !previous_profile.is_empty() {
+        event_log::info(Source::Device, "restoring the audio profile from before the call").device(address).field("profile", &previous_profile).record();
+        audio_profiles::device_set_profile(address.to_string(), previous_profile);
+    }
+}
+
+/// sends the default microphone to the phone's bluetooth sink, the other half of [route_to_default_output](route_to_default_output).
+/// again, pipewire does this by itself so this is only needed with pulseaudio
+fn route_default_input_to(address: String) -> Result<(), OverskrideError> {
+    let (mainloop, context) = connect_context()?;
+
+    // pulse audio bluetooth sinks are called "bluez_sink.XX_XX_XX_XX_XX_XX.headset_audio_gateway"
+    let sink_prefix = "bluez_sink.".to_string() + &address.replace(':', "_");
+
+    let sink_name = Rc::new(RefCell::new(String::new()));
+    let sink_name_clone = sink_name.clone();
+
+    let operation = context.borrow().introspect().get_sink_info_list(move |result| {
+        if let pulseaudio::callbacks::ListResult::Item(item) = result {
+            if let Some(name) = &item.name {
+                if name.starts_with(&sink_prefix) {
+                    *sink_name_clone.borrow_mut() = name.to_string();
+                }
+            }
+        }
+    });
+    wait_for_operation(&mainloop, &operation);
+
+    let sink = sink_name.borrow().clone();
+    if sink.is_empty() {
+        event_log::debug(Source::Device, "no bluetooth sink for the call, assuming the sound server routes it").field("device", &address).record();
+        disconnect_context(&mainloop, &context);
+        return Ok(());
+    }
+
+    // answering again, or a second call on the same phone, shouldn't send the microphone twice
+    let already_routed = Rc::new(RefCell::new(false));
+    let already_routed_clone = already_routed.clone();
+    let sink_clone = sink.clone();
+
+    let operation = context.borrow().introspect().get_module_info_list(move |result| {
+        if let pulseaudio::callbacks::ListResult::Item(item) = result {
+            let is_loopback = item.name.as_deref() == Some("module-loopback");
+            let has_sink = item.argument.as_deref().unwrap_or("").contains(&sink_clone);
+
+            if is_loopback && has_sink {
+                *already_routed_clone.borrow_mut() = true;
+            }
+        }
+    });
+    wait_for_operation(&mainloop, &operation);
+
+    if !*already_routed.borrow() {
+        // leaving out the source makes the loopback follow the default microphone
+        let arguments = "sink=".to_string() + &sink + " sink_dont_move=true";
+        let module_index = Rc::new(RefCell::new(u32::MAX));
+        let module_index_clone = module_index.clone();
+
+        let mut introspector = context.borrow().introspect();
+        let operation = introspector.load_module("module-loopback", &arguments, move |index| {
+            *module_index_clone.borrow_mut() = index;
+        });
+        wait_for_operation(&mainloop, &operation);
+
+        if *module_index.borrow() == u32::MAX {
+            disconnect_context(&mainloop, &context);
+            return Err(OverskrideError::CallAudioFailed);
+        }
+
+        event_log::info(Source::App, "routing the default microphone to the call").field("sink", &sink).record();
+    }
+
+    disconnect_context(&mainloop, &context);
+    Ok(())
+}
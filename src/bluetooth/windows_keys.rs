@@ -0,0 +1,727 @@
+use std::path::{Path, PathBuf};
+use gtk::glib;
+
+use crate::backup::{run_keys_helper, wait_for_adapter};
+use crate::error::OverskrideError;
+use crate::event_log::{self, Source};
+
+/// where windows keeps the link keys, under the current control set
+const KEYS_PATH: [&str; 4] = ["Services", "BTHPORT", "Parameters", "Keys"];
+
+/// windows stores an hbin's cells relative to the end of the hive's header
+const HBIN_START: usize = 0x1000;
+
+const REG_BINARY: u32 = 3;
+
+/// the LE keys windows has for a device, the names being the registry's
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LeKeys {
+    pub ltk: Option<[u8; 16]>,
+    pub key_length: u32,
+    pub erand: u64,
+    pub ediv: u32,
+    pub irk: Option<[u8; 16]>,
+    pub csrk: Option<[u8; 16]>,
+    pub csrk_inbound: Option<[u8; 16]>,
+    pub auth_req: u32,
+}
+
+/// everything windows has for one device paired with one of its adapters
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowsDeviceKeys {
+    pub address: bluer::Address,
+    /// the BR/EDR link key
+    pub link_key: Option<[u8; 16]>,
+    pub le: Option<LeKeys>,
+}
+
+impl WindowsDeviceKeys {
+    /// which keys there are, like "link key, LTK, IRK", for showing what gets synced
+    pub fn describe(&self) -> String {
+        let mut keys = vec![];
+
+        if self.link_key.is_some() {
+            keys.push("link key");
+        }
+        if let Some(le) = &self.le {
+            if le.ltk.is_some() {
+                keys.push("LTK");
+            }
+            if le.irk.is_some() {
+                keys.push("IRK");
+            }
+            if le.csrk.is_some() || le.csrk_inbound.is_some() {
+                keys.push("CSRK");
+            }
+        }
+
+        keys.join(", ")
+    }
+}
+
+/// a device paired on both systems, with the keys windows has for it
+#[derive(Debug, Clone)]
+pub struct KeySync {
+    pub address: bluer::Address,
+    pub name: String,
+    pub keys: WindowsDeviceKeys,
+    /// whether bluez's keys differ from windows', `None` until bluez's storage is read
+    pub changed: Option<bool>,
+}
+
+/// Reads the link keys out of an offline windows SYSTEM hive (`C:\Windows\System32\config\SYSTEM`), grouped by adapter.
+/// Windows has to be shut down fully (fast startup off) for the hive to have the newest keys in it.
+pub fn read_hive(path: &Path) -> Result<Vec<(bluer::Address, Vec<WindowsDeviceKeys>)>, OverskrideError> {
+    let data = std::fs::read(path).map_err(|err| {
+        event_log::warning(Source::App, "cannot read the windows hive").field("file", path.display()).field("error", &err).record();
+        OverskrideError::WindowsHiveUnreadable
+    })?;
+
+    let hive = Hive::new(&data).ok_or(OverskrideError::WindowsHiveUnreadable)?;
+    let root = hive.root().ok_or(OverskrideError::WindowsHiveUnreadable)?;
+
+    // Select\Current says which control set windows booted with last
+    let current = hive.subkey(root, "Select")
+        .and_then(|select| hive.value(select, "Current"))
+        .and_then(|(_, data)| read_u32(&data, 0))
+        .unwrap_or(1);
+    let control_set = hive.subkey(root, &format!("ControlSet{:03}", current))
+        .or_else(|| hive.subkey(root, "ControlSet001"))
+        .ok_or(OverskrideError::WindowsHiveUnreadable)?;
+
+    let keys = KEYS_PATH.iter().try_fold(control_set, |key, name| hive.subkey(key, name))
+        .ok_or(OverskrideError::WindowsHiveNoKeys)?;
+
+    let mut adapters = vec![];
+    for adapter_key in hive.subkeys(keys) {
+        let Some(adapter) = parse_address(&hive.key_name(adapter_key).unwrap_or_default()) else {
+            continue;
+        };
+
+        let mut devices: Vec<WindowsDeviceKeys> = vec![];
+
+        // BR/EDR devices are a value named after them holding the link key
+        for (name, kind, data) in hive.values(adapter_key) {
+            let (Some(address), Ok(link_key)) = (parse_address(&name), <[u8; 16]>::try_from(data.as_slice())) else {
+                continue;
+            };
+            if kind == REG_BINARY {
+                devices.push(WindowsDeviceKeys { address, link_key: Some(link_key), le: None });
+            }
+        }
+
+        // LE devices are a key named after them with a value per key
+        for device_key in hive.subkeys(adapter_key) {
+            let Some(address) = parse_address(&hive.key_name(device_key).unwrap_or_default()) else {
+                continue;
+            };
+
+            let binary = |name: &str| hive.value(device_key, name).and_then(|(_, data)| <[u8; 16]>::try_from(data.as_slice()).ok());
+            let number = |name: &str| hive.value(device_key, name).and_then(|(_, data)| read_u64(&data, 0).or(read_u32(&data, 0).map(u64::from)));
+
+            let le = LeKeys {
+                ltk: binary("LTK"),
+                key_length: number("KeyLength").unwrap_or(0) as u32,
+                erand: number("ERand").unwrap_or(0),
+                ediv: number("EDIV").unwrap_or(0) as u32,
+                irk: binary("IRK"),
+                csrk: binary("CSRK"),
+                csrk_inbound: binary("CSRKInbound"),
+                auth_req: number("AuthReq").unwrap_or(0) as u32,
+            };
+
+            // dual mode devices have both, in the same device
+            match devices.iter_mut().find(|device| device.address == address) {
+                Some(device) => device.le = Some(le),
+                None => devices.push(WindowsDeviceKeys { address, link_key: None, le: Some(le) }),
+            }
+        }
+
+        adapters.push((adapter, devices));
+    }
+
+    if adapters.iter().all(|(_, devices)| devices.is_empty()) {
+        return Err(OverskrideError::WindowsHiveNoKeys);
+    }
+
+    Ok(adapters)
+}
+
+/// Matches the keys in the hive at `path` to the devices paired in bluez, on the adapter windows paired them with.
+/// Only asks bluez, so nothing needs a password. Returns the adapter's name with the devices.
+pub async fn preview(path: PathBuf) -> Result<(String, Vec<KeySync>), OverskrideError> {
+    let windows_adapters = read_hive(&path)?;
+    let session = bluer::Session::new().await?;
+    let mut same_adapter = false;
+
+    for adapter_name in session.adapter_names().await? {
+        let adapter = session.adapter(&adapter_name)?;
+        let adapter_address = adapter.address().await?;
+
+        let Some((_, windows_devices)) = windows_adapters.iter().find(|(address, _)| *address == adapter_address) else {
+            continue;
+        };
+        same_adapter = true;
+
+        let mut matches = vec![];
+        for keys in windows_devices {
+            let Ok(device) = adapter.device(keys.address) else {
+                continue;
+            };
+            if !device.is_paired().await.unwrap_or(false) {
+                continue;
+            }
+
+            matches.push(KeySync {
+                address: keys.address,
+                name: device.alias().await.unwrap_or_else(|_| keys.address.to_string()),
+                keys: keys.clone(),
+                changed: None,
+            });
+        }
+
+        // windows may know more than one of the adapters here, the devices could be paired on another one
+        if !matches.is_empty() {
+            return Ok((adapter_name, matches));
+        }
+    }
+
+    if same_adapter {
+        Err(OverskrideError::WindowsKeysNoMatches)
+    } else {
+        Err(OverskrideError::WindowsKeysOtherAdapter)
+    }
+}
+
+/// Writes windows' keys for the devices in the hive at `path` into bluez's storage through the keys helper (asking for the password),
+/// then restarts bluetoothd so they're used. With `dry_run` bluez's storage is only read, to say which devices' keys would change.
+pub async fn sync(path: PathBuf, dry_run: bool) -> Result<Vec<KeySync>, OverskrideError> {
+    let (adapter_name, mut matches) = preview(path).await?;
+    let adapter_address = bluer::Session::new().await?.adapter(&adapter_name)?.address().await?;
+
+    let stored = tokio::task::spawn_blocking(move || run_keys_helper(&["export", &adapter_address.to_string()], None)).await
+        .map_err(|_| OverskrideError::KeysHelperFailed)??;
+
+    let mut updated = String::new();
+    for line in stored.lines() {
+        let Some((address, info)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some(sync) = matches.iter_mut().find(|sync| sync.address.to_string() == address) else {
+            continue;
+        };
+
+        let info = String::from_utf8_lossy(&glib::base64_decode(info.trim())).to_string();
+        let Some((before, after)) = apply_keys(&info, &sync.keys) else {
+            continue;
+        };
+
+        sync.changed = Some(before != after);
+        if before != after {
+            updated += &format!("{} {}\n", address, glib::base64_encode(after.as_bytes()));
+        }
+    }
+
+    if !dry_run && !updated.is_empty() {
+        tokio::task::spawn_blocking(move || run_keys_helper(&["import", &adapter_address.to_string()], Some(updated))).await
+            .map_err(|_| OverskrideError::KeysHelperFailed)??;
+
+        wait_for_adapter(&adapter_name).await;
+    }
+
+    Ok(matches)
+}
+
+/// Puts windows' keys into a bluez `info` file, returning it as it was and as it is after, both written out the same way
+/// so they can be compared. The sections are bluez's, see `doc/settings-storage.txt` in bluez.
+fn apply_keys(info: &str, keys: &WindowsDeviceKeys) -> Option<(String, String)> {
+    let key_file = glib::KeyFile::new();
+    key_file.load_from_data(info, glib::KeyFileFlags::KEEP_COMMENTS).ok()?;
+    let before = key_file.to_data().to_string();
+
+    if let Some(link_key) = keys.link_key {
+        key_file.set_string("LinkKey", "Key", &to_hex(&link_key));
+        key_file.set_integer("LinkKey", "Type", key_file.integer("LinkKey", "Type").unwrap_or(4));
+        key_file.set_integer("LinkKey", "PINLength", key_file.integer("LinkKey", "PINLength").unwrap_or(0));
+    }
+
+    if let Some(le) = &keys.le {
+        if let Some(ltk) = le.ltk {
+            // bluez's Authenticated is 0/1 for legacy pairing and 2/3 for secure connections, the odd ones having MITM protection
+            let mitm = le.auth_req & 0x04 != 0;
+            let secure_connections = le.auth_req & 0x08 != 0;
+            let authenticated = if secure_connections { 2 } else { 0 } + if mitm { 1 } else { 0 };
+
+            key_file.set_string("LongTermKey", "Key", &to_hex(&ltk));
+            key_file.set_integer("LongTermKey", "Authenticated", authenticated);
+            key_file.set_integer("LongTermKey", "EncSize", if le.key_length == 0 { 16 } else { le.key_length as i32 });
+            key_file.set_string("LongTermKey", "EDiv", &le.ediv.to_string());
+            key_file.set_string("LongTermKey", "Rand", &le.erand.to_string());
+        }
+        if let Some(irk) = le.irk {
+            key_file.set_string("IdentityResolvingKey", "Key", &to_hex(&irk));
+        }
+        for (group, csrk) in [("LocalSignatureKey", le.csrk), ("RemoteSignatureKey", le.csrk_inbound)] {
+            if let Some(csrk) = csrk {
+                key_file.set_string(group, "Key", &to_hex(&csrk));
+                key_file.set_integer(group, "Counter", key_file.integer(group, "Counter").unwrap_or(0));
+                key_file.set_boolean(group, "Authenticated", key_file.boolean(group, "Authenticated").unwrap_or(false));
+            }
+        }
+    }
+
+    Some((before, key_file.to_data().to_string()))
+}
+
+/// `overskride --sync-windows-keys SYSTEM [--preview | --dry-run]`, prints what's synced (or would be) and exits.
+/// `--preview` only lists the devices paired on both systems, `--dry-run` also says which keys differ, without writing anything.
+#[tokio::main]
+pub async fn run_command_line(args: Vec<String>) -> glib::ExitCode {
+    let preview_only = args.iter().any(|arg| arg == "--preview");
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+
+    let Some(path) = args.iter().skip_while(|arg| *arg != "--sync-windows-keys").nth(1).filter(|arg| !arg.starts_with('-')) else {
+        eprintln!("usage: overskride --sync-windows-keys <path to the windows SYSTEM hive> [--preview | --dry-run]");
+        return glib::ExitCode::FAILURE;
+    };
+    let path = PathBuf::from(path);
+
+    let result = if preview_only {
+        preview(path).await.map(|(_, matches)| matches)
+    } else {
+        sync(path, dry_run).await
+    };
+
+    let matches = match result {
+        Ok(matches) => matches,
+        Err(err) => {
+            eprintln!("{}", err.explanation());
+            if let Some(suggestion) = err.suggestion() {
+                eprintln!("{}", suggestion);
+            }
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    for sync in matches {
+        let status = match (sync.changed, dry_run) {
+            _ if preview_only => "paired on both",
+            (None, _) => "bluez has no keys stored for it, skipped",
+            (Some(false), _) => "already in sync",
+            (Some(true), true) => "would be updated",
+            (Some(true), false) => "updated",
+        };
+        println!("{}  {}  ({}): {}", sync.address, sync.name, sync.keys.describe(), status);
+    }
+
+    glib::ExitCode::SUCCESS
+}
+
+/// windows names adapters and devices by their address without colons, like "a0b1c2d3e4f5"
+fn parse_address(name: &str) -> Option<bluer::Address> {
+    if name.len() != 12 || !name.chars().all(|char| char.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut address = [0u8; 6];
+    for (index, byte) in address.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&name[index * 2..index * 2 + 2], 16).ok()?;
+    }
+
+    Some(bluer::Address::new(address))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+/// A read only view of a windows registry hive file ("regf"), only as much of it as is needed to find the link keys.
+/// Keys ("nk" cells) are referred to by their cell offset.
+struct Hive<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Hive<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        (data.get(0..4)? == b"regf").then_some(Hive { data })
+    }
+
+    fn root(&self) -> Option<u32> {
+        read_u32(self.data, 0x24)
+    }
+
+    /// a cell's data, without its size
+    fn cell(&self, offset: u32) -> Option<&'a [u8]> {
+        let start = HBIN_START + offset as usize;
+        // allocated cells have a negative size
+        let size = (read_u32(self.data, start)? as i32).unsigned_abs() as usize;
+        self.data.get(start + 4..start + size.max(4))
+    }
+
+    /// a key cell, checking it is one
+    fn key(&self, offset: u32) -> Option<&'a [u8]> {
+        self.cell(offset).filter(|cell| cell.starts_with(b"nk"))
+    }
+
+    fn key_name(&self, key: u32) -> Option<String> {
+        let cell = self.key(key)?;
+        let flags = read_u16(cell, 0x02)?;
+        let length = read_u16(cell, 0x48)? as usize;
+
+        Some(decode_name(cell.get(0x4C..0x4C + length)?, flags & 0x20 != 0))
+    }
+
+    fn subkeys(&self, key: u32) -> Vec<u32> {
+        let mut subkeys = vec![];
+
+        if let Some(cell) = self.key(key) {
+            if let (Some(count), Some(list)) = (read_u32(cell, 0x14), read_u32(cell, 0x1C)) {
+                if count > 0 && list != u32::MAX {
+                    self.collect_subkeys(list, &mut subkeys, 0);
+                }
+            }
+        }
+
+        subkeys
+    }
+
+    /// goes through a subkey list, "ri" lists being lists of lists
+    fn collect_subkeys(&self, list: u32, subkeys: &mut Vec<u32>, depth: u32) {
+        let Some(cell) = self.cell(list) else {
+            return;
+        };
+        let count = read_u16(cell, 2).unwrap_or(0) as usize;
+
+        match cell.get(0..2) {
+            Some(b"lf") | Some(b"lh") => subkeys.extend((0..count).filter_map(|index| read_u32(cell, 4 + index * 8))),
+            Some(b"li") => subkeys.extend((0..count).filter_map(|index| read_u32(cell, 4 + index * 4))),
+            Some(b"ri") if depth < 4 => {
+                for index in 0..count {
+                    if let Some(sublist) = read_u32(cell, 4 + index * 4) {
+                        self.collect_subkeys(sublist, subkeys, depth + 1);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// the subkey with this name, names being case insensitive like everywhere in the registry
+    fn subkey(&self, key: u32, name: &str) -> Option<u32> {
+        self.subkeys(key).into_iter().find(|subkey| {
+            self.key_name(*subkey).is_some_and(|subkey_name| subkey_name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// every value of the key as (name, type, data)
+    fn values(&self, key: u32) -> Vec<(String, u32, Vec<u8>)> {
+        let Some(cell) = self.key(key) else {
+            return vec![];
+        };
+        let (Some(count), Some(list)) = (read_u32(cell, 0x24), read_u32(cell, 0x28)) else {
+            return vec![];
+        };
+        if count == 0 || list == u32::MAX {
+            return vec![];
+        }
+        let Some(list) = self.cell(list) else {
+            return vec![];
+        };
+
+        (0..count as usize)
+            .filter_map(|index| read_u32(list, index * 4))
+            .filter_map(|offset| self.read_value(offset))
+            .collect()
+    }
+
+    fn value(&self, key: u32, name: &str) -> Option<(u32, Vec<u8>)> {
+        self.values(key).into_iter()
+            .find(|(value_name, _, _)| value_name.eq_ignore_ascii_case(name))
+            .map(|(_, kind, data)| (kind, data))
+    }
+
+    fn read_value(&self, offset: u32) -> Option<(String, u32, Vec<u8>)> {
+        let cell = self.cell(offset).filter(|cell| cell.starts_with(b"vk"))?;
+        let name_length = read_u16(cell, 0x02)? as usize;
+        let size = read_u32(cell, 0x04)?;
+        let data_offset = read_u32(cell, 0x08)?;
+        let kind = read_u32(cell, 0x0C)?;
+        let flags = read_u16(cell, 0x10)?;
+
+        let name = decode_name(cell.get(0x14..0x14 + name_length)?, flags & 0x01 != 0);
+
+        // up to 4 bytes are kept in the offset itself, which the top bit of the size says
+        let data = if size & 0x8000_0000 != 0 {
+            let size = (size & 0x7FFF_FFFF).min(4) as usize;
+            data_offset.to_le_bytes()[..size].to_vec()
+        } else {
+            // big data ("db") is only for values far larger than any key
+            self.cell(data_offset)?.get(..size as usize)?.to_vec()
+        };
+
+        Some((name, kind, data))
+    }
+}
+
+/// names are either latin 1 ("compressed") or utf-16
+fn decode_name(bytes: &[u8], compressed: bool) -> String {
+    if compressed {
+        bytes.iter().map(|byte| *byte as char).collect()
+    } else {
+        let units = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect::<Vec<u16>>();
+        String::from_utf16_lossy(&units)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REG_DWORD: u32 = 4;
+    const REG_QWORD: u32 = 11;
+
+    const ADAPTER: &str = "001a7dda7113";
+    const CLASSIC_DEVICE: &str = "a0b1c2d3e4f5";
+    const LE_DEVICE: &str = "c0ffee000001";
+
+    const LINK_KEY: [u8; 16] = [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF, 0xFE, 0xDC, 0xBA, 0x98, 0x76, 0x54, 0x32, 0x10];
+    const LTK: [u8; 16] = [0x11; 16];
+    const IRK: [u8; 16] = [0x22; 16];
+    const ERAND: u64 = 0x1122_3344_5566_7788;
+    const EDIV: u32 = 0xBEEF;
+
+    /// writes just enough of a hive for [Hive] to read, cells are appended and referred to by their offset
+    struct HiveBuilder {
+        cells: Vec<u8>,
+    }
+
+    impl HiveBuilder {
+        fn new() -> Self {
+            // where the hbin header would be
+            HiveBuilder { cells: vec![0; 0x20] }
+        }
+
+        fn cell(&mut self, data: &[u8]) -> u32 {
+            let offset = self.cells.len() as u32;
+            let size = (data.len() + 4).next_multiple_of(8);
+
+            self.cells.extend((-(size as i32)).to_le_bytes());
+            self.cells.extend(data);
+            self.cells.resize(offset as usize + size, 0);
+
+            offset
+        }
+
+        fn value(&mut self, name: &str, kind: u32, data: &[u8]) -> u32 {
+            let (size, data_offset) = if data.len() <= 4 {
+                let mut inline = [0u8; 4];
+                inline[..data.len()].copy_from_slice(data);
+                (data.len() as u32 | 0x8000_0000, u32::from_le_bytes(inline))
+            } else {
+                (data.len() as u32, self.cell(data))
+            };
+
+            let mut cell = b"vk".to_vec();
+            cell.extend((name.len() as u16).to_le_bytes());
+            cell.extend(size.to_le_bytes());
+            cell.extend(data_offset.to_le_bytes());
+            cell.extend(kind.to_le_bytes());
+            cell.extend(1u16.to_le_bytes());
+            cell.extend(0u16.to_le_bytes());
+            cell.extend(name.as_bytes());
+
+            self.cell(&cell)
+        }
+
+        fn key(&mut self, name: &str, subkeys: &[u32], values: &[u32]) -> u32 {
+            let subkey_list = if subkeys.is_empty() {
+                u32::MAX
+            } else {
+                let mut list = b"lf".to_vec();
+                list.extend((subkeys.len() as u16).to_le_bytes());
+                for subkey in subkeys {
+                    list.extend(subkey.to_le_bytes());
+                    list.extend([0; 4]);
+                }
+                self.cell(&list)
+            };
+
+            let value_list = if values.is_empty() {
+                u32::MAX
+            } else {
+                let list = values.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>();
+                self.cell(&list)
+            };
+
+            let mut cell = vec![0; 0x4C];
+            cell[0..2].copy_from_slice(b"nk");
+            cell[0x02..0x04].copy_from_slice(&0x20u16.to_le_bytes());
+            cell[0x14..0x18].copy_from_slice(&(subkeys.len() as u32).to_le_bytes());
+            cell[0x1C..0x20].copy_from_slice(&subkey_list.to_le_bytes());
+            cell[0x24..0x28].copy_from_slice(&(values.len() as u32).to_le_bytes());
+            cell[0x28..0x2C].copy_from_slice(&value_list.to_le_bytes());
+            cell[0x48..0x4A].copy_from_slice(&(name.len() as u16).to_le_bytes());
+            cell.extend(name.as_bytes());
+
+            self.cell(&cell)
+        }
+
+        fn build(self, root: u32) -> Vec<u8> {
+            let mut data = vec![0; HBIN_START];
+            data[0..4].copy_from_slice(b"regf");
+            data[0x24..0x28].copy_from_slice(&root.to_le_bytes());
+            data.extend(self.cells);
+            data
+        }
+    }
+
+    /// a SYSTEM hive with one adapter, which has a BR/EDR device and an LE one paired
+    fn system_hive() -> Vec<u8> {
+        let mut hive = HiveBuilder::new();
+
+        let classic_device = hive.value(CLASSIC_DEVICE, REG_BINARY, &LINK_KEY);
+        // adapters have values that aren't devices too
+        let central_irk = hive.value("CentralIRK", REG_BINARY, &[0x33; 16]);
+
+        let le_values = [
+            hive.value("LTK", REG_BINARY, &LTK),
+            hive.value("KeyLength", REG_DWORD, &16u32.to_le_bytes()),
+            hive.value("ERand", REG_QWORD, &ERAND.to_le_bytes()),
+            hive.value("EDIV", REG_DWORD, &EDIV.to_le_bytes()),
+            hive.value("IRK", REG_BINARY, &IRK),
+            hive.value("AuthReq", REG_DWORD, &0x2Du32.to_le_bytes()),
+        ];
+        let le_device = hive.key(LE_DEVICE, &[], &le_values);
+
+        let adapter = hive.key(ADAPTER, &[le_device], &[classic_device, central_irk]);
+        let keys = hive.key("Keys", &[adapter], &[]);
+        let parameters = hive.key("Parameters", &[keys], &[]);
+        let bthport = hive.key("BTHPORT", &[parameters], &[]);
+        let services = hive.key("Services", &[bthport], &[]);
+        let control_set = hive.key("ControlSet001", &[services], &[]);
+
+        let current = hive.value("Current", REG_DWORD, &1u32.to_le_bytes());
+        let select = hive.key("Select", &[], &[current]);
+
+        let root = hive.key("ROOT", &[control_set, select], &[]);
+        hive.build(root)
+    }
+
+    fn read_system_hive() -> Vec<(bluer::Address, Vec<WindowsDeviceKeys>)> {
+        let path = std::env::temp_dir().join(format!("overskride-test-SYSTEM-{}", std::process::id()));
+        std::fs::write(&path, system_hive()).unwrap();
+
+        let adapters = read_hive(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        adapters.unwrap()
+    }
+
+    #[test]
+    fn parses_windows_addresses() {
+        assert_eq!(parse_address("a0b1c2d3e4f5"), Some(bluer::Address::new([0xA0, 0xB1, 0xC2, 0xD3, 0xE4, 0xF5])));
+        assert_eq!(parse_address("A0B1C2D3E4F5"), Some(bluer::Address::new([0xA0, 0xB1, 0xC2, 0xD3, 0xE4, 0xF5])));
+        assert_eq!(parse_address("a0b1c2d3e4f"), None);
+        assert_eq!(parse_address("a0b1c2d3e4fg"), None);
+        assert_eq!(parse_address("CentralIRK"), None);
+    }
+
+    #[test]
+    fn reads_classic_and_le_keys() {
+        let adapters = read_system_hive();
+
+        assert_eq!(adapters.len(), 1);
+        let (adapter, devices) = &adapters[0];
+        assert_eq!(Some(*adapter), parse_address(ADAPTER));
+        assert_eq!(devices.len(), 2);
+
+        let classic = devices.iter().find(|device| Some(device.address) == parse_address(CLASSIC_DEVICE)).unwrap();
+        assert_eq!(classic.link_key, Some(LINK_KEY));
+        assert_eq!(classic.le, None);
+
+        let le = devices.iter().find(|device| Some(device.address) == parse_address(LE_DEVICE)).unwrap();
+        assert_eq!(le.link_key, None);
+        assert_eq!(le.le, Some(LeKeys {
+            ltk: Some(LTK),
+            key_length: 16,
+            erand: ERAND,
+            ediv: EDIV,
+            irk: Some(IRK),
+            csrk: None,
+            csrk_inbound: None,
+            auth_req: 0x2D,
+        }));
+    }
+
+    #[test]
+    fn rejects_files_that_arent_hives() {
+        let path = std::env::temp_dir().join(format!("overskride-test-not-a-hive-{}", std::process::id()));
+        std::fs::write(&path, b"not a registry hive").unwrap();
+
+        let result = read_hive(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, Err(OverskrideError::WindowsHiveUnreadable));
+    }
+
+    #[test]
+    fn writes_keys_into_bluez_info() {
+        let adapters = read_system_hive();
+        let devices = &adapters[0].1;
+        let classic = devices.iter().find(|device| device.link_key.is_some()).unwrap();
+        let le = devices.iter().find(|device| device.le.is_some()).unwrap();
+
+        let info = "[General]\nName=Headphones\n\n[LinkKey]\nKey=00000000000000000000000000000000\nType=5\nPINLength=0\n";
+        let (before, after) = apply_keys(info, classic).unwrap();
+        assert_ne!(before, after);
+
+        let key_file = glib::KeyFile::new();
+        key_file.load_from_data(&after, glib::KeyFileFlags::NONE).unwrap();
+        assert_eq!(key_file.string("LinkKey", "Key").unwrap(), "0123456789ABCDEFFEDCBA9876543210");
+        // the key type bluez had is kept, windows doesn't store it
+        assert_eq!(key_file.integer("LinkKey", "Type").unwrap(), 5);
+        assert_eq!(key_file.string("General", "Name").unwrap(), "Headphones");
+
+        // applying the same keys again changes nothing
+        let (before, again) = apply_keys(&after, classic).unwrap();
+        assert_eq!(before, again);
+
+        let (_, after) = apply_keys("[General]\nName=Mouse\n", le).unwrap();
+        let key_file = glib::KeyFile::new();
+        key_file.load_from_data(&after, glib::KeyFileFlags::NONE).unwrap();
+        assert_eq!(key_file.string("LongTermKey", "Key").unwrap(), to_hex(&LTK));
+        assert_eq!(key_file.integer("LongTermKey", "EncSize").unwrap(), 16);
+        // bluez wants EDiv and Rand in decimal
+        assert_eq!(key_file.string("LongTermKey", "EDiv").unwrap(), "48879");
+        assert_eq!(key_file.string("LongTermKey", "Rand").unwrap(), "1234605616436508552");
+        assert_eq!(key_file.string("IdentityResolvingKey", "Key").unwrap(), to_hex(&IRK));
+    }
+
+    #[test]
+    fn converts_auth_req_to_authenticated() {
+        let le = read_system_hive()[0].1.iter().find(|device| device.le.is_some()).unwrap().clone();
+
+        // bonding only, then MITM, secure connections, and both
+        for (auth_req, authenticated) in [(0x01, 0), (0x05, 1), (0x09, 2), (0x2D, 3)] {
+            let mut keys = le.clone();
+            keys.le.as_mut().unwrap().auth_req = auth_req;
+
+            let (_, after) = apply_keys("", &keys).unwrap();
+            let key_file = glib::KeyFile::new();
+            key_file.load_from_data(&after, glib::KeyFileFlags::NONE).unwrap();
+            assert_eq!(key_file.integer("LongTermKey", "Authenticated").unwrap(), authenticated, "AuthReq {:#x}", auth_req);
+        }
+    }
+}
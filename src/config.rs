@@ -2,3 +2,4 @@ pub static VERSION: &str = "0.6.5";
 pub static GETTEXT_PACKAGE: &str = "overskride";
 pub static LOCALEDIR: &str = "/usr/share/locale";
 pub static PKGDATADIR: &str = "/usr/share/overskride";
+pub static LIBEXECDIR: &str = "/usr/libexec";
@@ -28,8 +28,28 @@ mod window;
 #[path = "bluetooth/services.rs"] mod services;
 #[path = "bluetooth/audio_profiles.rs"] mod audio_profiles;
 #[path = "bluetooth/battery.rs"] mod battery;
+#[path = "bluetooth/audio_sink.rs"] mod audio_sink;
+#[path = "bluetooth/telephony.rs"] mod telephony;
+#[path = "bluetooth/broadcast.rs"] mod broadcast;
+#[path = "bluetooth/rfkill.rs"] mod rfkill;
+#[path = "bluetooth/identity.rs"] mod identity;
+#[path = "bluetooth/advertisement.rs"] mod advertisement;
+#[path = "bluetooth/reconnect.rs"] mod reconnect;
+#[path = "bluetooth/audio_priority.rs"] mod audio_priority;
+#[path = "bluetooth/profiles.rs"] mod profiles;
+#[path = "bluetooth/error.rs"] mod error;
+#[path = "bluetooth/backup.rs"] mod backup;
+#[path = "bluetooth/windows_keys.rs"] mod windows_keys;
+#[path = "bluetooth/device_store.rs"] mod device_store;
+#[path = "bluetooth/proximity.rs"] mod proximity;
+#[path = "bluetooth/rssi.rs"] mod rssi;
+#[path = "bluetooth/link_info.rs"] mod link_info;
+#[path = "bluetooth/event_log.rs"] mod event_log;
+#[path = "bluetooth/supervisor.rs"] mod supervisor;
 #[path = "obex/obex.rs"] mod obex;
 #[path = "obex/obex_utils.rs"] mod obex_utils;
+#[path = "tray/tray.rs"] mod tray;
+#[path = "api/dbus_api.rs"] mod dbus_api;
 #[path = "widgets/connected_switch_row.rs"] mod connected_switch_row;
 #[path = "widgets/device_action_row.rs"] mod device_action_row;
 #[path = "widgets/receiving_popover.rs"] mod receiving_popover;
@@ -38,6 +58,9 @@ mod window;
 #[path = "widgets/selectable_row.rs"] mod selectable_row;
 #[path = "widgets/battery_indicator.rs"] mod battery_indicator;
 #[path = "widgets/more_info_page.rs"] mod more_info_page;
+#[path = "widgets/diagnostics_page.rs"] mod diagnostics_page;
+#[path = "widgets/event_log_page.rs"] mod event_log_page;
+#[path = "widgets/broadcast_row.rs"] mod broadcast_row;
 mod Singletons;
 
 use self::application::OverskrideApplication;
@@ -56,6 +79,12 @@ fn main() -> glib::ExitCode {
         .expect("Unable to set the text domain encoding");
     textdomain(GETTEXT_PACKAGE).expect("Unable to switch to the text domain");
 
+    // syncing keys with windows is a command line tool as well, for scripts and trying it on hive files, it doesn't need a window
+    let args = std::env::args().collect::<Vec<String>>();
+    if args.iter().any(|arg| arg == "--sync-windows-keys") {
+        return windows_keys::run_command_line(args);
+    }
+
     // Load resources
     // let resources = gio::Resource::load(PKGDATADIR.to_owned() + "/overskride.gresource")
         // .expect("Could not load resources");
@@ -73,7 +102,7 @@ fn main() -> glib::ExitCode {
                 .expect("Unable to find overskride.gresource in devenv")
             }
             Err(err) => {
-                eprintln!("Unable to find the current path: {}", err);
+                event_log::error(event_log::Source::App, "cannot find the current path").field("error", &err).record();
                 return 1.into();
             }
         },
@@ -84,7 +113,7 @@ fn main() -> glib::ExitCode {
     // Create a new GtkApplication. The application manages our main loop,
     // application windows, integration with the window manager/compositor, and
     // desktop features such as file opening and single-instance applications.
-    let app = OverskrideApplication::new("io.github.kaii_lb.Overskride", &gio::ApplicationFlags::empty());
+    let app = OverskrideApplication::new("io.github.kaii_lb.Overskride", &gio::ApplicationFlags::HANDLES_COMMAND_LINE);
 
 	app.connect_startup(|_| {
 		load_css()
@@ -3,15 +3,20 @@ use dbus::{blocking::{Connection,
     Path, arg::{PropMap, RefArg, Variant}, MethodErr};
 
 use dbus_crossroads::Crossroads;
-use std::{time::Duration, collections::HashMap, sync::Mutex};
+use std::{time::Duration, collections::HashMap, sync::{Mutex, atomic::{AtomicBool, Ordering}}};
 use dbus::channel::MatchingReceiver;
+use dbus::message::MatchRule;
 use std::str::FromStr;
 use adw::glib;
 use async_channel::Sender;
 use futures::executor::block_on;
 use crate::{message::Message, obex_utils::{ObexAgentManager1, ObexTransfer1, ObexClient1, ObexObjectPush1},
             window::OVERSKRIDE_PROPS,
-            agent::wait_for_dialog_exit};
+            agent::wait_for_dialog_exit,
+            supervisor::{Agent, AgentHealth},
+            device_store,
+            error::OverskrideError,
+            event_log::{self, Source}};
 
 const SESSION_INTERFACE: &str = "org.bluez.obex.Session1";
 const TRANSFER_INTERFACE: &str = "org.bluez.obex.Transfer1";
@@ -27,6 +32,10 @@ static mut LAST_BYTES: u64 = 0;
 pub static mut BREAKING: bool = false;
 pub static mut CANCEL: bool = false;
 pub static mut AUTO_ACCEPT_AFTER_FIRST: bool = true;
+/// set when obexd leaves the bus, the agent went with it so serving it stops
+static OBEXD_LOST: AtomicBool = AtomicBool::new(false);
+/// the device the file being received comes from, its settings say where the file goes
+static RECEIVING_FROM: Mutex<Option<bluer::Address>> = Mutex::new(None);
 
 // fn approx_equal(a: f32, b: f32, decimal_places: u8) -> bool {
 //     let factor = 10.0f32.powi(decimal_places as i32);
@@ -41,47 +50,59 @@ async fn handle_properties_updated(interface: String, changed_properties: PropMa
         let sender = unsafe {
             CURRENT_SENDER.clone().unwrap()
         };
+        let filename = unsafe {
+            CURRENT_FILE_NAME.clone()
+        };
         let status = if let Some(status_holder) = &changed_properties.get_key_value("Status") {
             let dummy_status = status_holder.1.0.as_str().unwrap();
 
+            let direction = if unsafe { OUTBOUND } { "outbound" } else { "inbound" };
+            let entry = match dummy_status {
+                "error" => event_log::warning(Source::Obex, "transfer status changed"),
+                _ => event_log::info(Source::Obex, "transfer status changed"),
+            };
+            let entry = entry.field("status", dummy_status).field("direction", direction).field("transfer", &transfer).field("file", &filename);
+            match *RECEIVING_FROM.lock().unwrap() {
+                Some(address) if direction == "inbound" => entry.device(address).record(),
+                _ => entry.record(),
+            }
+
             // self-explanatory, but it tells the user about what's happening with the transfer
             match dummy_status {
                 "active" => {
                 	if unsafe { OUTBOUND } {
-                    	sender.send(Message::PopupError("obex-transfer-active-outbound".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+                    	sender.send(Message::PopupError(OverskrideError::SendingStarted, adw::ToastPriority::Normal)).await.expect("cannot send message");
                     	unsafe { BREAKING = false; }
                 	}
                 	else {
-                    	sender.send(Message::PopupError("obex-transfer-active-inbound".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+                    	sender.send(Message::PopupError(OverskrideError::ReceivingStarted, adw::ToastPriority::Normal)).await.expect("cannot send message");
                 	}
                 },
                 "complete" => {
                 	if unsafe { OUTBOUND } {
-                    	sender.send(Message::PopupError("obex-transfer-complete-outbound".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+                    	sender.send(Message::PopupError(OverskrideError::FileSent, adw::ToastPriority::Normal)).await.expect("cannot send message");
                     	unsafe { BREAKING = true; }
                 	}
                 	else {
-                    	sender.send(Message::PopupError("obex-transfer-complete-inbound".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+                    	sender.send(Message::PopupError(OverskrideError::FileReceived, adw::ToastPriority::Normal)).await.expect("cannot send message");
                         move_to_store_folder(&sender).await;
                 	}
                 },
                 "error" => {
                 	if unsafe { OUTBOUND } {
-                    	sender.send(Message::PopupError("obex-transfer-error-outbound".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+                    	sender.send(Message::PopupError(OverskrideError::SendingFailed, adw::ToastPriority::Normal)).await.expect("cannot send message");
                     	unsafe { BREAKING = true; }
                 	}
                 	else {
-                    	sender.send(Message::PopupError("obex-transfer-error-inbound".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+                    	sender.send(Message::PopupError(OverskrideError::ReceivingFailed, adw::ToastPriority::Normal)).await.expect("cannot send message");
                 	}
                 },
                 message => {
-                    sender.send(Message::PopupError(message.to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+                    sender.send(Message::PopupError(OverskrideError::from_key(message), adw::ToastPriority::Normal)).await.expect("cannot send message");
                   	unsafe { BREAKING = false; }
                 }
             }
 
-            // println!("status: {:?}", dummy_status);
-
             dummy_status
         }
         else {
@@ -94,7 +115,6 @@ async fn handle_properties_updated(interface: String, changed_properties: PropMa
 
             // calculate the transfer speed by subtracting the current amount from the last
             let value_kb = unsafe {
-                // println!("transferred: {}, last: {}", transferred.unwrap(), LAST_BYTES);
                 (transferred.unwrap_or(0) / 1000).saturating_sub(LAST_BYTES / 1000)
             };
 
@@ -106,7 +126,6 @@ async fn handle_properties_updated(interface: String, changed_properties: PropMa
                     0.0
                 }
             };
-            // println!("transferred: {}", value_mb);
 
             unsafe {
                 LAST_BYTES = transferred.unwrap_or(0);
@@ -118,7 +137,7 @@ async fn handle_properties_updated(interface: String, changed_properties: PropMa
             (0.0, 0)
         };
         // updates the transfer with the specified values
-        sender.send(Message::UpdateTransfer(transfer, unsafe { CURRENT_FILE_NAME.clone() }, mb, kb, status.to_string())).await.expect("cannot send message");
+        sender.send(Message::UpdateTransfer(transfer, filename, mb, kb, status.to_string())).await.expect("cannot send message");
     }
 }
 
@@ -128,7 +147,7 @@ fn handle_interface_added(path: &Path, interfaces: &HashMap<String, PropMap>) {
 
         // if interface is a session interface then set those variables accordingly
         if interface.0 == SESSION_INTERFACE && path.contains("server") {
-            println!("started session: {:?}", interface.0);
+            event_log::info(Source::Obex, "session started").field("session", path).record();
             unsafe {
                 let session_name = path.clone().to_string();
                 let holder = session_name;
@@ -145,10 +164,9 @@ fn handle_interface_added(path: &Path, interfaces: &HashMap<String, PropMap>) {
 				}
 
                 CURRENT_TRANSFER = path.to_string();
-                println!("path is {}", path);
             }
             let proxy = conn.with_proxy("org.bluez.obex", path, Duration::from_millis(1000));
-            proxy.match_signal(|signal: PropertiesPropertiesChanged, _: &Connection, message: &dbus::Message| {
+            let matched = proxy.match_signal(|signal: PropertiesPropertiesChanged, _: &Connection, message: &dbus::Message| {
                 let transfer = if let Some(path) = message.path() {
                     path.to_string()
                 }
@@ -160,10 +178,13 @@ fn handle_interface_added(path: &Path, interfaces: &HashMap<String, PropMap>) {
                     handle_properties_updated(signal.interface_name, signal.changed_properties, transfer).await;
                 });
                 true
-            }).expect("can't match signal");
+            });
+            if let Err(err) = matched {
+                event_log::warning(Source::Obex, "cannot follow the transfer's progress").field("transfer", path).field("error", err).record();
+            }
 
-            if let Some(session) = &interface.1.get_key_value("Session").unwrap().1.0.as_str() {
-                println!("transfer started at {:?}", session);
+            if let Some(session) = interface.1.get("Session").and_then(|session| session.0.as_str()) {
+                event_log::info(Source::Obex, "transfer added").field("transfer", path).field("session", session).record();
             }
         }
     }
@@ -183,9 +204,8 @@ pub fn register_obex_agent(sender: Sender<Message>) -> Result<(), dbus::Error> {
     // matches the signal of a new object getting added to the dbus interface (ie an agent)
     proxy.match_signal(|signal: ObjectManagerInterfacesAdded, _: &Connection, _: &dbus::Message| {
         handle_interface_added(&signal.object, &signal.interfaces);
-        // println!("caught signal! {:?}", signal);
         true
-    }).expect("cannot match signal");
+    })?;
 
     drop(proxy);
     let proxy2 = conn.with_proxy("org.bluez.obex", "/org/bluez/obex", Duration::from_millis(5000));
@@ -193,9 +213,19 @@ pub fn register_obex_agent(sender: Sender<Message>) -> Result<(), dbus::Error> {
     let mut cr = Crossroads::new();
 
     create_agent(&mut cr, sender.clone());
-    proxy2.register_agent(Path::from_slice("/overskride/agent").unwrap()).expect("can't create agent");
+    // fails when obexd isn't there, which is the case on quite a few setups
+    proxy2.register_agent(Path::from_slice("/overskride/agent").unwrap())?;
+    block_on(sender.send(Message::SetAgentHealth(Agent::Obex, AgentHealth::Running))).expect("cannot send message");
+
+    OBEXD_LOST.store(false, Ordering::Relaxed);
+    conn.add_match(MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged"), |(name, _, new_owner): (String, String, String), _: &Connection, _: &dbus::Message| {
+        if name == "org.bluez.obex" && new_owner.is_empty() {
+            OBEXD_LOST.store(true, Ordering::Relaxed);
+        }
+        true
+    })?;
 
-    block_on(serve(conn, Some(cr))).expect("Cannot serve obex");
+    block_on(serve(conn, Some(cr)))?;
 
     Ok(())
 }
@@ -215,8 +245,12 @@ async fn serve(conn: &mut Connection, cr: Option<Crossroads>) -> Result<(), dbus
         CANCEL = false;
 
         while !CANCEL && !BREAKING {
-            // println!("serving");
             conn.process(Duration::from_millis(1000))?;
+
+            if OBEXD_LOST.load(Ordering::Relaxed) {
+                event_log::warning(Source::Obex, "obexd went away, stopped serving the agent").record();
+                return Ok(());
+            }
         }
 
         let sender = CURRENT_SENDER.clone().unwrap();
@@ -225,9 +259,9 @@ async fn serve(conn: &mut Connection, cr: Option<Crossroads>) -> Result<(), dbus
 
         if CANCEL {
             if let Err(err) = proxy2.cancel() {
-                println!("error while canceling transfer {:?}", err.message());
+                event_log::warning(Source::Obex, "cannot cancel transfer").field("error", err.message().unwrap_or_default()).record();
             }
-            println!("canceled");
+            event_log::info(Source::Obex, "transfer canceled").field("transfer", &proxy2.path).record();
             CANCEL = false;
         }
 
@@ -247,22 +281,26 @@ async fn serve(conn: &mut Connection, cr: Option<Crossroads>) -> Result<(), dbus
     Ok(())
 }
 
+/// what obexd is told when a push can't go ahead, which cancels the transfer
+fn request_canceled() -> MethodErr {
+    MethodErr::from(("org.bluez.obex.Error.Canceled", "Request Canceled"))
+}
+
 /// This functions describes the methods an agent has, creates an object of that agent, and inserts it into a crossroads instance
 fn create_agent(cr: &mut Crossroads, sender: Sender<Message>) {
     let agent = cr.register("org.bluez.obex.Agent1", |b| {
         b.method("AuthorizePush", ("transfer",), ("filename",), move |_, _, (transfer,): (Path,)| {
-            println!("authorizing...");
-            let conn = Connection::new_session().expect("cannot create connection.");
+            event_log::info(Source::Obex, "file push authorization requested").field("transfer", &transfer).record();
+            let conn = Connection::new_session().map_err(|err| {
+                event_log::warning(Source::Obex, "cannot connect to the session bus for a file push").field("error", &err).record();
+                request_canceled()
+            })?;
             let props = conn.with_proxy("org.bluez.obex", transfer.clone(), Duration::from_secs(5)).get_all(TRANSFER_INTERFACE);
+            let filename = props.as_ref().ok().and_then(|all_props| all_props.get("Name")).and_then(|name| name.0.as_str()).map(str::to_owned);
 
-            if let Ok(all_props) = props {
-                // lots of fuckery but its self-explanatory
-                let filename = all_props.get("Name").expect("cannot get name of file.").0.as_str().unwrap().to_owned();
-                let filesize_holder = &*all_props.get("Size").expect("cannot get file size.").0;
-                let filesize = filesize_holder.as_u64().unwrap_or(9999);
-				let session = all_props.get("Session").expect("cannot get session for receive").0.as_str().unwrap_or("");
-
-                // println!("all props is: {:?}", all_props);
+            if let (Ok(all_props), Some(filename)) = (&props, filename) {
+                let filesize = all_props.get("Size").and_then(|size| size.0.as_u64()).unwrap_or(9999);
+				let session = all_props.get("Session").and_then(|session| session.0.as_str()).unwrap_or("");
 
                 unsafe {
                     CURRENT_FILE_NAME = filename.clone();
@@ -271,20 +309,29 @@ fn create_agent(cr: &mut Crossroads, sender: Sender<Message>) {
                 }
                 let mb = ((filesize as f32 / 1000000.0) * 100.0).round() / 100.0; // to megabytes
 
-				// get the target device, if it doesn't exist, panic ensues
-				let sender_props = conn.with_proxy("org.bluez.obex", session, Duration::from_secs(5)).get_all(SESSION_INTERFACE).unwrap();
-				let device = sender_props.get("Destination").expect("cannot get sender device").0.as_str().unwrap_or("00:00:00:00:00:00");
+				// get the sending device, the push is canceled without it
+				let device = conn.with_proxy("org.bluez.obex", session, Duration::from_secs(5)).get_all(SESSION_INTERFACE).ok()
+					.and_then(|sender_props| sender_props.get("Destination").and_then(|destination| destination.0.as_str()).map(str::to_owned));
+				let Some(device) = device else {
+					OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization = false;
+					event_log::warning(Source::Obex, "cannot find who is pushing the file").field("transfer", &transfer).field("session", session).record();
+					return Err(request_canceled());
+				};
 
-				let (device_name, device_trusted) = if let Ok(props) = get_device_props(device) {
+				let (device_name, device_trusted) = if let Ok(props) = get_device_props(&device) {
 					props
 				}
 				else {
-					return Err(MethodErr::from(("org.bluez.obex.Error.Canceled", "Request Canceled")));
+					return Err(request_canceled());
 				};
 
-				// if user sets auto accept from trusted, immediately accept the transfer without confirmation
-				if OVERSKRIDE_PROPS.lock().unwrap().auto_accept_from_trusted && device_trusted {
-                    println!("transfer is: {:?}", transfer);
+				let address = bluer::Address::from_str(&device).ok();
+				*RECEIVING_FROM.lock().unwrap() = address;
+				let auto_accept_files = address.is_some_and(|address| device_store::get(address).auto_accept_files);
+
+				// if user sets auto accept from trusted (or from this device), immediately accept the transfer without confirmation
+				if (OVERSKRIDE_PROPS.lock().unwrap().auto_accept_from_trusted && device_trusted) || auto_accept_files {
+                    log_push(address, "file push accepted automatically", &filename, filesize);
                     block_on(sender.send(Message::StartTransfer(transfer.to_string(), filename.clone(), 0.0, 0.0, mb, false))).expect("cannot send message");
 
 					return Ok((filename,));
@@ -292,14 +339,14 @@ fn create_agent(cr: &mut Crossroads, sender: Sender<Message>) {
 
 				// if the ~/.cache directory doesn't exist, return as we have nowhere to store the file
 				if !glib::user_cache_dir().exists() {
-                    block_on(sender.clone().send(Message::PopupError("file-storage-cache-invalid".to_string(), adw::ToastPriority::High))).expect("cannot send message");
+                    block_on(sender.clone().send(Message::PopupError(OverskrideError::CacheLocationInvalid, adw::ToastPriority::High))).expect("cannot send message");
 
-  					return Err(MethodErr::from(("org.bluez.obex.Error.Canceled", "Request Canceled")));
+  					return Err(request_canceled());
                 }
 
                 // spawn a dialog returning the accepted bool, no accepted => reject transfer
                 if OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization || spawn_dialog(filename.clone(), &sender, device_name) {
-                    println!("transfer is: {:?}", transfer);
+                    log_push(address, "file push accepted", &filename, filesize);
                     block_on(sender.send(Message::StartTransfer(transfer.to_string(), filename.clone(), 0.0, 0.0, mb, false))).expect("cannot send message");
 
                     unsafe {
@@ -311,7 +358,7 @@ fn create_agent(cr: &mut Crossroads, sender: Sender<Message>) {
                     Ok((filename,))
                 }
                 else {
-                    println!("rejected push");
+                    log_push(address, "file push rejected", &filename, filesize);
                     let error = MethodErr::from(("org.bluez.obex.Error.Rejected", "Not Authorized"));
                     OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization = false;
                     Err(error)
@@ -319,32 +366,39 @@ fn create_agent(cr: &mut Crossroads, sender: Sender<Message>) {
             }
             else {
                 OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization = false;
-                println!("failed to authorize push");
-                Err(MethodErr::from(("org.bluez.obex.Error.Canceled", "Request Canceled")))
+                event_log::warning(Source::Obex, "cannot read the pushed file's properties").field("transfer", &transfer).record();
+                Err(request_canceled())
             }
         });
 
         // these are never called, not sure why they exist
         b.method("Cancel", (), (), move |_, _, _: ()| {
-            println!("Cancelling...");
+            event_log::info(Source::Obex, "agent request canceled by obexd").record();
             Ok(())
         });
 
         b.method("Release", (), (), move |_, _, _: ()| {
-            println!("Releasing...");
+            event_log::info(Source::Obex, "agent released by obexd").record();
             Ok(())
         });
     });
     cr.insert("/overskride/agent", &[agent], ());
 
-    println!("created obex agent");
+    event_log::debug(Source::Obex, "agent created").record();
+}
+
+/// records a decision about a file pushed by a device in the event log
+fn log_push(address: Option<bluer::Address>, message: &str, filename: &str, filesize: u64) {
+    let entry = event_log::info(Source::Obex, message).field("file", filename).field("size", filesize);
+    match address {
+        Some(address) => entry.device(address).record(),
+        None => entry.record(),
+    }
 }
 
 /// Spawns a new dialog asking the user to allow or reject a file transfer from a device
 #[tokio::main]
 async fn spawn_dialog(filename: String, sender: &Sender<Message>, device_name: String) -> bool {
-    println!("file receive request incoming!");
-
     let title = "File Transfer Incoming".to_string();
     let subtitle = "Accept <span font_weight='bold' color='#78aeed'>".to_string() + &filename + "</span> from <span font_weight='bold'>" + &device_name + "?</span>";
     let confirm = "Accept".to_string();
@@ -359,21 +413,31 @@ async fn spawn_dialog(filename: String, sender: &Sender<Message>, device_name: S
     OVERSKRIDE_PROPS.lock().unwrap().confirm_authorization
 }
 
-/// Wrapper function handling the adapter and target device, looping over all the files needing to be sent and sending them on by one
+/// Wrapper function handling the adapter and target device, looping over all the files needing to be sent and sending them on by one.
+/// If no files are given the user picks them first.
 #[tokio::main]
-pub async fn start_send_file(destination: bluer::Address, source: bluer::Address, sender: Sender<Message>) {
-    OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
-    sender.send(Message::GetFile(gtk::FileChooserAction::Open)).await.expect("cannot send message");
+pub async fn start_send_file(destination: bluer::Address, source: bluer::Address, file_paths: Vec<String>, sender: Sender<Message>) {
+    let file_paths = if file_paths.is_empty() {
+        OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
+        sender.send(Message::GetFile(gtk::FileChooserAction::Open)).await.expect("cannot send message");
 
-    wait_for_dialog_exit().await;
+        wait_for_dialog_exit().await;
 
-    let file_paths = OVERSKRIDE_PROPS.lock().unwrap().send_files_path.clone();
+        OVERSKRIDE_PROPS.lock().unwrap().send_files_path.clone()
+    } else {
+        file_paths
+    };
 
     // if calling on an empty transfer, get out
     if file_paths.is_empty() {
         return;
     }
 
+    send_files_to(destination, source, file_paths, sender).await;
+}
+
+/// Opens an OBEX session from the source adapter to the target device, then sends the files one by one
+async fn send_files_to(destination: bluer::Address, source: bluer::Address, file_paths: Vec<String>, sender: Sender<Message>) {
     let conn = Connection::new_session().expect("cannot create send connection");
     let proxy = conn.with_proxy("org.bluez.obex", "/org/bluez/obex", Duration::from_secs(5));
 
@@ -390,18 +454,17 @@ pub async fn start_send_file(destination: bluer::Address, source: bluer::Address
     	sesh.unwrap()
     }
     else {
-        println!("Could not establish connection to send file {:?}", sesh.err().unwrap());
-        sender.send(Message::PopupError("obex-transfer-connection-error".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+        event_log::warning(Source::Obex, "cannot open a session to send files").device(destination).field("error", format!("{:?}", sesh.err().unwrap())).record();
+        sender.send(Message::PopupError(OverskrideError::TransferConnectionFailed, adw::ToastPriority::Normal)).await.expect("cannot send message");
     	return;
     };
-    println!("send session is: {:?}, with filepaths {:?}", send_session, file_paths);
+    event_log::info(Source::Obex, "sending files").device(destination).field("session", &send_session).field("files", file_paths.len()).record();
 
     // for every file, try to send it to the target device
     for file in file_paths {
-        println!("file to be sent is {}", file);
         send_file(file.clone(), &send_session, sender.clone()).await;
     }
-    println!("done sending files");
+    event_log::info(Source::Obex, "done sending files").device(destination).record();
 }
 
 /// Sends a specified file from the file path to a target device, updating the UI in the process
@@ -412,8 +475,7 @@ async fn send_file(source_file: String, session_path: &Path<'_>, sender: Sender<
     // return the path and properties
     let output = proxy.send_file(source_file.as_str()).unwrap();
 
-    println!("send transfer path is: {:?}", output.0.clone());
-    println!("send properties are: {:?}\n", output.1);
+    event_log::info(Source::Obex, "transfer added").field("transfer", &output.0).field("file", &source_file).record();
 
 	// create a new proxy to the transfer path for easier processing of properties
 	let transfer_proxy = conn.with_proxy("org.bluez.obex", output.0.clone(), Duration::from_secs(5));
@@ -447,8 +509,8 @@ async fn send_file(source_file: String, session_path: &Path<'_>, sender: Sender<
         // stop sending this file
         if CANCEL {
             if let Err(err) = transfer_proxy.cancel() {
-                // sender.send(Message::PopupError("obex-transfer-cancel-not-authorized".to_string(), adw::ToastPriority::Normal)).await.expect("cannot send message");
-                println!("error while canceling transfer {:?}", err.message());
+                // sender.send(Message::PopupError("obex-transfer-cancel-not-authorized".into(), adw::ToastPriority::Normal)).await.expect("cannot send message");
+                event_log::warning(Source::Obex, "cannot cancel transfer").field("error", err.message().unwrap_or_default()).record();
             }
         	let transferred = (transfer_proxy.transferred().unwrap_or(9999) as f32 / 1000000.0).round() / 100.0;
             sender.send(Message::UpdateTransfer(CURRENT_TRANSFER.clone(), CURRENT_FILE_NAME.clone(), transferred, 0, "error".to_string())).await.expect("cannot send message");
@@ -483,21 +545,34 @@ pub async fn move_to_store_folder(sender: &Sender<Message>) {
         cache_dir.to_string() + "/obexd/" + &filename
     }
     else {
-    	sender.send(Message::PopupError("obex-transfer-cant-move".to_string(), adw::ToastPriority::High)).await.expect("cannot send message");
-        println!("unable to save file to store folder, it should still remain in ~/.cache/obexd");
+    	sender.send(Message::PopupError(OverskrideError::TransferCantMove, adw::ToastPriority::High)).await.expect("cannot send message");
+        event_log::warning(Source::Obex, "cannot find the received file, it should still be in ~/.cache/obexd").field("file", &filename).record();
         return;
     };
 
+    // files from a device with its own folder go in there
+    let download_folder = RECEIVING_FROM.lock().unwrap().map(|address| device_store::get(address).download_folder).unwrap_or_default();
+    let store_folder = if download_folder.is_empty() {
+        store_folder
+    }
+    else {
+        let folder = store_folder + download_folder.trim_matches('/') + "/";
+        if let Err(err) = std::fs::create_dir_all(&folder) {
+            event_log::warning(Source::Obex, "cannot create the device's download folder").field("folder", &folder).field("error", err).record();
+        }
+        folder
+    };
+
     let new_filepath = store_folder + &filename;
 
     // move file to location and handle error
-    match std::fs::rename(filepath, new_filepath) {
+    match std::fs::rename(filepath, &new_filepath) {
         Ok(()) => {
-            println!("file moved to directory");
+            event_log::info(Source::Obex, "received file moved to the download folder").field("file", &new_filepath).record();
         },
         Err(err) => {
-          	sender.send(Message::PopupError("obex-transfer-cant-move".to_string(), adw::ToastPriority::High)).await.expect("cannot send message");
-            println!("file was not moved due to {:?}", err);
+          	sender.send(Message::PopupError(OverskrideError::TransferCantMove, adw::ToastPriority::High)).await.expect("cannot send message");
+            event_log::warning(Source::Obex, "cannot move the received file").field("file", &filename).field("error", err).record();
         },
     }
 }
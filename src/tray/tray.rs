@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use async_channel::Sender;
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::blocking::Connection;
+use dbus::channel::{MatchingReceiver, Sender as DbusSender};
+use dbus::message::MatchRule;
+use dbus::Path;
+use dbus_crossroads::{Crossroads, MethodErr};
+use futures::executor::block_on;
+use crate::message::Message;
+use crate::window::{runtime, OVERSKRIDE_PROPS};
+use crate::bluetooth_settings;
+use crate::reconnect;
+use crate::event_log::{self, Source};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_INTERFACE: &str = "com.canonical.dbusmenu";
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+
+// menu item ids, devices are numbered from DEVICE_ID onwards
+const ROOT_ID: i32 = 0;
+const SHOW_ID: i32 = 1;
+const RADIO_ID: i32 = 2;
+const QUIT_ID: i32 = 3;
+const DEVICES_SEPARATOR_ID: i32 = 10;
+const RADIO_SEPARATOR_ID: i32 = 11;
+const QUIT_SEPARATOR_ID: i32 = 12;
+const DEVICE_ID: i32 = 100;
+
+/// how often the devices and their batteries are checked again while the tray is up
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+static TRAY_RUNNING: AtomicBool = AtomicBool::new(false);
+
+type MenuLayout = (i32, PropMap, Vec<Variant<Box<dyn RefArg>>>);
+/// id, event id (like "clicked"), data and timestamp
+type MenuEvent = (i32, String, Variant<Box<dyn RefArg>>, u32);
+
+/// a paired device as shown in the tray menu
+#[derive(Debug, Clone, PartialEq)]
+struct TrayDevice {
+    address: bluer::Address,
+    name: String,
+    connected: bool,
+    battery: Option<u8>,
+}
+
+/// everything the tray menu shows, `revision` goes up every time it changes so the host knows to fetch it again
+#[derive(Debug, Clone, Default)]
+struct TrayState {
+    powered: bool,
+    devices: Vec<TrayDevice>,
+    revision: u32,
+}
+
+/// the data shared between the tray's D-Bus objects
+struct Tray {
+    state: Arc<Mutex<TrayState>>,
+    sender: Sender<Message>,
+}
+
+/// Shows a tray icon (StatusNotifierItem) with the connected devices, quick connect/disconnect and a radio toggle.
+/// Does nothing if the tray is already up. This blocks until background running is turned off, so run it in its own thread.
+pub fn run_tray(sender: Sender<Message>) -> Result<(), dbus::Error> {
+    if TRAY_RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let result = serve_tray(sender);
+    TRAY_RUNNING.store(false, Ordering::SeqCst);
+
+    result
+}
+
+fn serve_tray(sender: Sender<Message>) -> Result<(), dbus::Error> {
+    let conn = Connection::new_session()?;
+    let service_name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+    conn.request_name(service_name.as_str(), false, true, false)?;
+
+    let state = Arc::new(Mutex::new(TrayState::default()));
+    refresh_state(&state);
+
+    let mut cr = Crossroads::new();
+    let item = create_item(&mut cr);
+    let menu = create_menu(&mut cr);
+    cr.insert(ITEM_PATH, &[item], Tray { state: state.clone(), sender: sender.clone() });
+    cr.insert(MENU_PATH, &[menu], Tray { state: state.clone(), sender: sender.clone() });
+
+    conn.start_receive(MatchRule::new_method_call(), Box::new(move |msg, conn| {
+        let member = msg.member().map(|member| member.to_string()).unwrap_or_default();
+        if cr.handle_message(msg, conn).is_err() {
+            event_log::warning(Source::App, "tray icon cannot answer a message").field("method", member).record();
+        }
+        true
+    }));
+
+    // register again if the tray host (the panel) restarts
+    let name_clone = service_name.clone();
+    conn.add_match(MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged"), move |(name, _, new_owner): (String, String, String), conn: &Connection, _: &dbus::Message| {
+        if name == WATCHER && !new_owner.is_empty() {
+            register_with_watcher(conn, &name_clone);
+        }
+        true
+    })?;
+
+    register_with_watcher(&conn, &service_name);
+
+    let mut last_refresh = Instant::now();
+    while OVERSKRIDE_PROPS.lock().unwrap().run_in_background {
+        conn.process(Duration::from_millis(1000))?;
+
+        if last_refresh.elapsed() < REFRESH_INTERVAL {
+            continue;
+        }
+        last_refresh = Instant::now();
+
+        let (old_revision, old_powered) = {
+            let state = state.lock().unwrap();
+            (state.revision, state.powered)
+        };
+        refresh_state(&state);
+        let (revision, powered) = {
+            let state = state.lock().unwrap();
+            (state.revision, state.powered)
+        };
+
+        if revision != old_revision {
+            let signal = dbus::Message::new_signal(MENU_PATH, MENU_INTERFACE, "LayoutUpdated").unwrap().append2(revision, ROOT_ID);
+            let _ = conn.send(signal);
+        }
+        if powered != old_powered {
+            let signal = dbus::Message::new_signal(ITEM_PATH, ITEM_INTERFACE, "NewIcon").unwrap();
+            let _ = conn.send(signal);
+        }
+    }
+
+    event_log::info(Source::App, "tray stopped").record();
+    Ok(())
+}
+
+/// tells the tray host about us, without a host (like plain GNOME) there is simply no icon
+fn register_with_watcher(conn: &Connection, service_name: &str) {
+    let proxy = conn.with_proxy(WATCHER, WATCHER_PATH, Duration::from_millis(5000));
+    let result: Result<(), dbus::Error> = proxy.method_call(WATCHER, "RegisterStatusNotifierItem", (service_name,));
+
+    if let Err(err) = result {
+        event_log::warning(Source::App, "cannot register the tray icon, is there a tray?").field("error", err.message().unwrap_or_default()).record();
+    }
+}
+
+/// gets the adapter's powered state and its paired devices, bumping the revision if anything changed
+fn refresh_state(state: &Arc<Mutex<TrayState>>) {
+    let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+
+    let (powered, devices) = match runtime().block_on(get_tray_devices(adapter_name)) {
+        Ok(result) => result,
+        Err(err) => {
+            event_log::warning(Source::App, "cannot update the tray").field("error", &err).record();
+            (false, vec![])
+        }
+    };
+
+    let mut state = state.lock().unwrap();
+    if state.powered != powered || state.devices != devices {
+        state.powered = powered;
+        state.devices = devices;
+        state.revision += 1;
+    }
+}
+
+/// the paired devices of this adapter, connected ones first
+async fn get_tray_devices(adapter_name: String) -> bluer::Result<(bool, Vec<TrayDevice>)> {
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let powered = adapter.is_powered().await?;
+    let mut devices = vec![];
+
+    for address in adapter.device_addresses().await? {
+        let device = adapter.device(address)?;
+        if !device.is_paired().await? {
+            continue;
+        }
+
+        let connected = device.is_connected().await?;
+        let battery = if connected {
+            device.battery_percentage().await.unwrap_or(None)
+        } else {
+            None
+        };
+
+        devices.push(TrayDevice {
+            address,
+            name: device.alias().await?,
+            connected,
+            battery,
+        });
+    }
+
+    devices.sort_by(|one, two| two.connected.cmp(&one.connected).then(one.name.cmp(&two.name)));
+
+    Ok((powered, devices))
+}
+
+/// the StatusNotifierItem itself, the icon in the tray
+fn create_item(cr: &mut Crossroads) -> dbus_crossroads::IfaceToken<Tray> {
+    cr.register(ITEM_INTERFACE, |b| {
+        b.property("Category").get(|_, _| Ok("Hardware".to_string()));
+        b.property("Id").get(|_, _| Ok("overskride".to_string()));
+        b.property("Title").get(|_, _| Ok("Overskride".to_string()));
+        b.property("Status").get(|_, _| Ok("Active".to_string()));
+        b.property("IconName").get(|_, tray: &mut Tray| {
+            let powered = tray.state.lock().unwrap().powered;
+
+            Ok(if powered { "bluetooth-active-symbolic" } else { "bluetooth-disabled-symbolic" }.to_string())
+        });
+        b.property("ItemIsMenu").get(|_, _| Ok(false));
+        b.property("Menu").get(|_, _| Ok(Path::from(MENU_PATH)));
+        b.signal::<(), _>("NewIcon", ());
+
+        // left click shows the window, the menu is on right click
+        b.method("Activate", ("x", "y"), (), |_, tray: &mut Tray, (_, _): (i32, i32)| {
+            show_window(&tray.sender);
+            Ok(())
+        });
+        b.method("SecondaryActivate", ("x", "y"), (), |_, tray: &mut Tray, (_, _): (i32, i32)| {
+            show_window(&tray.sender);
+            Ok(())
+        });
+        b.method("ContextMenu", ("x", "y"), (), |_, _, (_, _): (i32, i32)| Ok(()));
+        b.method("Scroll", ("delta", "orientation"), (), |_, _, (_, _): (i32, String)| Ok(()));
+    })
+}
+
+/// the tray menu, as a `com.canonical.dbusmenu` which is what tray hosts expect
+fn create_menu(cr: &mut Crossroads) -> dbus_crossroads::IfaceToken<Tray> {
+    cr.register(MENU_INTERFACE, |b| {
+        b.property("Version").get(|_, _| Ok(3u32));
+        b.property("TextDirection").get(|_, _| Ok("ltr".to_string()));
+        b.property("Status").get(|_, _| Ok("normal".to_string()));
+        b.property("IconThemePath").get(|_, _| Ok(Vec::<String>::new()));
+        b.signal::<(u32, i32), _>("LayoutUpdated", ("revision", "parent"));
+
+        b.method("GetLayout", ("parentId", "recursionDepth", "propertyNames"), ("revision", "layout"), |_, tray: &mut Tray, (_, _, _): (i32, i32, Vec<String>)| {
+            let state = tray.state.lock().unwrap().clone();
+
+            Ok((state.revision, menu_layout(&state)))
+        });
+        b.method("GetGroupProperties", ("ids", "propertyNames"), ("properties",), |_, tray: &mut Tray, (ids, _): (Vec<i32>, Vec<String>)| {
+            let state = tray.state.lock().unwrap().clone();
+            let properties: Vec<(i32, PropMap)> = menu_items(&state)
+                .into_iter()
+                .filter(|(id, _)| ids.is_empty() || ids.contains(id))
+                .collect();
+
+            Ok((properties,))
+        });
+        b.method("GetProperty", ("id", "name"), ("value",), |_, tray: &mut Tray, (id, name): (i32, String)| {
+            let state = tray.state.lock().unwrap().clone();
+            let mut items = menu_items(&state);
+
+            items.iter_mut()
+                .find(|(item_id, _)| *item_id == id)
+                .and_then(|(_, properties)| properties.remove(&name))
+                .map(|value| (value,))
+                .ok_or_else(|| MethodErr::invalid_arg(&name))
+        });
+        b.method("Event", ("id", "eventId", "data", "timestamp"), (), |_, tray: &mut Tray, (id, event_id, _, _): MenuEvent| {
+            if event_id == "clicked" {
+                handle_click(tray, id);
+            }
+            Ok(())
+        });
+        b.method("EventGroup", ("events",), ("idErrors",), |_, tray: &mut Tray, (events,): (Vec<MenuEvent>,)| {
+            for (id, event_id, _, _) in events {
+                if event_id == "clicked" {
+                    handle_click(tray, id);
+                }
+            }
+            Ok((Vec::<i32>::new(),))
+        });
+        b.method("AboutToShow", ("id",), ("needUpdate",), |_, _, (_,): (i32,)| Ok((false,)));
+        b.method("AboutToShowGroup", ("ids",), ("updatesNeeded", "idErrors"), |_, _, (_,): (Vec<i32>,)| {
+            Ok((Vec::<i32>::new(), Vec::<i32>::new()))
+        });
+    })
+}
+
+/// every menu item (besides the root) with its properties, in the order they're shown
+fn menu_items(state: &TrayState) -> Vec<(i32, PropMap)> {
+    let mut items = vec![(SHOW_ID, label_item("Show Overskride"))];
+
+    if !state.devices.is_empty() {
+        items.push((DEVICES_SEPARATOR_ID, separator_item()));
+    }
+
+    for (index, device) in state.devices.iter().enumerate() {
+        let label = match device.battery {
+            Some(battery) => format!("{} ({}%)", device.name, battery),
+            None => device.name.clone(),
+        };
+
+        let mut item = label_item(&label);
+        item.insert("toggle-type".to_string(), Variant(Box::new("checkmark".to_string())));
+        item.insert("toggle-state".to_string(), Variant(Box::new(device.connected as i32)));
+        item.insert("enabled".to_string(), Variant(Box::new(state.powered)));
+
+        items.push((DEVICE_ID + index as i32, item));
+    }
+
+    items.push((RADIO_SEPARATOR_ID, separator_item()));
+
+    let mut radio = label_item("Bluetooth");
+    radio.insert("toggle-type".to_string(), Variant(Box::new("checkmark".to_string())));
+    radio.insert("toggle-state".to_string(), Variant(Box::new(state.powered as i32)));
+    items.push((RADIO_ID, radio));
+
+    items.push((QUIT_SEPARATOR_ID, separator_item()));
+    items.push((QUIT_ID, label_item("Quit")));
+
+    items
+}
+
+/// the whole menu tree, all items are direct children of the root
+fn menu_layout(state: &TrayState) -> MenuLayout {
+    let children = menu_items(state)
+        .into_iter()
+        .map(|(id, properties)| {
+            let child: MenuLayout = (id, properties, vec![]);
+
+            Variant(Box::new(child) as Box<dyn RefArg>)
+        })
+        .collect();
+
+    let mut root = PropMap::new();
+    root.insert("children-display".to_string(), Variant(Box::new("submenu".to_string())));
+
+    (ROOT_ID, root, children)
+}
+
+fn label_item(label: &str) -> PropMap {
+    let mut item: PropMap = HashMap::new();
+    item.insert("label".to_string(), Variant(Box::new(label.replace('_', "__"))));
+    item
+}
+
+fn separator_item() -> PropMap {
+    let mut item: PropMap = HashMap::new();
+    item.insert("type".to_string(), Variant(Box::new("separator".to_string())));
+    item
+}
+
+fn show_window(sender: &Sender<Message>) {
+    block_on(sender.send(Message::ShowWindow())).expect("cannot send message");
+}
+
+/// does whatever the clicked menu item says, devices toggle between connected and disconnected
+fn handle_click(tray: &Tray, id: i32) {
+    let sender = tray.sender.clone();
+    let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+
+    match id {
+        SHOW_ID => show_window(&sender),
+        QUIT_ID => block_on(sender.send(Message::QuitApplication())).expect("cannot send message"),
+        RADIO_ID => {
+            runtime().spawn(async move {
+                if let Err(err) = bluetooth_settings::set_adapter_powered(adapter_name, sender.clone()).await {
+                    sender.send(Message::PopupError(err.into(), adw::ToastPriority::High)).await.expect("cannot send message");
+                }
+            });
+        }
+        id if id >= DEVICE_ID => {
+            let Some(device) = tray.state.lock().unwrap().devices.get((id - DEVICE_ID) as usize).cloned() else {
+                return;
+            };
+
+            runtime().spawn(async move {
+                if let Err(err) = toggle_device_connected(device.address, device.connected, adapter_name).await {
+                    sender.send(Message::PopupError(err.into(), adw::ToastPriority::High)).await.expect("cannot send message");
+                }
+            });
+        }
+        _ => (),
+    }
+}
+
+async fn toggle_device_connected(address: bluer::Address, connected: bool, adapter_name: String) -> bluer::Result<()> {
+    let adapter = bluer::Session::new().await?.adapter(adapter_name.as_str())?;
+    let device = adapter.device(address)?;
+
+    if connected {
+        reconnect::user_disconnected(address);
+        device.disconnect().await
+    } else {
+        device.connect().await
+    }
+}
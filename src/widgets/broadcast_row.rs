@@ -0,0 +1,146 @@
+use glib::{Object, Properties};
+use gtk::glib;
+use adw::subclass::prelude::{ExpanderRowImpl, PreferencesRowImpl};
+use adw::prelude::{ActionRowExt, ExpanderRowExt, PreferencesRowExt};
+use gtk::subclass::prelude::*;
+use gtk::prelude::{ButtonExt, ObjectExt, WidgetExt};
+use std::cell::RefCell;
+
+use crate::broadcast::BroadcastSource;
+
+mod imp {
+    use super::*;
+
+    /// an expander row showing an audio broadcast and its metadata, with a button to join or leave it
+    #[derive(Properties, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/kaii_lb/Overskride/gtk/broadcast-row.ui")]
+    #[properties(wrapper_type = super::BroadcastRow)]
+    pub struct BroadcastRow {
+        #[template_child]
+        pub rssi_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub join_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub broadcast_id_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub contexts_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub rating_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub address_row: TemplateChild<adw::ActionRow>,
+
+        #[property(get, set = Self::private_set_joined)]
+        pub joined: RefCell<bool>,
+
+        pub address: RefCell<bluer::Address>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for BroadcastRow {
+        const NAME: &'static str = "BroadcastRow";
+        type Type = super::BroadcastRow;
+        type ParentType = adw::ExpanderRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for BroadcastRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+        }
+    }
+
+    impl ExpanderRowImpl for BroadcastRow {}
+    impl WidgetImpl for BroadcastRow {}
+    impl ListBoxRowImpl for BroadcastRow {}
+    impl PreferencesRowImpl for BroadcastRow {}
+
+    impl BroadcastRow {
+        /// switches the join button between joining and leaving
+        pub fn private_set_joined(&self, joined: bool) {
+            let join_button = self.join_button.get();
+
+            if joined {
+                join_button.set_label("Leave");
+                join_button.set_css_classes(&["pill", "destructive-action"]);
+            }
+            else {
+                join_button.set_label("Join");
+                join_button.set_css_classes(&["pill", "suggested-action"]);
+            }
+
+            *self.joined.borrow_mut() = joined;
+        }
+
+        /// same rssi levels as the device rows use
+        pub fn private_update_rssi_icon(&self, rssi: Option<i16>) {
+            let icon_name = match rssi {
+                None => "rssi-none-symbolic",
+                Some(n) if n >= -60 => "rssi-high-symbolic",
+                Some(n) if n >= -70 => "rssi-medium-symbolic",
+                Some(n) if n >= -80 => "rssi-low-symbolic",
+                Some(n) if n >= -90 => "rssi-dead-symbolic",
+                Some(_) => "rssi-none-symbolic",
+            };
+
+            self.rssi_icon.get().set_icon_name(Some(icon_name));
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct BroadcastRow(ObjectSubclass<imp::BroadcastRow>)
+        @extends adw::ExpanderRow, gtk::Widget, gtk::ListBoxRow, adw::PreferencesRow,
+        @implements gtk::Accessible, gtk::Actionable, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl BroadcastRow {
+    /// creates a new `BroadcastRow`, fill it in with `update_from_source`
+    pub fn new() -> Self {
+        Object::builder()
+            .build()
+    }
+
+    pub fn get_bluer_address(&self) -> bluer::Address {
+        *self.imp().address.borrow()
+    }
+
+    pub fn join_button(&self) -> gtk::Button {
+        self.imp().join_button.get()
+    }
+
+    /// shows everything the broadcast told us about itself
+    pub fn update_from_source(&self, source: &BroadcastSource) {
+        let imp = self.imp();
+        *imp.address.borrow_mut() = source.address;
+
+        self.set_title(&source.name);
+        self.set_subtitle(&source.summary());
+        self.set_joined(source.joined);
+        imp.private_update_rssi_icon(source.rssi);
+
+        imp.broadcast_id_row.get().set_subtitle(&format!("{:06X}", source.broadcast_id));
+        imp.address_row.get().set_subtitle(&source.address.to_string());
+
+        let contexts_row = imp.contexts_row.get();
+        contexts_row.set_visible(!source.contexts.is_empty());
+        contexts_row.set_subtitle(&source.contexts.join(", "));
+
+        let rating_row = imp.rating_row.get();
+        rating_row.set_visible(!source.parental_rating.is_empty());
+        rating_row.set_subtitle(&source.parental_rating);
+    }
+}
+
+impl Default for BroadcastRow {
+	fn default() -> Self {
+		Self::new()
+	}
+}
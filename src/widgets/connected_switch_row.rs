@@ -72,7 +72,6 @@ mod imp {
             if current_active == active {
                 return;
             }
-            // println!("current active for custom row is: {}", current_active);
 
             *self.active.borrow_mut() = active;
             self.spinner.set_spinning(true);
@@ -5,6 +5,8 @@ use gtk::subclass::prelude::*;
 use gtk::prelude::{ObjectExt, WidgetExt};
 use std::cell::RefCell;
 
+use crate::event_log::{self, Source};
+
 mod imp {
     use adw::glib::property::PropertySet;
     use super::*;
@@ -18,15 +20,27 @@ mod imp {
         pub rssi_icon: TemplateChild<gtk::Image>,
         #[template_child]
         pub connected_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub device_icon: TemplateChild<gtk::Image>,
 
         #[property(get, set)]
         pub rssi: RefCell<i32>,
         #[property(get, set = Self::private_set_adapter_name)]
         pub adapter_name: RefCell<String>,
+        #[property(get, set)]
+        pub adapter_alias: RefCell<String>,
         #[property(get, set = Self::private_set_current_connected)]
         pub is_connected: RefCell<bool>,
         #[property(get, set = Self::private_set_trusted)]
         pub trusted: RefCell<bool>,
+        #[property(get, set)]
+        pub paired: RefCell<bool>,
+        #[property(get, set)]
+        pub manufacturer: RefCell<String>,
+        #[property(get, set = Self::private_set_device_type)]
+        pub device_type: RefCell<String>,
+        #[property(get, set)]
+        pub beacon: RefCell<bool>,
 
         pub address: RefCell<bluer::Address>,
         pub adapter_address: RefCell<bluer::Address>,
@@ -88,6 +102,18 @@ mod imp {
         pub fn private_set_adapter_name(&self, name: String) {
             *self.adapter_name.borrow_mut() = name;
         }
+
+        /// the device type is a freedesktop icon name, so it doubles as the row's icon
+        pub fn private_set_device_type(&self, device_type: String) {
+            let icon_name = if device_type.is_empty() {
+                "bluetooth-symbolic".to_string()
+            } else {
+                device_type.clone() + "-symbolic"
+            };
+
+            self.device_icon.set_icon_name(Some(icon_name.as_str()));
+            *self.device_type.borrow_mut() = device_type;
+        }
     }
 }
 
@@ -142,7 +168,7 @@ impl DeviceActionRow {
                 "rssi-none-symbolic"
             }
             val => {
-                println!("rssi unknown value: {}", val);
+                event_log::debug(Source::Device, "signal strength out of range").field("rssi", val).record();
                 "rssi-not-found-symbolic"
             }
         };
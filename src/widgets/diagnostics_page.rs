@@ -0,0 +1,332 @@
+use adw::ApplicationWindow;
+use adw::gio::{ActionGroup, ActionMap};
+use glib::Object;
+use gtk::glib;
+use gtk::cairo;
+use adw::subclass::prelude::AdwApplicationWindowImpl;
+use gtk::subclass::prelude::*;
+use adw::prelude::ActionRowExt;
+use gtk::prelude::{WidgetExt, ButtonExt, DrawingAreaExtManual, GtkWindowExt, ObjectExt};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::link_info::{self, LinkInfo};
+use crate::rssi::{self, SmoothedRssi};
+use crate::window::runtime;
+
+/// how many seconds of signal strength the graph shows
+const HISTORY_LENGTH: usize = 120;
+/// the signal strengths at the bottom and top of the graph, in dBm
+const GRAPH_FLOOR: f64 = -100.0;
+const GRAPH_CEILING: f64 = -20.0;
+
+/// what's sampled about the device every second
+pub struct DiagnosticsSample {
+    link: Result<Option<LinkInfo>, String>,
+    scanned: Option<SmoothedRssi>,
+}
+
+mod imp {
+    use super::*;
+
+    /// graphs a device's signal over time, with what the controller says about its connection
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/kaii_lb/Overskride/gtk/diagnostics-page.ui")]
+    pub struct DiagnosticsPage {
+        #[template_child]
+        pub window_title: TemplateChild<adw::WindowTitle>,
+        #[template_child]
+        pub rssi_graph: TemplateChild<gtk::DrawingArea>,
+        #[template_child]
+        pub signal_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub connection_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub link_quality_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub tx_power_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub encryption_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub parameters_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub parameters_button: TemplateChild<gtk::Button>,
+
+        pub address: RefCell<Option<bluer::Address>>,
+        pub adapter_name: RefCell<String>,
+        /// one signal strength per second, oldest first, `None` for seconds without one
+        pub history: RefCell<VecDeque<Option<f64>>>,
+        /// how many samples the filter had the last time, so only new ones are graphed
+        pub scanned_samples: RefCell<u32>,
+        pub samples: RefCell<Option<async_channel::Receiver<DiagnosticsSample>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for DiagnosticsPage {
+        const NAME: &'static str = "DiagnosticsPage";
+        type Type = super::DiagnosticsPage;
+        type ParentType = adw::ApplicationWindow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for DiagnosticsPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj().downgrade();
+            self.rssi_graph.get().set_draw_func(move |_, context, width, height| {
+                if let Some(page) = obj.upgrade() {
+                    draw_graph(context, width as f64, height as f64, &page.imp().history.borrow());
+                }
+            });
+
+            let obj = self.obj().downgrade();
+            self.parameters_button.get().connect_clicked(move |_| {
+                if let Some(page) = obj.upgrade() {
+                    page.read_connection_parameters();
+                }
+            });
+
+            // sampling stops once nobody is listening
+            self.obj().connect_close_request(|page| {
+                if let Some(samples) = page.imp().samples.borrow().as_ref() {
+                    samples.close();
+                }
+                glib::Propagation::Proceed
+            });
+        }
+    }
+
+    impl WidgetImpl for DiagnosticsPage {}
+    impl AdwApplicationWindowImpl for DiagnosticsPage {}
+    impl ApplicationWindowImpl for DiagnosticsPage {}
+    impl WindowImpl for DiagnosticsPage {}
+}
+
+glib::wrapper! {
+    pub struct DiagnosticsPage(ObjectSubclass<imp::DiagnosticsPage>)
+    @extends ApplicationWindow, gtk::Widget, gtk::Window, gtk::ApplicationWindow,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager, ActionGroup, ActionMap;
+}
+
+impl DiagnosticsPage {
+    /// creates a new `DiagnosticsPage`
+    pub fn new() -> Self {
+        Object::builder()
+            .build()
+    }
+
+    /// starts sampling the device every second until the page is closed
+    pub fn start(&self, name: String, address: bluer::Address, adapter_name: String) {
+        self.imp().window_title.get().set_subtitle(&name);
+        *self.imp().address.borrow_mut() = Some(address);
+        *self.imp().adapter_name.borrow_mut() = adapter_name.clone();
+        *self.imp().history.borrow_mut() = std::iter::repeat_n(None, HISTORY_LENGTH).collect();
+
+        let (sender, receiver) = async_channel::unbounded();
+        *self.imp().samples.borrow_mut() = Some(receiver.clone());
+
+        std::thread::spawn(move || {
+            let adapter_index = link_info::adapter_index(&adapter_name);
+
+            while !sender.is_closed() {
+                let link = match adapter_index {
+                    Some(index) => link_info::read(index, address).map_err(|err| err.to_string()),
+                    None => Err(format!("{} isn't an hci adapter", adapter_name)),
+                };
+
+                let sample = DiagnosticsSample {
+                    link,
+                    scanned: rssi::smoothed(address),
+                };
+                if sender.send_blocking(sample).is_err() {
+                    break;
+                }
+
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        });
+
+        let page = self.downgrade();
+        glib::spawn_future_local(async move {
+            while let Ok(sample) = receiver.recv().await {
+                let Some(page) = page.upgrade() else {
+                    break;
+                };
+                page.show_sample(sample);
+            }
+        });
+    }
+
+    fn show_sample(&self, sample: DiagnosticsSample) {
+        let imp = self.imp();
+
+        // a low energy connection's rssi is in dBm, otherwise only new samples from searching are graphed
+        let connected_rssi = match &sample.link {
+            Ok(Some(link)) if link.le => link.rssi.map(|rssi| rssi as f64),
+            _ => None,
+        };
+        let scanned_rssi = sample.scanned
+            .filter(|scanned| scanned.samples != *imp.scanned_samples.borrow())
+            .map(|scanned| scanned.rssi as f64);
+        *imp.scanned_samples.borrow_mut() = sample.scanned.map(|scanned| scanned.samples).unwrap_or(0);
+
+        let point = connected_rssi.or(scanned_rssi);
+        {
+            let mut history = imp.history.borrow_mut();
+            history.push_back(point);
+            while history.len() > HISTORY_LENGTH {
+                history.pop_front();
+            }
+        }
+        imp.rssi_graph.get().queue_draw();
+
+        let signal_row = imp.signal_row.get();
+        match (connected_rssi, sample.scanned) {
+            (Some(rssi), _) => signal_row.set_subtitle(&format!("{} dBm on the connection", rssi)),
+            (None, Some(scanned)) => signal_row.set_subtitle(&format!("{:.0} dBm filtered, ± {:.1} dB, from {} samples while searching", scanned.rssi, scanned.spread, scanned.samples)),
+            (None, None) => signal_row.set_subtitle("no samples, search for devices to get some"),
+        }
+
+        let connection_row = imp.connection_row.get();
+        let link_quality_row = imp.link_quality_row.get();
+        let tx_power_row = imp.tx_power_row.get();
+        let encryption_row = imp.encryption_row.get();
+
+        let link = match sample.link {
+            Ok(Some(link)) => link,
+            Ok(None) => {
+                connection_row.set_subtitle("not connected");
+                for row in [&link_quality_row, &tx_power_row, &encryption_row] {
+                    row.set_subtitle("only known while connected");
+                }
+                return;
+            }
+            Err(err) => {
+                connection_row.set_subtitle(&format!("cannot ask the controller: {}", err));
+                for row in [&link_quality_row, &tx_power_row, &encryption_row] {
+                    row.set_subtitle("unknown");
+                }
+                return;
+            }
+        };
+
+        let kind = if link.le { "Low Energy" } else { "Classic" };
+        let role = if link.central { "this adapter is central" } else { "this adapter is peripheral" };
+        let mut connection = format!("{}, {}", kind, role);
+        if let (false, Some(rssi)) = (link.le, link.rssi) {
+            connection += &match rssi {
+                0 => ", signal in the golden range".to_string(),
+                rssi => format!(", signal {} dB off the golden range", rssi),
+            };
+        }
+        connection_row.set_subtitle(&connection);
+
+        link_quality_row.set_subtitle(&match (link.le, link.link_quality) {
+            (true, _) => "not reported for low energy".to_string(),
+            (false, Some(quality)) => format!("{} of 255", quality),
+            (false, None) => "the controller didn't say".to_string(),
+        });
+
+        tx_power_row.set_subtitle(&link.tx_power.map(|power| format!("{} dBm", power)).unwrap_or("the controller didn't say".to_string()));
+
+        let encryption = match (link.encrypted, link.authenticated, link.secure) {
+            (false, _, _) => "not encrypted".to_string(),
+            (true, false, _) => "encrypted, unauthenticated".to_string(),
+            (true, true, false) => "encrypted, authenticated".to_string(),
+            (true, true, true) => "encrypted, authenticated, secure connections".to_string(),
+        };
+        let key_size = match (link.encrypted, link.key_size) {
+            (true, Some(key_size)) => format!(", {} bit key", key_size as u32 * 8),
+            _ => String::new(),
+        };
+        encryption_row.set_subtitle(&(encryption + &key_size));
+    }
+
+    /// asks for the password to read the connection parameters bluez stored for the device
+    fn read_connection_parameters(&self) {
+        let Some(address) = *self.imp().address.borrow() else {
+            return;
+        };
+        let adapter_name = self.imp().adapter_name.borrow().clone();
+
+        let parameters_button = self.imp().parameters_button.get();
+        parameters_button.set_sensitive(false);
+
+        let (sender, receiver) = async_channel::bounded(1);
+        runtime().spawn(async move {
+            let parameters = link_info::read_connection_parameters(adapter_name, address).await;
+            let _ = sender.send(parameters).await;
+        });
+
+        let page = self.downgrade();
+        glib::spawn_future_local(async move {
+            let Ok(parameters) = receiver.recv().await else {
+                return;
+            };
+            let Some(page) = page.upgrade() else {
+                return;
+            };
+
+            let parameters_row = page.imp().parameters_row.get();
+            match parameters {
+                Ok(Some(parameters)) => parameters_row.set_subtitle(&parameters.describe()),
+                Ok(None) => parameters_row.set_subtitle("bluez has none stored, the device never asked for its own"),
+                Err(err) => parameters_row.set_subtitle(&err.explanation()),
+            }
+            page.imp().parameters_button.get().set_sensitive(true);
+        });
+    }
+}
+
+impl Default for DiagnosticsPage {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// draws the signal strength history as a line over a grid every 20 dBm, leaving gaps where there were no samples
+fn draw_graph(context: &cairo::Context, width: f64, height: f64, history: &VecDeque<Option<f64>>) {
+    let y_for = |rssi: f64| height - (rssi.clamp(GRAPH_FLOOR, GRAPH_CEILING) - GRAPH_FLOOR) / (GRAPH_CEILING - GRAPH_FLOOR) * height;
+    let step = width / (HISTORY_LENGTH - 1) as f64;
+
+    context.set_line_width(1.0);
+    context.set_source_rgba(0.5, 0.5, 0.5, 0.3);
+    context.set_font_size(10.0);
+    let mut rssi = GRAPH_CEILING - 20.0;
+    while rssi > GRAPH_FLOOR {
+        let y = y_for(rssi);
+        context.move_to(0.0, y);
+        context.line_to(width, y);
+        let _ = context.stroke();
+
+        context.move_to(4.0, y - 3.0);
+        let _ = context.show_text(&format!("{}", rssi));
+        rssi -= 20.0;
+    }
+
+    context.set_line_width(2.0);
+    context.set_source_rgb(0.21, 0.52, 0.89);
+
+    let mut drawing = false;
+    for (index, point) in history.iter().enumerate() {
+        let x = index as f64 * step;
+        match point {
+            Some(rssi) if drawing => context.line_to(x, y_for(*rssi)),
+            Some(rssi) => {
+                context.move_to(x, y_for(*rssi));
+                drawing = true;
+            }
+            None => drawing = false,
+        }
+    }
+    let _ = context.stroke();
+}
@@ -0,0 +1,255 @@
+use adw::ApplicationWindow;
+use adw::gio::{ActionGroup, ActionMap};
+use glib::Object;
+use gtk::glib;
+use adw::subclass::prelude::AdwApplicationWindowImpl;
+use gtk::subclass::prelude::*;
+use adw::prelude::{ActionRowExt, ComboRowExt, PreferencesRowExt};
+use gtk::prelude::{WidgetExt, ButtonExt, GtkWindowExt, ObjectExt, DialogExt, DialogExtManual, FileChooserExt, FileExt, ListBoxRowExt};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use crate::event_log::{self, Level, LogEntry, MAX_ENTRIES};
+
+mod imp {
+    use super::*;
+
+    /// shows what happened with the adapter, devices, agents and transfers as it happens
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/io/github/kaii_lb/Overskride/gtk/event-log-page.ui")]
+    pub struct EventLogPage {
+        #[template_child]
+        pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub export_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub clear_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub device_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub device_list: TemplateChild<gtk::StringList>,
+        #[template_child]
+        pub debug_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub entries_listbox: TemplateChild<gtk::ListBox>,
+
+        /// the entries shown, newest first like their rows
+        pub entries: RefCell<VecDeque<LogEntry>>,
+        /// the devices in the device filter, after "All Devices"
+        pub devices: RefCell<Vec<bluer::Address>>,
+        pub receiver: RefCell<Option<async_channel::Receiver<LogEntry>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EventLogPage {
+        const NAME: &'static str = "EventLogPage";
+        type Type = super::EventLogPage;
+        type ParentType = adw::ApplicationWindow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for EventLogPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj().downgrade();
+            self.entries_listbox.get().set_filter_func(move |row| {
+                let Some(page) = obj.upgrade() else {
+                    return true;
+                };
+                let entries = page.imp().entries.borrow();
+                entries.get(row.index() as usize).is_none_or(|entry| page.shows(entry))
+            });
+
+            let listbox = self.entries_listbox.get();
+            self.device_row.get().connect_selected_notify(move |_| {
+                listbox.invalidate_filter();
+            });
+
+            let listbox = self.entries_listbox.get();
+            self.debug_row.get().connect_active_notify(move |_| {
+                listbox.invalidate_filter();
+            });
+
+            let obj = self.obj().downgrade();
+            self.export_button.get().connect_clicked(move |_| {
+                if let Some(page) = obj.upgrade() {
+                    page.choose_export_file();
+                }
+            });
+
+            let obj = self.obj().downgrade();
+            self.clear_button.get().connect_clicked(move |_| {
+                if let Some(page) = obj.upgrade() {
+                    event_log::clear();
+                    page.clear_rows();
+                }
+            });
+
+            // entries stop coming once nobody is listening
+            self.obj().connect_close_request(|page| {
+                if let Some(receiver) = page.imp().receiver.borrow().as_ref() {
+                    receiver.close();
+                }
+                glib::Propagation::Proceed
+            });
+        }
+    }
+
+    impl WidgetImpl for EventLogPage {}
+    impl AdwApplicationWindowImpl for EventLogPage {}
+    impl ApplicationWindowImpl for EventLogPage {}
+    impl WindowImpl for EventLogPage {}
+}
+
+glib::wrapper! {
+    pub struct EventLogPage(ObjectSubclass<imp::EventLogPage>)
+    @extends ApplicationWindow, gtk::Widget, gtk::Window, gtk::ApplicationWindow,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager, ActionGroup, ActionMap;
+}
+
+impl EventLogPage {
+    /// creates a new `EventLogPage`
+    pub fn new() -> Self {
+        Object::builder()
+            .build()
+    }
+
+    /// shows the entries kept so far, then every new one until the page is closed
+    pub fn start(&self) {
+        let (entries, receiver) = event_log::subscribe();
+        *self.imp().receiver.borrow_mut() = Some(receiver.clone());
+
+        for entry in entries {
+            self.add_entry(entry);
+        }
+
+        let page = self.downgrade();
+        glib::spawn_future_local(async move {
+            while let Ok(entry) = receiver.recv().await {
+                let Some(page) = page.upgrade() else {
+                    break;
+                };
+                page.add_entry(entry);
+            }
+        });
+    }
+
+    /// whether the entry passes the device filter, and isn't a hidden debug one
+    fn shows(&self, entry: &LogEntry) -> bool {
+        let imp = self.imp();
+
+        if entry.level == Level::Debug && !imp.debug_row.get().is_active() {
+            return false;
+        }
+
+        match imp.device_row.get().selected() {
+            0 | gtk::INVALID_LIST_POSITION => true,
+            selected => imp.devices.borrow().get(selected as usize - 1).is_some_and(|address| entry.device == Some(*address)),
+        }
+    }
+
+    fn add_entry(&self, entry: LogEntry) {
+        let imp = self.imp();
+
+        if let Some(address) = entry.device {
+            if !imp.devices.borrow().contains(&address) {
+                imp.devices.borrow_mut().push(address);
+                imp.device_list.get().append(&address.to_string());
+            }
+        }
+
+        let device = entry.device.map(|address| address.to_string()).unwrap_or_default();
+        let time = entry.time.format("%H:%M:%S").map(|time| time.to_string()).unwrap_or_default();
+        let subtitle = [time, entry.source.name().to_string(), device, entry.describe_fields()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<String>>()
+            .join("  ·  ");
+
+        let row = adw::ActionRow::new();
+        row.set_use_markup(false);
+        row.set_title(&entry.message);
+        row.set_subtitle(&subtitle);
+        row.set_subtitle_selectable(true);
+
+        let icon = match entry.level {
+            Level::Warning => Some("dialog-warning-symbolic"),
+            Level::Error => Some("dialog-error-symbolic"),
+            _ => None,
+        };
+        if let Some(icon) = icon {
+            let image = gtk::Image::from_icon_name(icon);
+            image.add_css_class(entry.level.name());
+            row.add_prefix(&image);
+        }
+
+        let listbox = imp.entries_listbox.get();
+        imp.entries.borrow_mut().push_front(entry);
+        listbox.prepend(&row);
+
+        while imp.entries.borrow().len() > MAX_ENTRIES {
+            imp.entries.borrow_mut().pop_back();
+            if let Some(oldest) = listbox.row_at_index(MAX_ENTRIES as i32) {
+                listbox.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear_rows(&self) {
+        let listbox = self.imp().entries_listbox.get();
+        while let Some(row) = listbox.row_at_index(0) {
+            listbox.remove(&row);
+        }
+        self.imp().entries.borrow_mut().clear();
+    }
+
+    /// asks where to save the entries shown, oldest first, one per line
+    fn choose_export_file(&self) {
+        let dialog = gtk::FileChooserDialog::new(Some("Export Event Log To"),
+            Some(self),
+            gtk::FileChooserAction::Save,
+            &[("Cancel", gtk::ResponseType::Cancel),
+              ("Export", gtk::ResponseType::Accept)
+        ]);
+        dialog.set_destroy_with_parent(true);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+        dialog.set_modal(true);
+        dialog.set_current_name("overskride-events.log");
+
+        let page = self.downgrade();
+        dialog.run_async(move |file_chooser, response| {
+            let path = file_chooser.file().and_then(|file| file.path());
+            file_chooser.destroy();
+
+            let (gtk::ResponseType::Accept, Some(path), Some(page)) = (response, path, page.upgrade()) else {
+                return;
+            };
+
+            let lines = page.imp().entries.borrow().iter()
+                .rev()
+                .filter(|entry| page.shows(entry))
+                .map(|entry| entry.describe() + "\n")
+                .collect::<String>();
+
+            let toast = match std::fs::write(&path, lines) {
+                Ok(()) => adw::Toast::new("event log exported"),
+                Err(err) => adw::Toast::new(&format!("cannot export the event log: {}", err)),
+            };
+            page.imp().toast_overlay.get().add_toast(toast);
+        });
+    }
+}
+
+impl Default for EventLogPage {
+	fn default() -> Self {
+		Self::new()
+	}
+}
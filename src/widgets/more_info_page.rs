@@ -6,7 +6,12 @@ use adw::subclass::prelude::AdwApplicationWindowImpl;
 use gtk::subclass::prelude::*;
 use adw::prelude::PreferencesRowExt;
 use adw::prelude::ExpanderRowExt;
-use gtk::prelude::WidgetExt;
+use adw::prelude::ActionRowExt;
+use gtk::prelude::{WidgetExt, ButtonExt};
+use std::cell::RefCell;
+use crate::identity::DeviceIdentity;
+use crate::rssi::{self, DistanceEstimate};
+use crate::event_log::{self, Source};
 
 mod imp {
     use super::*;
@@ -24,9 +29,20 @@ mod imp {
         #[template_child]
         pub type_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub class_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub appearance_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub vendor_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
         pub distance_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub calibrate_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub services_row: TemplateChild<adw::ExpanderRow>,
+
+        pub address: RefCell<Option<bluer::Address>>,
+        pub tx_power: RefCell<Option<i16>>,
     }
 
     #[glib::object_subclass]
@@ -47,6 +63,13 @@ mod imp {
     impl ObjectImpl for MoreInfoPage {
         fn constructed(&self) {
             self.parent_constructed();
+
+            let obj = self.obj().downgrade();
+            self.calibrate_button.get().connect_clicked(move |_| {
+                if let Some(page) = obj.upgrade() {
+                    page.calibrate();
+                }
+            });
         }
     }
 
@@ -71,12 +94,38 @@ impl MoreInfoPage {
             .build()
     }
 
-    pub fn initialize_from_info(&self, name: String, address: String, manufacturer: String, device_type: String, distance: String, services_list: Vec<String>) {
+    pub fn initialize_from_info(&self, name: String, address: String, tx_power: Option<i16>, distance: Option<DistanceEstimate>, services_list: Vec<String>, identity: DeviceIdentity) {
+		*self.imp().address.borrow_mut() = address.parse::<bluer::Address>().ok();
+		*self.imp().tx_power.borrow_mut() = tx_power;
+
 		self.imp().name_row.get().set_title(&("Name: ".to_string() + &name));
 		self.imp().address_row.get().set_title(&("Address: ".to_string() + &address));
-		self.imp().manufacturer_row.get().set_title(&("Manufacturer: ".to_string() + &manufacturer));
-		self.imp().type_row.get().set_title(&("Type: ".to_string() + &device_type));
-		self.imp().distance_row.get().set_title(&("Distance: ".to_string() + &distance));
+
+		// the first manufacturer is in the title, the rest (usually chip makers) go below it
+		let manufacturer_row = self.imp().manufacturer_row.get();
+		let manufacturer = identity.maker().unwrap_or("Unknown".to_string());
+		let others = identity.manufacturers.iter().filter(|other| **other != manufacturer).cloned().collect::<Vec<String>>();
+		manufacturer_row.set_title(&("Manufacturer: ".to_string() + &manufacturer));
+		manufacturer_row.set_subtitle(&others.join(", "));
+
+		let type_row = self.imp().type_row.get();
+		type_row.set_title(&("Type: ".to_string() + &identity.kind.unwrap_or("Unknown".to_string())));
+
+		let class_row = self.imp().class_row.get();
+		class_row.set_visible(identity.class.is_some());
+		class_row.set_title(&("Class: ".to_string() + &identity.class.unwrap_or_default()));
+		class_row.set_subtitle(&identity.service_classes.join(", "));
+
+		let appearance_row = self.imp().appearance_row.get();
+		appearance_row.set_visible(identity.appearance.is_some());
+		appearance_row.set_title(&("Appearance: ".to_string() + &identity.appearance.unwrap_or_default()));
+
+		let vendor_row = self.imp().vendor_row.get();
+		vendor_row.set_visible(identity.vendor.is_some());
+		vendor_row.set_title(&("Vendor: ".to_string() + &identity.vendor.unwrap_or_default()));
+		vendor_row.set_subtitle(&("Product ".to_string() + &identity.product.unwrap_or_default()));
+
+		self.show_distance(distance);
 
 		let expander_row = self.imp().services_row.get();
 		expander_row.set_title("Available Services");
@@ -97,6 +146,44 @@ impl MoreInfoPage {
     }
 }
 
+impl MoreInfoPage {
+    /// shows the distance estimate with the range it's probably in, or that it's unknown without any signal to go by
+    fn show_distance(&self, distance: Option<DistanceEstimate>) {
+		let distance_row = self.imp().distance_row.get();
+
+		match distance {
+			Some(distance) => {
+				distance_row.set_title(&("Distance: ".to_string() + &distance.describe()));
+				distance_row.set_subtitle(&distance.describe_range());
+			}
+			None => {
+				distance_row.set_title("Distance: Unknown");
+				distance_row.set_subtitle("search for devices to get its signal strength");
+			}
+		}
+    }
+
+    /// takes the device's current signal as its signal at 1 meter, so distances are estimated from that
+    fn calibrate(&self) {
+		let Some(address) = *self.imp().address.borrow() else {
+			return;
+		};
+
+		match rssi::calibrate(address) {
+			Some(measured_power) => {
+				event_log::info(Source::Device, "distance calibrated").device(address).field("rssi", measured_power).record();
+				self.show_distance(rssi::estimate_distance(address, *self.imp().tx_power.borrow()));
+			}
+			None => {
+				self.imp().distance_row.get().set_subtitle(&format!(
+					"needs {} signal samples first, keep searching for devices with it 1 meter away",
+					rssi::MIN_CALIBRATION_SAMPLES
+				));
+			}
+		}
+    }
+}
+
 impl Default for MoreInfoPage {
 	fn default() -> Self {
 		Self::new()
@@ -5,6 +5,7 @@ use gtk::prelude::{IsA, Cast};
 use gtk::subclass::prelude::*;
 
 use crate::receiving_row::ReceivingRow;
+use crate::event_log::{self, Source};
 
 mod imp {
     use super::*;    
@@ -62,7 +63,7 @@ impl ReceivingPopover {
         let listbox = self.imp().listbox.get();
         listbox.append(row);
 
-        println!("added row");
+        event_log::debug(Source::Obex, "transfer row added").record();
 
         if listbox.row_at_index(2).is_some() {
             listbox.set_show_separators(true);
@@ -79,7 +80,7 @@ impl ReceivingPopover {
             if let Ok(receiving_row) = row.clone().downcast::<ReceivingRow>() {
                 if receiving_row.transfer().contains(&transfer) && receiving_row.filename().contains(&filename) {
                     listbox.remove(&row);
-                    println!("removed row");
+                    event_log::debug(Source::Obex, "transfer row removed").field("transfer", &transfer).field("file", &filename).record();
                 }
             }
 
@@ -112,7 +113,7 @@ impl ReceivingPopover {
 
             index += 1;
         }
-        println!("unknown row {} {}", transfer, filename);
+        event_log::debug(Source::Obex, "no row for the transfer").field("transfer", transfer).field("file", filename).record();
         None
     }
 }
@@ -7,6 +7,7 @@ use std::cell::RefCell;
 use gtk::prelude::WidgetExt;
 
 use crate::obex::CANCEL;
+use crate::event_log::{self, Source};
 
 mod imp {
     use super::*;    
@@ -88,7 +89,6 @@ mod imp {
         
         pub fn private_set_progress_bar_fraction(&self, fraction: f32) {
             let holder = (fraction / 100.0) as f64;
-            // println!("divved {}", holder);
             self.progress_bar.get().set_fraction(holder.clamp(0.0, 1.0));
         }
         
@@ -173,7 +173,7 @@ impl ReceivingRow {
             // notify user of "special case", this is most likely it's still running
             e => {
                 if !e.is_empty() {
-                    println!("special icon case: {}", e);
+                    event_log::debug(Source::Obex, "transfer has an unknown status").field("status", e).record();
                 }
                 self_destruct = false;
                 "cross-large-symbolic"
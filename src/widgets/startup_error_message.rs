@@ -3,6 +3,9 @@ use glib::Object;
 use gtk::glib;
 use adw::subclass::prelude::AdwApplicationWindowImpl;
 use gtk::subclass::prelude::*;
+use gtk::prelude::WidgetExt;
+
+use crate::error::OverskrideError;
 
 mod imp {
     use adw::prelude::ButtonExt;
@@ -17,6 +20,8 @@ mod imp {
         pub run_enable_bluetooth_button: TemplateChild<gtk::Button>,
         #[template_child]
         pub error_toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub error_status_page: TemplateChild<adw::StatusPage>,
     }
 
     #[glib::object_subclass]
@@ -89,6 +94,23 @@ impl StartupErrorMessage {
         Object::builder()
             .build()
     }
+
+    /// Shows what went wrong and how to fix it. The commands for starting the bluetooth service stay
+    /// for when that's the problem, or when we can't tell what is.
+    pub fn set_error(&self, error: &OverskrideError) {
+        let status_page = self.imp().error_status_page.get();
+        let service_problem = matches!(error, OverskrideError::BluezNotRunning | OverskrideError::Unknown(_));
+
+        if !matches!(error, OverskrideError::Unknown(_)) {
+            status_page.set_title(&error.explanation());
+        }
+
+        if let Some(suggestion) = error.suggestion().filter(|_| !service_problem) {
+            status_page.set_description(Some(suggestion));
+        }
+
+        self.imp().run_enable_bluetooth_button.get().set_visible(service_problem);
+    }
 }
 
 impl Default for StartupErrorMessage {
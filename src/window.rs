@@ -24,18 +24,37 @@ use gtk::glib::clone;
 use gtk::glib::SignalHandlerId;
 use gtk::{gio, glib, Accessible, Buildable, ConstraintTarget, Grid, Native, ResponseType, Root, ShortcutManager, Widget, Window};
 
-use crate::agent::register_bluetooth_agent;
 use crate::application::OverskrideApplication;
 use crate::audio_profiles;
+use crate::audio_sink;
 use crate::bluetooth_settings::get_store_location_from_dialog;
+use crate::broadcast;
+use crate::broadcast_row::BroadcastRow;
+use crate::dbus_api::{self, ApiSignal};
+use crate::error::OverskrideError;
 use crate::device_action_row::DeviceActionRow;
+use crate::identity;
+use crate::advertisement;
+use crate::reconnect;
+use crate::supervisor::{self, Agent, AgentHealth};
+use crate::audio_priority;
+use crate::backup;
+use crate::windows_keys;
+use crate::device_store;
+use crate::rssi;
 use crate::message::Message;
 use crate::more_info_page::MoreInfoPage;
-use crate::obex::{self, register_obex_agent};
+use crate::diagnostics_page::DiagnosticsPage;
+use crate::event_log_page::EventLogPage;
+use crate::event_log::{self, Source};
+use crate::obex;
 use crate::receiving_row::ReceivingRow;
 use crate::selectable_row::SelectableRow;
 use crate::services::get_name_from_service;
 use crate::startup_error_message::StartupErrorMessage;
+use crate::rfkill;
+use crate::telephony;
+use crate::tray;
 use crate::{bluetooth_settings, connected_switch_row::ConnectedSwitchRow, device};
 use crate::Singletons::OverskrideProperties;
 
@@ -44,7 +63,6 @@ use async_channel::Sender;
 use std::cell::{OnceCell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
-use std::str::FromStr;
 use std::sync::{Mutex, OnceLock};
 use tokio::runtime::Runtime;
 use lazy_static::lazy_static;
@@ -55,8 +73,10 @@ lazy_static! {
 
 // U N S A F E T Y
 static mut RSSI_LUT: Option<HashMap<String, i32>> = None;
-pub static mut DEVICES_LUT: Option<HashMap<bluer::Address, String>> = None;
 pub static mut ADAPTERS_LUT: Option<HashMap<String, String>> = None;
+/// the name of every listed device, by the adapter that found it and its address.
+/// with every adapter shown each one searches in its own task, so this is locked rather than copied and written back
+pub static DEVICES_LUT: Mutex<Option<HashMap<(String, bluer::Address), String>>> = Mutex::new(None);
 
 mod imp {
     use crate::{
@@ -129,14 +149,141 @@ mod imp {
         #[template_child]
         pub more_info_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub diagnostics_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
         pub window_title: TemplateChild<adw::WindowTitle>,
         #[template_child]
         pub hide_unknowns_switch_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub audio_sink_switch_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub streaming_device_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub call_integration_switch_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub radio_banner: TemplateChild<adw::Banner>,
+        #[template_child]
+        pub run_in_background_switch_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub device_search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub call_bar_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub call_bar_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub call_bar_title: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub call_bar_subtitle: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub answer_call_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub hangup_call_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub broadcast_listbox: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub broadcast_scan_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub lc3_configuration_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub show_all_adapters_switch_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub connect_using_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub connect_using_list: TemplateChild<gtk::StringList>,
+        #[template_child]
+        pub discovery_transport_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub discovery_rssi_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub discovery_pathloss_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub discovery_uuids_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub discovery_pattern_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub discovery_duplicate_data_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub scan_spinner: TemplateChild<gtk::Spinner>,
+        #[template_child]
+        pub keep_connected_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub scan_timeout_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub scan_when_focused_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub audio_priority_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub audio_switching_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub distance_environment_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub distance_environment_list: TemplateChild<gtk::StringList>,
+        #[template_child]
+        pub audio_priority_listbox: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub profiles_expander: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        pub bluetooth_agent_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub bluetooth_agent_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub obex_agent_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub obex_agent_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub backup_keys_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub export_backup_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub import_backup_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub windows_keys_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub device_icon_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub device_icon_list: TemplateChild<gtk::StringList>,
+        #[template_child]
+        pub preferred_profile_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub preferred_profile_list: TemplateChild<gtk::StringList>,
+        #[template_child]
+        pub battery_alert_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub auto_connect_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub auto_accept_files_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub proximity_row: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        pub proximity_away_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub proximity_back_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub proximity_lock_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub proximity_unlock_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub proximity_away_command_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub proximity_back_command_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub download_folder_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub notes_row: TemplateChild<adw::EntryRow>,
 
         pub settings: OnceCell<Settings>,
         pub display_pass_key_dialog: RefCell<Option<adw::MessageDialog>>,
         pub index: RefCell<u32>,
         pub timeout_signal_id: OnceCell<SignalHandlerId>,
+        pub connect_using_adapters: RefCell<Vec<String>>,
+        pub scan_timeout_id: RefCell<Option<glib::SourceId>>,
+        /// waits for the signal and path loss rows to settle, so each step of them doesn't restart discovery
+        pub discovery_settings_timeout_id: RefCell<Option<glib::SourceId>>,
+        pub profile_rows: RefCell<Vec<adw::ActionRow>>,
+        /// the profile names behind the preferred audio profile row's choices, "" being "don't change"
+        pub preferred_profiles: RefCell<Vec<String>>,
+        /// set while the device's settings are put in their rows, so that doesn't count as changing them
+        pub filling_device_settings: RefCell<bool>,
     }
 
     #[glib::object_subclass]
@@ -152,6 +299,9 @@ mod imp {
             SelectableRow::ensure_type();
             BatteryLevelIndicator::ensure_type();
             MoreInfoPage::ensure_type();
+            DiagnosticsPage::ensure_type();
+            EventLogPage::ensure_type();
+            BroadcastRow::ensure_type();
 
             klass.bind_template();
             /*klass.install_action("win.refresh_devices", None, move |win, _, _| {
@@ -230,11 +380,11 @@ impl OverskrideWindow {
         // suggest solutions
         // then profit
         if let Err(err) = self.pre_setup(sender.clone()) {
-            println!("ERROR: cannot start presetup, something got REALLY fucked");
-            println!("error is: {:?}", err);
+            event_log::error(Source::App, "cannot start, bluetooth isn't usable").field("error", &err).record();
 
             let clone = self.clone();
             let message = StartupErrorMessage::new();
+            message.set_error(&err.into());
 
             message.set_transient_for(Some(&clone));
             message.set_modal(true);
@@ -270,6 +420,10 @@ impl OverskrideWindow {
                         let listbox = clone.imp().main_listbox.get();
                         let mut listbox_index = 0;
 
+                        if address != bluer::Address::any() {
+                            dbus_api::emit(ApiSignal::ConnectionChanged(address, active));
+                        }
+
                         if is_current {
                             let connected_switch_row = clone.imp().connected_switch_row.get();
                             connected_switch_row.set_toggle_switch_active(active);
@@ -288,7 +442,6 @@ impl OverskrideWindow {
                             let action_row = row.downcast::<DeviceActionRow>().expect("cannot downcast to action row.");
                             if action_row.get_bluer_address() == address {
                                 action_row.set_connected(active);
-                                // println!("connected: {}", action_row.connected());
                             } else if address == bluer::Address::any() {
                                 action_row.set_connected(false);
                             }
@@ -316,9 +469,7 @@ impl OverskrideWindow {
                             }
                         } else {
                             while let Some(row) = list_box.clone().row_at_index(listbox_index) {
-                                //println!("{}", index);
                                 let action_row = row.downcast::<DeviceActionRow>().expect("cannot downcast to action row.");
-                                //println!("{:?}", action_row.clone().title());
                                 if action_row.title() == optional_old_alias.clone().unwrap() && action_row.get_bluer_address() == address {
                                     action_row.set_title(alias.as_str());
                                 }
@@ -341,8 +492,6 @@ impl OverskrideWindow {
                         while let Some(row) = list_box.row_at_index(listbox_index) {
                             let action_row = row.downcast::<DeviceActionRow>().expect("cannot downcast to device action row.");
 
-                            // println!("device {}, with rssi {} changed", device_name.clone(), rssi);
-
                             if action_row.title() == device_name {
                                 // not sure why those two aren't in the same function
                                 action_row.set_row_rssi(rssi);
@@ -359,14 +508,14 @@ impl OverskrideWindow {
                             let hide_unknowns_switch_row = clone.imp().hide_unknowns_switch_row.get();
                             let is_active = hide_unknowns_switch_row.is_active();
 
-                            if !(ok_row.title() == "Unknown Device" && is_active) {
+                            if !(is_unknown_device(&ok_row) && is_active) {
                                 let main_listbox = clone.imp().main_listbox.get();
                                 main_listbox.append(&ok_row);
                                 main_listbox.invalidate_sort();
                             }
                         }
                     }
-                    Message::RemoveDevice(name, address) => {
+                    Message::RemoveDevice(name, address, adapter_name) => {
                         let listbox = clone.clone().imp().main_listbox.get();
                         let mut index = 0;
                         let mut selected = true;
@@ -377,11 +526,11 @@ impl OverskrideWindow {
                             let hide_unknowns_switch_row = clone.imp().hide_unknowns_switch_row.get();
                             let is_active = hide_unknowns_switch_row.is_active();
 
-                            if action_row.title() == "Unknown Device" && is_active {
+                            if is_unknown_device(&action_row) && is_active {
                                 listbox.remove(&action_row);
                             }
 
-                            if action_row.title() == name && action_row.get_bluer_address() == address {
+                            if action_row.title() == name && action_row.get_bluer_address() == address && action_row.adapter_name() == adapter_name {
                                 action_row.set_connected(false);
 
                                 if let Some(selected_row) = listbox.selected_row() {
@@ -419,7 +568,7 @@ impl OverskrideWindow {
                             let final_icon_name = icon.clone() + "-symbolic";
 
                             device_icon.set_icon_name(Some(final_icon_name.as_str()));
-                            println!("icon name is: {}", icon);
+                            event_log::debug(Source::App, "device page icon set").field("icon", &icon).record();
                         }
 
                         let secondary_listbox = clone.imp().secondary_listbox.get();
@@ -495,8 +644,6 @@ impl OverskrideWindow {
                             let holder = OVERSKRIDE_PROPS.lock().unwrap().name.to_string();
 
                             let name = val.clone().unwrap_or(holder);
-                            //println!("name is {}", name.clone());
-                            //println!("alias is {}", alias.clone());
 
                             row.set_title(alias.as_str());
 
@@ -522,19 +669,17 @@ impl OverskrideWindow {
                                     let mut index = 0;
                                     if listbox_clone.clone().is_ok() {
                                         while let Some(row_to_set_selected) = listbox_clone.clone().unwrap().row_at_index(index) {
-                                            //println!("{}", index);
                                             let action_row = row_to_set_selected.downcast::<SelectableRow>().expect("cannot downcast to action row.");
-                                            //println!("{:?}", action_row.clone().title());
                                             action_row.set_selected(false);
                                             index += 1;
                                         }
                                     }
 
                                     OVERSKRIDE_PROPS.lock().unwrap().current_adapter = name.to_string();
-                                    println!("current adapter name is: {}", OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone());
+                                    event_log::info(Source::Adapter, "switched adapter").field("adapter", name).record();
 
                                     if sender_clone.send(Message::RefreshDevicesList()).await.is_err() {
-                                        sender_clone.send(Message::PopupError("bt-refresh-adapter-failed".to_string(), adw::ToastPriority::High)).await.expect("cannot send message");
+                                        sender_clone.send(Message::PopupError(OverskrideError::AdapterRefreshFailed, adw::ToastPriority::High)).await.expect("cannot send message");
                                     }
                                     row_to_set_selected.set_selected(true);
                                 });
@@ -543,136 +688,38 @@ impl OverskrideWindow {
                             default_controller_expander.add_row(&row);
                         }
                     }
-                    Message::PopupError(string, priority) => {
+                    Message::PopupError(error, priority) => {
                         let toast_overlay = clone.imp().toast_overlay.get();
                         let toast = adw::Toast::new("");
 
                         toast.set_priority(priority);
 
-                        // best practices out the window :D
-                        // need to ~hashmap~ this shit later
-                        let title_holder = match string {
-                            s if s.to_lowercase().contains("page-timeout") => {
-                                "Failed to connect to device, connection timed out"
-                            }
-                            s if s.to_lowercase().contains("already-connected") => {
-                                "Device is already connected"
-                            }
-                            s if s.to_lowercase().contains("profile-unavailable") => {
-                                "Failed to find the target profile"
-                            }
-                            s if s.to_lowercase().contains("create-socket") => {
-                                "Failed to connect to Bluetooth socket, this is bad"
-                            }
-                            s if s.to_lowercase().contains("bad-socket") => {
-                                "Bad socket for connection, this is bad"
-                            }
-                            s if s.to_lowercase().contains("memory-allocation") => {
-                                "Failed to allocate memory"
-                            }
-                            s if s.to_lowercase().contains("busy") => {
-                                "Other operations pending, please try again in a bit"
-                            }
-                            s if s.to_lowercase().contains("limit") => {
-                                "Reached limit, cannot connect to anymore devices"
-                            }
-                            s if s.to_lowercase().contains("connection-timeout") => {
-                                "Failed to connect to device, connection timed out"
-                            }
-                            s if s.to_lowercase().contains("refused") => {
-                                "Connection was refused by target device"
-                            }
-                            s if s.to_lowercase().contains("aborted-by-remote") => {
-                                "Target device aborted connection"
-                            }
-                            s if s.to_lowercase().contains("aborted-by-local") => {
-                                "Connection has been aborted"
-                            }
-                            s if s.to_lowercase().contains("lmp-protocol-error") => {
-                                "Connection failed, lmp protocol error"
-                            }
-                            s if s.to_lowercase().contains("canceled") => {
-                                "Connection was canceled due to unforeseen circumstances"
-                            }
-                            s if s.to_lowercase().contains("unknown-error") => {
-                                "Connection failed, no idea why tho"
-                            }
-                            s if s.to_lowercase().contains("invalid-arguments") => {
-                                "Invalid arguments provided"
-                            }
-                            s if s.to_lowercase().contains("not-powered") || s.to_lowercase().contains("resource not ready") => {
-                                "Adapter is not powered"
-                            }
-                            s if s.to_lowercase().contains("not-supported") => {
-                                "Connection failed, requested features are not supported"
-                            }
-                            s if s.to_lowercase().contains("layer-protocol-error") => {
-                                "Connection failed, layer protocol error"
-                            }
-                            s if s.to_lowercase().contains("gatt-browsing") => {
-                                "Failed to complete GATT service browsing"
-                            }
-                            s if s.to_lowercase().contains("refreshed") => {
-                                "Refreshed devices list"
-                            }
-                            s if s.to_lowercase().contains("stopped searching for devices") => {
-                                "Stopped Searching for devices"
-                            }
-                            s if s.to_lowercase().contains("connection-unknown") => {
-                                "Connection unknown, please try again"
-                            }
-                            s if s.to_lowercase().contains("home-unknown") => {
-                                "Unable to get home folder, are you sure its configured correctly?"
-                            }
-                            s if s.to_lowercase().contains("transfer-complete-inbound") => {
-                                "File has been received"
-                            }
-                            s if s.to_lowercase().contains("transfer-complete-outbound") => {
-                                "File has been transferred"
-                            }
-                            s if s.to_lowercase().contains("transfer-active-inbound") => {
-                                "Started receiving file"
-                            }
-                            s if s.to_lowercase().contains("transfer-active-outbound") => {
-                                "Started transferring file"
-                            }
-                            s if s.to_lowercase().contains("transfer-error-inbound") => {
-                                "Receiving file stopped, error occurred"
-                            }
-                            s if s.to_lowercase().contains("transfer-error-outbound") => {
-                                "Sending file stopped, error occurred"
-                            }
-                            s if s.to_lowercase().contains("transfer-not-authorized") => {
-                                "File transfer has been rejected"
-                            }
-                            s if s.to_lowercase().contains("transfer-cancel-not-authorized") => {
-                                "Unable to cancel file transfer"
-                            }
-                            s if s.to_lowercase().contains("transfer-connection-error") => {
-                                "Unable to send file, connection is not possible"
-                            }
-                            s if s.to_lowercase().contains("refresh-adapter-failed") => {
-                                "Unable to refresh devices list after adapter change"
-                            }
-                            s if s.to_lowercase().contains("file-storage-not-valid") => {
-                                "Location is not valid, please try again"
-                            }
-                            s if s.to_lowercase().contains("file-storage-cache-invalid") => {
-                                "File cache location is invalid, are you sure ~/.cache (or equivalent) exists?"
-                            }
-                            s if s.to_lowercase().contains("device-name-exists") => {
-                                "Error, device with name already exists"
-                            }
-                            e => {
-                                println!("unknown error: {}", e.clone());
-                                "Unknown error occurred"
-                            }
-                        };
+                        if let OverskrideError::Unknown(message) = &error {
+                            event_log::warning(Source::App, "unknown error").field("error", message).record();
+                        }
+
+                        let explanation = error.explanation();
+                        let title_holder = glib::markup_escape_text(&explanation);
+
+                        // errors that can be fixed get a button explaining how, in a dialog so there's room for it
+                        if let Some(suggestion) = error.suggestion() {
+                            toast.set_button_label(Some("How to Fix"));
+                            toast.connect_button_clicked(clone!(
+                                #[weak]
+                                clone,
+                                move |_| {
+                                let dialog = adw::MessageDialog::new(Some(&clone), Some(&explanation), Some(suggestion));
+                                dialog.add_response("close", "Close");
+                                dialog.set_default_response(Some("close"));
+                                dialog.present();
+                            }));
+                        }
 
                         let mut title = String::new();
                         let boxholder = gtk::Box::new(gtk::Orientation::Horizontal, 8);
 
-                        toast.set_timeout(3);
+                        // a little longer for the ones with a button, so there's time to press it
+                        toast.set_timeout(if error.suggestion().is_some() { 6 } else { 3 });
                         match priority {
                             adw::ToastPriority::High => {
                                 // custom_title.set_css_classes(&["warning", state.as_str()]);
@@ -689,7 +736,7 @@ impl OverskrideWindow {
                         let label = gtk::Label::new(Some(""));
                         boxholder.append(&label);
 
-                        title += title_holder;
+                        title += title_holder.as_str();
                         title += "</span>";
 
                         label.set_use_markup(true);
@@ -717,7 +764,7 @@ impl OverskrideWindow {
                         let device: String;
                         let adapter: String;
                         unsafe {
-                            device = DEVICES_LUT.clone().unwrap().get(&request.device).unwrap_or(&"Unknown Device".to_string()).to_string();
+                            device = listed_name(&request.adapter, request.device).unwrap_or("Unknown Device".to_string());
                             adapter = ADAPTERS_LUT.clone().unwrap().get(&request.adapter).unwrap_or(&"Unknown Adapter".to_string()).to_string();
                             OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
                         }
@@ -769,11 +816,8 @@ impl OverskrideWindow {
                     }
                     Message::DisplayPinCode(request) => {
                         let pin_code = &request.pincode;
-                        let device: String;
-                        unsafe {
-                            device = DEVICES_LUT.clone().unwrap().get(&request.device).unwrap_or(&"Unknown Device".to_string()).to_string();
-                            OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
-                        }
+                        let device = listed_name(&request.adapter, request.device).unwrap_or("Unknown Device".to_string());
+                        OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
 
                         let body = "Please enter this pin code on ".to_string() + device.as_str();
                         let popup = adw::MessageDialog::new(Some(&clone), None, Some(body.as_str()));
@@ -793,7 +837,7 @@ impl OverskrideWindow {
                         let device: String;
                         let adapter: String;
                         unsafe {
-                            device = DEVICES_LUT.clone().unwrap().get(&request.device).unwrap_or(&"Unknown Device".to_string()).to_string();
+                            device = listed_name(&request.adapter, request.device).unwrap_or("Unknown Device".to_string());
                             adapter = ADAPTERS_LUT.clone().unwrap().get(&request.adapter).unwrap_or(&"Unknown Adapter".to_string()).to_string();
                             OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
                         }
@@ -850,11 +894,8 @@ impl OverskrideWindow {
                     }
                     Message::DisplayPassKey(request) => {
                         let pin_code = &request.passkey;
-                        let device: String;
-                        unsafe {
-                            device = DEVICES_LUT.clone().unwrap().get(&request.device).unwrap_or(&"Unknown Device".to_string()).to_string();
-                            OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
-                        }
+                        let device = listed_name(&request.adapter, request.device).unwrap_or("Unknown Device".to_string());
+                        OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
 
                         if clone.imp().display_pass_key_dialog.borrow().clone().is_some() {
                             let dialog = clone.imp().display_pass_key_dialog.borrow().clone().unwrap();
@@ -883,7 +924,7 @@ impl OverskrideWindow {
                         let passkey = &request.passkey.to_string();
                         unsafe {
                             OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
-                            device = DEVICES_LUT.clone().unwrap().get(&request.device).unwrap_or(&"Unknown Device".to_string()).to_string();
+                            device = listed_name(&request.adapter, request.device).unwrap_or("Unknown Device".to_string());
                             let mut holder = String::new();
                             for key in ADAPTERS_LUT.clone().unwrap().keys() {
                                 if let Some(pair) = ADAPTERS_LUT.clone().unwrap().get_key_value(key) {
@@ -939,7 +980,7 @@ impl OverskrideWindow {
                         let device: String;
                         let adapter: String;
                         unsafe {
-                            device = DEVICES_LUT.clone().unwrap().get(&request.device).unwrap_or(&"Unknown Device".to_string()).to_string();
+                            device = listed_name(&request.adapter, request.device).unwrap_or("Unknown Device".to_string());
                             adapter = ADAPTERS_LUT.clone().unwrap().get(&request.adapter).unwrap_or(&"Unknown Adapter".to_string()).to_string();
                             OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
                         }
@@ -978,7 +1019,7 @@ impl OverskrideWindow {
                         let adapter: String;
                         unsafe {
                             OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = true;
-                            device = DEVICES_LUT.clone().unwrap().get(&request.device).unwrap_or(&"Unknown Device".to_string()).to_string();
+                            device = listed_name(&request.adapter, request.device).unwrap_or("Unknown Device".to_string());
                             adapter = ADAPTERS_LUT.clone().unwrap().iter()
                                 .find_map(|(key, val)| if val == &request.adapter { Some(key) } else { None })
                                 .unwrap_or(&"Unknown Adapter".to_string()).to_string();
@@ -986,7 +1027,7 @@ impl OverskrideWindow {
 
                         let service_id = match bluer::id::Service::try_from(request.service) {
                             Ok(name) => {
-                                println!("service name is: {}", name.clone());
+                                event_log::debug(Source::Agent, "service authorization is for a known service").field("service", name).record();
                                 format!("{}", name)
                             }
                             Err(_) => {
@@ -1089,12 +1130,13 @@ impl OverskrideWindow {
                     Message::StartTransfer(transfer, filename, percent, current, filesize, outbound) => {
                         let receiving_popover = clone.imp().receiving_popover.get();
 
+                        dbus_api::emit(ApiSignal::TransferStarted(transfer.clone(), filename.clone(), outbound));
+
                         let row = ReceivingRow::new(transfer, filename.clone(), filesize, outbound);
-                        println!("row is: {}, {:?}", row.get_row_transfer(), row.filename());
+                        event_log::debug(Source::Obex, "transfer row added").field("transfer", row.get_row_transfer()).field("file", row.filename()).record();
 
                         row.set_extra(percent, current, filesize, 0);
                         row.set_progress_bar_fraction(percent);
-                        // println!("{} {} {}", row.percentage(), row.get_extra(), row.filesize());
 
                         receiving_popover.add_row(&row);
                     }
@@ -1106,10 +1148,12 @@ impl OverskrideWindow {
                             let filesize = row.get_row_filesize();
                             let fraction = current_mb / filesize * 100.0;
 
-                            // println!("status {}", &status);
-
                             row.set_progress_bar_fraction(fraction);
                             row.set_extra(fraction.round(), current_mb, filesize, current_rate);
+                            if status == "complete" || status == "error" {
+                                dbus_api::emit(ApiSignal::TransferFinished(transfer.clone(), filename.clone(), status.clone()));
+                            }
+
                             let nuked = row.set_active_icon(status, filesize);
 
                             // if row is canceled or error, remove it in a minute
@@ -1153,13 +1197,12 @@ impl OverskrideWindow {
                                     if file.as_ref().unwrap().is::<gio::File>() {
                                         if let Some(path) = file.unwrap().dynamic_cast::<gio::File>().unwrap().path() {
                                             all_files.push(path.to_str().unwrap_or("").to_string());
-
-                                            println!("file {:?}", all_files.last());
+                                            event_log::debug(Source::Obex, "file chosen to send").field("file", path.display()).record();
                                         };
                                     }
                                 }
                             } else {
-                                eprintln!("File selection failed: {:?}", response);
+                                event_log::debug(Source::App, "no files chosen").field("response", format!("{:?}", response)).record();
                             }
 
 	                        OVERSKRIDE_PROPS.lock().unwrap().displaying_dialog = false;
@@ -1177,7 +1220,7 @@ impl OverskrideWindow {
                         let file_save_location = clone.imp().file_save_location.get();
                         if !std::path::Path::new(&holder_location).is_dir() {
                             file_save_location.set_css_classes(&["error"]);
-                            sender_for_receiver_clone.clone().send(Message::PopupError("file-storage-not-valid".to_string(), adw::ToastPriority::High)).await.expect("cannot send message");
+                            sender_for_receiver_clone.clone().send(Message::PopupError(OverskrideError::StorageLocationInvalid, adw::ToastPriority::High)).await.expect("cannot send message");
                         } else {
                             file_save_location.set_css_classes(&[""]);
 
@@ -1200,7 +1243,7 @@ impl OverskrideWindow {
                         let hide_unknowns_switch_row = clone.imp().hide_unknowns_switch_row.get();
                         hide_unknowns_switch_row.set_active(hidden);
 
-                        println!("hidden devices set to {}", hidden);
+                        event_log::debug(Source::App, "hiding unknown devices changed").field("hidden", hidden).record();
 
                         OVERSKRIDE_PROPS.lock().unwrap().hide_unknown_devices = hidden;
 
@@ -1221,6 +1264,8 @@ impl OverskrideWindow {
                         }
                     }
                     Message::PopulateAudioProfilesList(hashmap) => {
+                        clone.fill_preferred_profiles(hashmap.clone());
+
                         let audio_profile_expander = clone.imp().audio_profile_expander.get();
                         let unknown = &"Unknown Profile".to_string();
 
@@ -1236,9 +1281,7 @@ impl OverskrideWindow {
 
                             if let Ok(list) = listbox.clone() {
                                 while let Some(row) = list.row_at_index(index) {
-                                    // println!("{}", index);
                                     let selectable_row = row.downcast::<SelectableRow>().expect("cannot downcast to action row.");
-                                    // println!("{:?}", action_row.clone());
 
                                     if selectable_row.selected() {
                                         last_profile = selectable_row.get_row_profile();
@@ -1297,7 +1340,6 @@ impl OverskrideWindow {
                                         audio_profiles::device_set_profile(address, profile);
                                     });
                                     sender_clone_2.send(Message::SetActiveAudioProfile(profile_clone)).await.expect("cannot send message");
-                                    // println!("set active profile");
                                 });
                             });
 
@@ -1338,9 +1380,7 @@ impl OverskrideWindow {
                         // loop over all the devices and check which one matches out profile
                         if let Ok(list) = listbox.clone() {
                             while let Some(row) = list.row_at_index(index) {
-                                // println!("{}", index);
                                 let selectable_row = row.downcast::<SelectableRow>().expect("cannot downcast to action row.");
-                                // println!("{:?}", action_row.clone());
 
                                 if selectable_row.get_row_profile() == profile {
                                     selectable_row.set_selected(true);
@@ -1351,6 +1391,195 @@ impl OverskrideWindow {
                             }
                         }
                     }
+                    Message::PopulateProfilesList(states) => {
+                        let profiles_expander = clone.imp().profiles_expander.get();
+
+                        for row in clone.imp().profile_rows.take() {
+                            profiles_expander.remove(&row);
+                        }
+
+                        profiles_expander.set_visible(!states.is_empty());
+                        let active_count = states.iter().filter(|state| state.active).count();
+                        profiles_expander.set_subtitle(&format!("{} of {} connected", active_count, states.len()));
+
+                        for state in states {
+                            let row = adw::ActionRow::new();
+                            row.set_title(&state.name);
+                            row.set_subtitle(if state.active { "connected" } else { "not connected" });
+
+                            let switch = gtk::Switch::new();
+                            switch.set_active(state.active);
+                            switch.set_valign(gtk::Align::Center);
+
+                            let sender_clone = sender_for_receiver_clone.clone();
+                            let uuid = state.uuid;
+                            switch.connect_state_set(move |_, active| {
+                                let sender_clone = sender_clone.clone();
+                                let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+                                let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
+
+                                runtime().spawn(async move {
+                                    if let Err(err) = device::set_profile_active(address, uuid, active, sender_clone.clone(), adapter_name).await {
+                                        sender_clone
+                                            .send(Message::PopupError(err.into(), adw::ToastPriority::High))
+                                            .await.expect("cannot send message");
+                                    }
+                                });
+
+                                glib::Propagation::Proceed
+                            });
+
+                            row.add_suffix(&switch);
+                            row.set_activatable_widget(Some(&switch));
+                            profiles_expander.add_row(&row);
+                            clone.imp().profile_rows.borrow_mut().push(row);
+                        }
+                    }
+                    Message::SetAgentHealth(agent, health) => {
+                        let (row, icon, service) = match agent {
+                            Agent::Bluetooth => (clone.imp().bluetooth_agent_row.get(), clone.imp().bluetooth_agent_icon.get(), "bluetoothd"),
+                            Agent::Obex => (clone.imp().obex_agent_row.get(), clone.imp().obex_agent_icon.get(), "obexd"),
+                        };
+
+                        match health {
+                            AgentHealth::Running => {
+                                row.set_subtitle("running");
+                                row.set_tooltip_text(None);
+                                icon.set_icon_name(Some("check-plain-symbolic"));
+                            }
+                            AgentHealth::Waiting => {
+                                row.set_subtitle(&format!("waiting for {} to start", service));
+                                row.set_tooltip_text(None);
+                                icon.set_icon_name(Some("content-loading-symbolic"));
+                            }
+                            AgentHealth::Failed(error) => {
+                                row.set_subtitle(&(error.explanation() + ", trying again"));
+                                row.set_tooltip_text(error.suggestion());
+                                icon.set_icon_name(Some("cross-large-symbolic"));
+                            }
+                        }
+                    }
+                    Message::ChooseBackupFile(export) => {
+                        let (title, action, accept) = if export {
+                            ("Back Up Devices To", gtk::FileChooserAction::Save, "Back Up")
+                        } else {
+                            ("Restore Devices From", gtk::FileChooserAction::Open, "Restore")
+                        };
+
+                        let dialog = gtk::FileChooserDialog::new(Some(title),
+                            Some(&clone),
+                            action,
+                            &[("Cancel", gtk::ResponseType::Cancel),
+                              (accept, gtk::ResponseType::Accept)
+                        ]);
+                        dialog.set_destroy_with_parent(true);
+                        dialog.set_default_response(gtk::ResponseType::Accept);
+                        dialog.set_modal(true);
+                        if export {
+                            dialog.set_current_name("overskride-devices.backup");
+                        }
+
+                        let include_keys = clone.imp().backup_keys_row.get().is_active();
+                        let sender_clone = sender_for_receiver_clone.clone();
+
+                        dialog.run_async(move |file_chooser, response| {
+                            let path = file_chooser.file().and_then(|file| file.path());
+                            file_chooser.destroy();
+
+                            let (ResponseType::Accept, Some(path)) = (response, path) else {
+                                return;
+                            };
+
+                            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+                            runtime().spawn(async move {
+                                let message = if export {
+                                    match backup::export(path, include_keys, adapter_name).await {
+                                        Ok(_) => Message::PopupError(OverskrideError::BackupExported, adw::ToastPriority::Normal),
+                                        Err(err) => Message::PopupError(err, adw::ToastPriority::High),
+                                    }
+                                } else {
+                                    match backup::import(path, include_keys, adapter_name).await {
+                                        Ok((_, keys_skipped)) => Message::BackupImported(keys_skipped),
+                                        Err(err) => Message::PopupError(err, adw::ToastPriority::High),
+                                    }
+                                };
+
+                                sender_clone.send(message).await.expect("cannot send message");
+                            });
+                        });
+                    }
+                    Message::ChooseWindowsHive() => {
+                        let dialog = gtk::FileChooserDialog::new(Some("Windows Registry SYSTEM File"),
+                            Some(&clone),
+                            gtk::FileChooserAction::Open,
+                            &[("Cancel", gtk::ResponseType::Cancel),
+                              ("Select", gtk::ResponseType::Accept)
+                        ]);
+                        dialog.set_destroy_with_parent(true);
+                        dialog.set_default_response(gtk::ResponseType::Accept);
+                        dialog.set_modal(true);
+
+                        let sender_clone = sender_for_receiver_clone.clone();
+                        dialog.run_async(move |file_chooser, response| {
+                            let path = file_chooser.file().and_then(|file| file.path());
+                            file_chooser.destroy();
+
+                            let (ResponseType::Accept, Some(path)) = (response, path) else {
+                                return;
+                            };
+
+                            runtime().spawn(async move {
+                                let message = match windows_keys::preview(path.clone()).await {
+                                    Ok((_, matches)) => Message::PreviewWindowsKeys(path, matches),
+                                    Err(err) => Message::PopupError(err, adw::ToastPriority::High),
+                                };
+
+                                sender_clone.send(message).await.expect("cannot send message");
+                            });
+                        });
+                    }
+                    Message::PreviewWindowsKeys(path, matches) => {
+                        let devices = matches.iter()
+                            .map(|sync| format!("• {} ({}): {}", sync.name, sync.address, sync.keys.describe()))
+                            .collect::<Vec<String>>()
+                            .join("\n");
+                        let body = "These devices are paired on both systems, their keys here are replaced with the ones Windows has and Bluetooth is restarted.\n\n".to_string() + &devices;
+
+                        let popup = adw::MessageDialog::new(Some(&clone), Some("Copy Pairing Keys From Windows?"), Some(body.as_str()));
+                        popup.set_close_response("cancel");
+                        popup.set_destroy_with_parent(true);
+
+                        popup.add_response("cancel", "Cancel");
+                        popup.add_response("copy", "Copy Keys");
+                        popup.set_response_appearance("copy", adw::ResponseAppearance::Suggested);
+                        popup.set_default_response(Some("copy"));
+
+                        let sender_clone = sender_for_receiver_clone.clone();
+                        popup.clone().choose(gio::Cancellable::NONE, move |response| {
+                            if response != "copy" {
+                                return;
+                            }
+
+                            runtime().spawn(async move {
+                                let message = match windows_keys::sync(path, false).await {
+                                    Ok(_) => Message::PopupError(OverskrideError::WindowsKeysSynced, adw::ToastPriority::Normal),
+                                    Err(err) => Message::PopupError(err, adw::ToastPriority::High),
+                                };
+
+                                sender_clone.send(message).await.expect("cannot send message");
+                            });
+                        });
+                    }
+                    Message::BackupImported(keys_skipped) => {
+                        let keep_connected_list = OVERSKRIDE_PROPS.lock().unwrap().keep_connected.iter().map(|address| address.to_string()).collect::<Vec<String>>();
+                        clone.imp().settings.get().expect("cannot get settings for keep connected").set_strv("keep-connected-devices", keep_connected_list).expect("cannot set keep connected devices");
+                        clone.save_audio_priority();
+                        populate_audio_priority_list(&clone.imp().audio_priority_listbox.get(), sender_for_receiver_clone.clone());
+
+                        WidgetExt::activate_action(&clone, "win.refresh-devices", None).expect("cannot refresh devices list");
+                        let toast = if keys_skipped { OverskrideError::BackupImportedWithoutKeys } else { OverskrideError::BackupImported };
+                        sender_for_receiver_clone.send(Message::PopupError(toast, adw::ToastPriority::Normal)).await.expect("cannot send message");
+                    }
                     Message::SwitchAudioProfileExpanded(state) => {
                         let audio_profile_expander = clone.imp().audio_profile_expander.get();
                         audio_profile_expander.set_expanded(state);
@@ -1360,63 +1589,757 @@ impl OverskrideWindow {
 
                         battery_level_indicator.set_indicator_battery_level(level);
                     }
-                }
-            }
-        });
+                    Message::SetAudioSinkMode(enabled) => {
+                        let audio_sink_switch_row = clone.imp().audio_sink_switch_row.get();
+                        audio_sink_switch_row.set_active(enabled);
 
-        let main_listbox = self.imp().main_listbox.get();
+                        OVERSKRIDE_PROPS.lock().unwrap().audio_sink_mode = enabled;
 
-        // smaller => one before two
-        // larger => two before one
-        // equal => they're equal
-        // how this works is beyond me (yes, I wrote it)
-        main_listbox.set_sort_func(|row_one, row_two| {
-            let actionrow_one = row_one.clone().downcast::<DeviceActionRow>().unwrap();
-            let actionrow_two = row_two.clone().downcast::<DeviceActionRow>().unwrap();
+                        if !enabled {
+                            let streaming_device_row = clone.imp().streaming_device_row.get();
+                            streaming_device_row.set_visible(false);
+                        }
 
-            let title_one = actionrow_one.title().to_lowercase();
-            let title_two = actionrow_two.title().to_lowercase();
+                        clone.imp().settings.get().expect("cannot get settings for audio sink mode").set_boolean("audio-sink-mode", enabled).expect("cannot set audio sink mode");
+                    }
+                    Message::SetStreamingDevice(alias) => {
+                        let streaming_device_row = clone.imp().streaming_device_row.get();
 
-            match (title_one.as_str(), title_two.as_str()) {
-                ("unknown device", _) if title_two != "unknown device" => {
-                    return gtk::Ordering::Larger
-                }
-                (_, "unknown device") if title_one != "unknown device" => {
-                    return gtk::Ordering::Smaller
-                }
-                ("unknown device", "unknown device") => (),
-                _ => (),
-            }
+                        if let Some(name) = alias {
+                            streaming_device_row.set_subtitle(&name);
+                            streaming_device_row.set_visible(true);
+                        } else {
+                            streaming_device_row.set_visible(false);
+                        }
+                    }
+                    Message::SetCallIntegration(enabled) => {
+                        let call_integration_switch_row = clone.imp().call_integration_switch_row.get();
+                        call_integration_switch_row.set_active(enabled);
 
-            match (actionrow_one.connected(), actionrow_two.connected()) {
-                (true, false) => return gtk::Ordering::Smaller,
-                (false, true) => return gtk::Ordering::Larger,
-                _ => (),
-            }
+                        OVERSKRIDE_PROPS.lock().unwrap().call_integration = enabled;
 
-            match (actionrow_one.trusted(), actionrow_two.trusted()) {
-                (true, false) => return gtk::Ordering::Smaller,
-                (false, true) => return gtk::Ordering::Larger,
-                _ => (),
-            }
+                        if !enabled {
+                            let call_bar_revealer = clone.imp().call_bar_revealer.get();
+                            call_bar_revealer.set_reveal_child(false);
+                        }
 
-            match actionrow_one.rssi().cmp(&actionrow_two.rssi()) {
-                std::cmp::Ordering::Less => return gtk::Ordering::Smaller,
-                std::cmp::Ordering::Greater => return gtk::Ordering::Larger,
-                _ => (),
-            }
+                        clone.imp().settings.get().expect("cannot get settings for call integration").set_boolean("call-integration", enabled).expect("cannot set call integration");
+                    }
+                    Message::UpdateCall(call) => {
+                        let call_bar_revealer = clone.imp().call_bar_revealer.get();
+                        let call_bar_icon = clone.imp().call_bar_icon.get();
+                        let call_bar_title = clone.imp().call_bar_title.get();
+                        let call_bar_subtitle = clone.imp().call_bar_subtitle.get();
+                        let answer_call_button = clone.imp().answer_call_button.get();
+                        let hangup_call_button = clone.imp().hangup_call_button.get();
+                        let application = clone.application().expect("cannot get application for call");
+
+                        OVERSKRIDE_PROPS.lock().unwrap().current_call = call.path.clone();
+
+                        let ringing = call.state == "incoming" || call.state == "waiting";
+                        let subtitle = match call.state.as_str() {
+                            "incoming" | "waiting" => "Incoming call via ".to_string() + &call.device_name,
+                            "dialing" | "alerting" => "Calling via ".to_string() + &call.device_name,
+                            "held" => "On hold via ".to_string() + &call.device_name,
+                            "disconnected" => "Call ended".to_string(),
+                            _ => "On call via ".to_string() + &call.device_name,
+                        };
 
-            match title_one.cmp(&title_two) {
-                std::cmp::Ordering::Less => return gtk::Ordering::Smaller,
-                std::cmp::Ordering::Greater => return gtk::Ordering::Larger,
-                _ => (),
-            }
+                        call_bar_title.set_label(&call.caller());
+                        call_bar_subtitle.set_label(&subtitle);
+                        answer_call_button.set_visible(ringing);
+
+                        if ringing {
+                            call_bar_icon.set_icon_name(Some("call-incoming-symbolic"));
+                            hangup_call_button.set_tooltip_text(Some("Reject Call"));
+
+                            // let the user know even if the window isn't focused (or visible)
+                            let notification = gio::Notification::new("Incoming Call");
+                            notification.set_body(Some(&(call.caller() + " is calling via " + &call.device_name)));
+                            notification.set_priority(gio::NotificationPriority::Urgent);
+                            notification.add_button("Answer", "app.answer-call");
+                            notification.add_button("Reject", "app.reject-call");
+                            application.send_notification(Some("incoming-call"), &notification);
+                        } else {
+                            call_bar_icon.set_icon_name(Some("call-start-symbolic"));
+                            hangup_call_button.set_tooltip_text(Some("Hang Up"));
+                            application.withdraw_notification("incoming-call");
+                        }
 
-            return gtk::Ordering::Equal;
-        });
-        main_listbox.invalidate_sort();
+                        call_bar_revealer.set_reveal_child(call.state != "disconnected");
+                    }
+                    Message::RemoveCall(path) => {
+                        let call_bar_revealer = clone.imp().call_bar_revealer.get();
+                        let current_call = OVERSKRIDE_PROPS.lock().unwrap().current_call.clone();
 
-        // refresh devices action, possibly most important action here
+                        if current_call == path {
+                            OVERSKRIDE_PROPS.lock().unwrap().current_call = String::new();
+                            call_bar_revealer.set_reveal_child(false);
+
+                            if let Some(application) = clone.application() {
+                                application.withdraw_notification("incoming-call");
+                            }
+                        }
+                    }
+                    Message::AddBroadcast(source) => {
+                        let broadcast_listbox = clone.imp().broadcast_listbox.get();
+                        let mut index = 0;
+
+                        // already listed broadcasts just get their metadata updated
+                        while let Some(row) = broadcast_listbox.row_at_index(index) {
+                            let broadcast_row = row.downcast::<BroadcastRow>().expect("cannot downcast to broadcast row.");
+                            if broadcast_row.get_bluer_address() == source.address {
+                                broadcast_row.update_from_source(&source);
+                                break;
+                            }
+                            index += 1;
+                        }
+
+                        if broadcast_listbox.row_at_index(index).is_none() {
+                            let broadcast_row = BroadcastRow::new();
+                            broadcast_row.update_from_source(&source);
+
+                            let sender = sender_for_receiver_clone.clone();
+                            broadcast_row.join_button().connect_clicked(clone!(
+                                #[weak]
+                                broadcast_row,
+                                move |_| {
+                                let sender = sender.clone();
+                                let address = broadcast_row.get_bluer_address();
+                                let join = !broadcast_row.joined();
+                                let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+
+                                runtime().spawn(async move {
+                                    match tokio::task::spawn_blocking(move || broadcast::set_broadcast_joined(address, adapter_name, join)).await {
+                                        Ok(Ok(())) => {
+                                            sender.send(Message::SetBroadcastJoined(address, join)).await.expect("cannot send message");
+                                        }
+                                        Ok(Err(err)) => {
+                                            sender.send(Message::PopupError(err, adw::ToastPriority::High)).await.expect("cannot send message");
+                                        }
+                                        Err(err) => {
+                                            event_log::error(Source::Device, "joining the broadcast panicked").field("error", &err).record();
+                                        }
+                                    }
+                                });
+                            }));
+
+                            broadcast_listbox.append(&broadcast_row);
+                        }
+                    }
+                    Message::RemoveBroadcast(address) => {
+                        let broadcast_listbox = clone.imp().broadcast_listbox.get();
+                        let mut index = 0;
+
+                        while let Some(row) = broadcast_listbox.row_at_index(index) {
+                            let broadcast_row = row.downcast::<BroadcastRow>().expect("cannot downcast to broadcast row.");
+                            if broadcast_row.get_bluer_address() == address {
+                                broadcast_listbox.remove(&broadcast_row);
+                                break;
+                            }
+                            index += 1;
+                        }
+                    }
+                    Message::SetBroadcastJoined(address, joined) => {
+                        let broadcast_listbox = clone.imp().broadcast_listbox.get();
+                        let mut index = 0;
+
+                        while let Some(row) = broadcast_listbox.row_at_index(index) {
+                            let broadcast_row = row.downcast::<BroadcastRow>().expect("cannot downcast to broadcast row.");
+                            if broadcast_row.get_bluer_address() == address {
+                                broadcast_row.set_joined(joined);
+                            }
+                            index += 1;
+                        }
+                    }
+                    Message::SetBroadcastScanning(scanning) => {
+                        let broadcast_scan_switch = clone.imp().broadcast_scan_switch.get();
+                        broadcast_scan_switch.set_active(scanning);
+
+                        OVERSKRIDE_PROPS.lock().unwrap().broadcast_scanning = scanning;
+
+                        // restart discovery with the new filter
+                        WidgetExt::activate_action(&clone, "win.refresh-devices", None).expect("cannot refresh devices");
+                    }
+                    Message::SetLc3Configuration(descriptions) => {
+                        let lc3_configuration_row = clone.imp().lc3_configuration_row.get();
+
+                        lc3_configuration_row.set_subtitle(&descriptions.join("\n"));
+                        lc3_configuration_row.set_visible(!descriptions.is_empty());
+                    }
+                    Message::SetShowAllAdapters(enabled) => {
+                        let show_all_adapters_switch_row = clone.imp().show_all_adapters_switch_row.get();
+                        show_all_adapters_switch_row.set_active(enabled);
+
+                        OVERSKRIDE_PROPS.lock().unwrap().show_all_adapters = enabled;
+                        clone.imp().settings.get().expect("cannot get settings for show all adapters").set_boolean("show-all-adapters", enabled).expect("cannot set show all adapters");
+
+                        let listbox = clone.imp().main_listbox.get();
+                        let current_adapter = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+                        let mut index = 0;
+
+                        // only keep the current adapter's devices when going back to a single adapter
+                        while let Some(row) = listbox.row_at_index(index) {
+                            let action_row = row.downcast::<DeviceActionRow>().expect("cannot downcast to action row.");
+
+                            if !enabled && action_row.adapter_name() != current_adapter {
+                                forget_device(&action_row.adapter_name(), action_row.get_bluer_address());
+                                listbox.remove(&action_row);
+                            } else {
+                                index += 1;
+                            }
+                        }
+
+                        listbox.invalidate_sort();
+                        listbox.invalidate_headers();
+                        sender_for_receiver_clone.send(Message::UpdateListBoxImage()).await.expect("cannot send message");
+                        WidgetExt::activate_action(&clone, "win.refresh-devices", None).expect("cannot refresh devices");
+                    }
+                    Message::RemoveAdapterDevices(adapter_name) => {
+                        let listbox = clone.imp().main_listbox.get();
+                        let mut index = 0;
+
+                        while let Some(row) = listbox.row_at_index(index) {
+                            let action_row = row.downcast::<DeviceActionRow>().expect("cannot downcast to action row.");
+
+                            if action_row.adapter_name() == adapter_name {
+                                forget_device(&action_row.adapter_name(), action_row.get_bluer_address());
+                                listbox.remove(&action_row);
+                            } else {
+                                index += 1;
+                            }
+                        }
+
+                        // the open device page can't be reached anymore if its adapter is gone
+                        if OVERSKRIDE_PROPS.lock().unwrap().device_adapter == adapter_name {
+                            let bluetooth_settings_row = clone.imp().bluetooth_settings_row.get();
+                            bluetooth_settings_row.emit_activate();
+                        }
+
+                        sender_for_receiver_clone.send(Message::UpdateListBoxImage()).await.expect("cannot send message");
+                    }
+                    Message::SetConnectUsing(adapters, adapter_name) => {
+                        let connect_using_row = clone.imp().connect_using_row.get();
+                        let connect_using_list = clone.imp().connect_using_list.get();
+
+                        let mut aliases: Vec<&String> = adapters.keys().collect();
+                        aliases.sort();
+
+                        let names: Vec<String> = aliases.iter().map(|alias| adapters[*alias].clone()).collect();
+                        let selected = names.iter().position(|name| *name == adapter_name).unwrap_or(0);
+                        *clone.imp().connect_using_adapters.borrow_mut() = names;
+
+                        let aliases: Vec<&str> = aliases.iter().map(|alias| alias.as_str()).collect();
+                        connect_using_list.splice(0, connect_using_list.n_items(), &aliases);
+                        connect_using_row.set_selected(selected as u32);
+
+                        OVERSKRIDE_PROPS.lock().unwrap().device_adapter = adapter_name;
+                        connect_using_row.set_visible(adapters.len() > 1);
+                    }
+                    Message::SetRfkillState(state) => {
+                        let was_blocked = OVERSKRIDE_PROPS.lock().unwrap().rfkill_state.is_blocked();
+                        OVERSKRIDE_PROPS.lock().unwrap().rfkill_state = state;
+                        clone.update_radio_banner();
+
+                        // the radio is back, search again once the adapter powers up
+                        if was_blocked && !state.is_blocked() {
+                            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+                            let sender = sender_for_receiver_clone.clone();
+
+                            runtime().spawn(async move {
+                                if let Err(err) = bluetooth_settings::resume_discovery(adapter_name, sender).await {
+                                    event_log::warning(Source::Adapter, "cannot resume discovery").field("error", &err).record();
+                                }
+                            });
+                        }
+                    }
+                    Message::SetAdapterMissing(missing) => {
+                        OVERSKRIDE_PROPS.lock().unwrap().adapter_missing = missing;
+                        clone.update_radio_banner();
+                    }
+                    Message::SetRunInBackground(enabled) => {
+                        let run_in_background_switch_row = clone.imp().run_in_background_switch_row.get();
+                        run_in_background_switch_row.set_active(enabled);
+
+                        OVERSKRIDE_PROPS.lock().unwrap().run_in_background = enabled;
+                        clone.imp().settings.get().expect("cannot get settings for background running").set_boolean("run-in-background", enabled).expect("cannot set background running");
+
+                        // turning it off makes the tray go away by itself
+                        if enabled {
+                            spawn_tray(sender_for_receiver_clone.clone());
+                        }
+                    }
+                    Message::ShowWindow() => {
+                        clone.set_visible(true);
+                        clone.present();
+                    }
+                    Message::QuitApplication() => {
+                        if let Err(err) = clone.save_settings() {
+                            event_log::warning(Source::App, "cannot save the window size").field("error", err).record();
+                        }
+
+                        if let Some(application) = clone.application() {
+                            application.quit();
+                        }
+                    }
+                    Message::PickDeviceForFiles(files, source, devices) => {
+                        if devices.is_empty() {
+                            sender_for_receiver_clone.send(Message::PopupError(OverskrideError::NoPairedDevices, adw::ToastPriority::High)).await.expect("cannot send message");
+                            continue;
+                        }
+
+                        let heading = if files.len() == 1 {
+                            "Send 1 File".to_string()
+                        } else {
+                            format!("Send {} Files", files.len())
+                        };
+                        let popup = adw::MessageDialog::new(Some(&clone), Some(&heading), Some("Choose the device to send to"));
+
+                        popup.set_close_response("cancel");
+                        popup.set_modal(true);
+                        popup.set_destroy_with_parent(true);
+
+                        popup.add_response("cancel", "Cancel");
+                        popup.add_response("send", "Send");
+                        popup.set_response_appearance("send", adw::ResponseAppearance::Suggested);
+                        popup.set_default_response(Some("send"));
+
+                        // one radio button per device, the first (most likely connected) one is picked already
+                        let listbox = gtk::ListBox::new();
+                        listbox.add_css_class("boxed-list");
+                        listbox.set_selection_mode(gtk::SelectionMode::None);
+
+                        let mut buttons: Vec<(gtk::CheckButton, bluer::Address)> = vec![];
+                        for (address, name, connected) in devices {
+                            let row = adw::ActionRow::new();
+                            row.set_title(&glib::markup_escape_text(&name));
+                            row.set_subtitle(if connected { "Connected" } else { "Not Connected" });
+
+                            let check_button = gtk::CheckButton::new();
+                            if let Some((first, _)) = buttons.first() {
+                                check_button.set_group(Some(first));
+                            } else {
+                                check_button.set_active(true);
+                            }
+
+                            row.add_prefix(&check_button);
+                            row.set_activatable_widget(Some(&check_button));
+                            listbox.append(&row);
+
+                            buttons.push((check_button, address));
+                        }
+                        popup.set_extra_child(Some(&listbox));
+
+                        clone.set_visible(true);
+                        clone.present();
+
+                        let sender = sender_for_receiver_clone.clone();
+                        popup.clone().choose(gio::Cancellable::NONE, move |response| {
+                            if response != "send" {
+                                return;
+                            }
+
+                            if let Some((_, destination)) = buttons.iter().find(|(button, _)| button.is_active()) {
+                                let destination = *destination;
+                                let sender = sender.clone();
+
+                                std::thread::spawn(move || {
+                                    obex::start_send_file(destination, source, files, sender);
+                                });
+                            }
+                        });
+                    }
+                    Message::SwitchRowPaired(address, paired) => {
+                        let main_listbox = clone.imp().main_listbox.get();
+
+                        if let Some(action_row) = find_device_row(&main_listbox, address) {
+                            action_row.set_paired(paired);
+                            refilter_devices(&main_listbox);
+                        }
+                    }
+                    Message::SwitchRowTrusted(address, trusted) => {
+                        let main_listbox = clone.imp().main_listbox.get();
+
+                        if let Some(action_row) = find_device_row(&main_listbox, address) {
+                            action_row.set_row_trusted(trusted);
+                            refilter_devices(&main_listbox);
+                        }
+                    }
+                    Message::ShowDevice(address) => {
+                        let main_listbox = clone.imp().main_listbox.get();
+
+                        clone.set_visible(true);
+                        clone.present();
+
+                        // activating the row does the same as clicking it
+                        if let Some(action_row) = find_device_row(&main_listbox, address) {
+                            main_listbox.select_row(Some(&action_row));
+                            WidgetExt::activate(&action_row);
+                        } else {
+                            sender_for_receiver_clone.send(Message::PopupError(OverskrideError::DeviceUnknown, adw::ToastPriority::Normal)).await.expect("cannot send message");
+                        }
+                    }
+                    Message::SetDiscoverySettings(discovery_settings) => {
+                        let settings = clone.imp().settings.get().expect("cannot get settings for discovery");
+                        settings.set_string("discovery-transport", &discovery_settings.transport).expect("cannot set discovery transport");
+                        settings.set_value("discovery-rssi", &discovery_settings.rssi.to_variant()).expect("cannot set discovery rssi");
+                        settings.set_value("discovery-pathloss", &discovery_settings.pathloss.to_variant()).expect("cannot set discovery pathloss");
+                        settings.set_strv("discovery-uuids", discovery_settings.uuids.clone()).expect("cannot set discovery uuids");
+                        settings.set_boolean("discovery-duplicate-data", discovery_settings.duplicate_data).expect("cannot set discovery duplicate data");
+                        settings.set_string("discovery-pattern", &discovery_settings.pattern).expect("cannot set discovery pattern");
+
+                        let changed = OVERSKRIDE_PROPS.lock().unwrap().discovery_settings != discovery_settings;
+                        OVERSKRIDE_PROPS.lock().unwrap().discovery_settings = discovery_settings;
+
+                        // restart discovery with the new filter, broadcast scanning keeps its own
+                        if changed && !OVERSKRIDE_PROPS.lock().unwrap().broadcast_scanning {
+                            WidgetExt::activate_action(&clone, "win.refresh-devices", None).expect("cannot refresh devices");
+                        }
+                    }
+                    Message::SetScanning(scanning) => {
+                        if let Some(scan_action) = clone.lookup_action("scan").and_downcast::<gio::SimpleAction>() {
+                            scan_action.set_state(&scanning.to_variant());
+                        }
+
+                        let scan_spinner = clone.imp().scan_spinner.get();
+                        scan_spinner.set_visible(scanning);
+                        scan_spinner.set_spinning(scanning);
+
+                        // a new scan gets a fresh timer, and stopping doesn't need one at all
+                        if let Some(source_id) = clone.imp().scan_timeout_id.take() {
+                            source_id.remove();
+                        }
+
+                        let scan_timeout = OVERSKRIDE_PROPS.lock().unwrap().scan_timeout;
+                        if scanning && scan_timeout > 0 {
+                            let sender = sender_for_receiver_clone.clone();
+                            let source_id = glib::timeout_add_seconds_local_once(scan_timeout, clone!(
+                                #[weak]
+                                clone,
+                                move || {
+                                clone.imp().scan_timeout_id.take();
+                                glib::spawn_future_local(async move {
+                                    sender.send(Message::SetScanning(false)).await.expect("cannot send message");
+                                });
+                            }));
+                            clone.imp().scan_timeout_id.replace(Some(source_id));
+                        }
+
+                        if OVERSKRIDE_PROPS.lock().unwrap().scanning != scanning {
+                            OVERSKRIDE_PROPS.lock().unwrap().scanning = scanning;
+
+                            // restart the device loop, with discovery on or off
+                            WidgetExt::activate_action(&clone, "win.refresh-devices", None).expect("cannot refresh devices");
+                        }
+                    }
+                    Message::SwitchKeepConnected(keep_connected) => {
+                        let keep_connected_row = clone.imp().keep_connected_row.get();
+                        keep_connected_row.set_active(keep_connected);
+                    }
+                    Message::SetKeepConnected(address, keep_connected) => {
+                        let keep_connected_list = {
+                            let mut props = OVERSKRIDE_PROPS.lock().unwrap();
+                            props.keep_connected.retain(|other| *other != address);
+                            if keep_connected {
+                                props.keep_connected.push(address);
+                            }
+
+                            props.keep_connected.iter().map(|address| address.to_string()).collect::<Vec<String>>()
+                        };
+
+                        clone.imp().settings.get().expect("cannot get settings for keep connected").set_strv("keep-connected-devices", keep_connected_list).expect("cannot set keep connected devices");
+
+                        if keep_connected {
+                            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
+                            let sender = sender_for_receiver_clone.clone();
+
+                            runtime().spawn(async move {
+                                if let Err(err) = reconnect::keep_connected(address, adapter_name, sender).await {
+                                    event_log::warning(Source::Device, "cannot keep the device connected").device(address).field("error", &err).record();
+                                }
+                            });
+                        }
+                    }
+                    Message::SwitchAudioPriority(visible, active) => {
+                        let audio_priority_row = clone.imp().audio_priority_row.get();
+                        audio_priority_row.set_visible(visible);
+                        audio_priority_row.set_active(active);
+                        clone.imp().preferred_profile_row.get().set_visible(visible);
+                    }
+                    Message::SetAudioPriority(address, prioritized) => {
+                        {
+                            let mut props = OVERSKRIDE_PROPS.lock().unwrap();
+                            props.audio_priority.retain(|other| *other != address);
+                            if prioritized {
+                                props.audio_priority.push(address);
+                            }
+                        }
+
+                        clone.save_audio_priority();
+                        populate_audio_priority_list(&clone.imp().audio_priority_listbox.get(), sender_for_receiver_clone.clone());
+
+                        if address == OVERSKRIDE_PROPS.lock().unwrap().address {
+                            clone.imp().audio_priority_row.get().set_active(prioritized);
+                        }
+                    }
+                    Message::MoveAudioPriority(address, offset) => {
+                        {
+                            let mut props = OVERSKRIDE_PROPS.lock().unwrap();
+                            if let Some(index) = props.audio_priority.iter().position(|other| *other == address) {
+                                let new_index = (index as i32 + offset).clamp(0, props.audio_priority.len() as i32 - 1) as usize;
+                                let moved = props.audio_priority.remove(index);
+                                props.audio_priority.insert(new_index, moved);
+                            }
+                        }
+
+                        clone.save_audio_priority();
+                        populate_audio_priority_list(&clone.imp().audio_priority_listbox.get(), sender_for_receiver_clone.clone());
+                    }
+                    Message::SetAudioSwitching(audio_switching) => {
+                        clone.imp().settings.get().expect("cannot get settings for audio switching").set_string("audio-switching", &audio_switching).expect("cannot set audio switching");
+                        OVERSKRIDE_PROPS.lock().unwrap().audio_switching = audio_switching;
+                    }
+                    Message::SetDistanceEnvironment(environment) => {
+                        clone.imp().settings.get().expect("cannot get settings for distance environment").set_string("distance-environment", &environment).expect("cannot set distance environment");
+                        OVERSKRIDE_PROPS.lock().unwrap().distance_environment = environment;
+                    }
+                    Message::ReconnectGaveUp(name) => {
+                        if let Some(application) = clone.application() {
+                            let notification = gio::Notification::new("Couldn't Reconnect");
+                            notification.set_body(Some(&(name + " seems to be out of range or turned off, stopped trying to reconnect")));
+                            application.send_notification(Some("reconnect-gave-up"), &notification);
+                        }
+                    }
+                    Message::SwitchDeviceSettings(settings) => {
+                        *clone.imp().filling_device_settings.borrow_mut() = true;
+
+                        let icon = device_store::ICONS.iter().position(|(_, icon)| *icon == settings.icon).unwrap_or(0);
+                        clone.imp().device_icon_row.get().set_selected(icon as u32);
+                        clone.fill_preferred_profiles(HashMap::new());
+                        clone.imp().battery_alert_row.get().set_value(settings.battery_alert as f64);
+                        clone.imp().auto_connect_row.get().set_active(settings.auto_connect);
+                        clone.imp().auto_accept_files_row.get().set_active(settings.auto_accept_files);
+                        clone.imp().proximity_row.get().set_enable_expansion(settings.proximity);
+                        clone.imp().proximity_away_row.get().set_value(settings.proximity_away_rssi as f64);
+                        clone.imp().proximity_back_row.get().set_value(settings.proximity_back_rssi as f64);
+                        clone.imp().proximity_lock_row.get().set_active(settings.proximity_lock);
+                        clone.imp().proximity_unlock_row.get().set_active(settings.proximity_unlock);
+                        clone.imp().proximity_away_command_row.get().set_text(&settings.proximity_away_command);
+                        clone.imp().proximity_back_command_row.get().set_text(&settings.proximity_back_command);
+                        clone.imp().download_folder_row.get().set_text(&settings.download_folder);
+                        clone.imp().notes_row.get().set_text(&settings.notes);
+
+                        *clone.imp().filling_device_settings.borrow_mut() = false;
+                    }
+                    Message::SwitchDeviceIcon(address, icon) => {
+                        let main_listbox = clone.imp().main_listbox.get();
+                        if let Some(action_row) = find_device_row(&main_listbox, address) {
+                            action_row.set_device_type(icon.clone());
+                            refilter_devices(&main_listbox);
+                        }
+
+                        if address == OVERSKRIDE_PROPS.lock().unwrap().address {
+                            clone.imp().device_icon.get().set_icon_name(Some(&(icon + "-symbolic")));
+                        }
+                    }
+                    Message::BatteryLow(name, percentage) => {
+                        if let Some(application) = clone.application() {
+                            let notification = gio::Notification::new("Low Battery");
+                            notification.set_body(Some(&format!("{} is down to {}%", name, percentage)));
+                            application.send_notification(Some("battery-low"), &notification);
+                        }
+                    }
+                    Message::SetAdvertisedName(device, advertised) => {
+                        let main_listbox = clone.imp().main_listbox.get();
+                        let hide_unknowns = clone.imp().hide_unknowns_switch_row.get().is_active();
+                        let address = device.address();
+
+                        match find_device_row(&main_listbox, address) {
+                            Some(action_row) if action_row.title() != advertised.name => {
+                                action_row.set_title(&advertised.name);
+                                action_row.set_beacon(advertised.beacon);
+                                // rssi updates look rows up by their name
+                                remember_device(device.adapter_name(), address, advertised.name);
+
+                                if is_unknown_device(&action_row) && hide_unknowns {
+                                    main_listbox.remove(&action_row);
+                                    sender_for_receiver_clone.send(Message::UpdateListBoxImage()).await.expect("cannot send message");
+                                }
+
+                                refilter_devices(&main_listbox);
+                            }
+                            Some(_) => {}
+                            // it was hidden as an unknown device, but now there's something to show
+                            None if !(advertised.beacon && hide_unknowns) => {
+                                forget_device(device.adapter_name(), address);
+                                sender_for_receiver_clone.send(Message::AddRow(device)).await.expect("cannot send message");
+                                sender_for_receiver_clone.send(Message::UpdateListBoxImage()).await.expect("cannot send message");
+                            }
+                            None => {}
+                        }
+                    }
+                }
+            }
+        });
+
+        let main_listbox = self.imp().main_listbox.get();
+
+        // smaller => one before two
+        // larger => two before one
+        // equal => they're equal
+        // how this works is beyond me (yes, I wrote it)
+        main_listbox.set_sort_func(|row_one, row_two| {
+            let actionrow_one = row_one.clone().downcast::<DeviceActionRow>().unwrap();
+            let actionrow_two = row_two.clone().downcast::<DeviceActionRow>().unwrap();
+
+            // with every adapter in one list, keep each adapter's devices together
+            if OVERSKRIDE_PROPS.lock().unwrap().show_all_adapters {
+                match actionrow_one.adapter_name().cmp(&actionrow_two.adapter_name()) {
+                    std::cmp::Ordering::Less => return gtk::Ordering::Smaller,
+                    std::cmp::Ordering::Greater => return gtk::Ordering::Larger,
+                    _ => (),
+                }
+            }
+
+            // then keep each section's devices together
+            let grouping = OVERSKRIDE_PROPS.lock().unwrap().device_grouping.clone();
+            match device_group(&ListedDevice::from_row(&actionrow_one), &grouping).cmp(&device_group(&ListedDevice::from_row(&actionrow_two), &grouping)) {
+                std::cmp::Ordering::Less => return gtk::Ordering::Smaller,
+                std::cmp::Ordering::Greater => return gtk::Ordering::Larger,
+                _ => (),
+            }
+
+            let title_one = actionrow_one.title().to_lowercase();
+            let title_two = actionrow_two.title().to_lowercase();
+
+            match (title_one.as_str(), title_two.as_str()) {
+                ("unknown device", _) if title_two != "unknown device" => {
+                    return gtk::Ordering::Larger
+                }
+                (_, "unknown device") if title_one != "unknown device" => {
+                    return gtk::Ordering::Smaller
+                }
+                ("unknown device", "unknown device") => (),
+                _ => (),
+            }
+
+            match (actionrow_one.connected(), actionrow_two.connected()) {
+                (true, false) => return gtk::Ordering::Smaller,
+                (false, true) => return gtk::Ordering::Larger,
+                _ => (),
+            }
+
+            match (actionrow_one.trusted(), actionrow_two.trusted()) {
+                (true, false) => return gtk::Ordering::Smaller,
+                (false, true) => return gtk::Ordering::Larger,
+                _ => (),
+            }
+
+            match actionrow_one.rssi().cmp(&actionrow_two.rssi()) {
+                std::cmp::Ordering::Less => return gtk::Ordering::Smaller,
+                std::cmp::Ordering::Greater => return gtk::Ordering::Larger,
+                _ => (),
+            }
+
+            match title_one.cmp(&title_two) {
+                std::cmp::Ordering::Less => return gtk::Ordering::Smaller,
+                std::cmp::Ordering::Greater => return gtk::Ordering::Larger,
+                _ => (),
+            }
+
+            return gtk::Ordering::Equal;
+        });
+        main_listbox.invalidate_sort();
+
+        // a header above the first device of each adapter (when showing all adapters) and of each section (when grouping)
+        main_listbox.set_header_func(|row, before| {
+            let action_row = row.clone().downcast::<DeviceActionRow>().unwrap();
+            let title = device_header(&action_row);
+
+            let first_of_section = match before {
+                Some(before) => device_header(&before.clone().downcast::<DeviceActionRow>().unwrap()) != title,
+                None => true,
+            };
+
+            if !title.is_empty() && first_of_section {
+                let header = gtk::Label::new(Some(&title));
+                header.set_xalign(0.0);
+                header.set_margin_top(8);
+                header.set_margin_bottom(8);
+                header.set_margin_start(12);
+                header.add_css_class("heading");
+                header.add_css_class("dim-label");
+
+                row.set_header(Some(&header));
+            } else {
+                row.set_header(None::<&gtk::Widget>);
+            }
+        });
+
+        // searching and filtering only hide rows, so nothing has to be searched for again when they change
+        let device_search_entry = self.imp().device_search_entry.get();
+        main_listbox.set_filter_func(clone!(
+            #[weak]
+            device_search_entry,
+            #[upgrade_or]
+            true,
+            move |row| {
+            let action_row = row.clone().downcast::<DeviceActionRow>().unwrap();
+            let filter = OVERSKRIDE_PROPS.lock().unwrap().device_filter.clone();
+
+            device_matches(&ListedDevice::from_row(&action_row), &filter, &device_search_entry.text().to_lowercase())
+        }));
+
+        device_search_entry.connect_search_changed(clone!(
+            #[weak]
+            main_listbox,
+            move |_| {
+            refilter_devices(&main_listbox);
+        }));
+
+        let device_filter_action = gio::SimpleAction::new_stateful(
+            "device-filter",
+            Some(glib::VariantTy::STRING),
+            &OVERSKRIDE_PROPS.lock().unwrap().device_filter.to_variant(),
+        );
+        device_filter_action.connect_activate(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |action, parameter| {
+            let Some(filter) = parameter.and_then(|parameter| parameter.get::<String>()) else {
+                return;
+            };
+
+            action.set_state(&filter.to_variant());
+            window.imp().settings.get().expect("cannot get settings for device filter").set_string("device-filter", &filter).expect("cannot set device filter");
+            OVERSKRIDE_PROPS.lock().unwrap().device_filter = filter;
+
+            refilter_devices(&window.imp().main_listbox.get());
+        }));
+        self.add_action(&device_filter_action);
+
+        let device_grouping_action = gio::SimpleAction::new_stateful(
+            "device-grouping",
+            Some(glib::VariantTy::STRING),
+            &OVERSKRIDE_PROPS.lock().unwrap().device_grouping.to_variant(),
+        );
+        device_grouping_action.connect_activate(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |action, parameter| {
+            let Some(grouping) = parameter.and_then(|parameter| parameter.get::<String>()) else {
+                return;
+            };
+
+            action.set_state(&grouping.to_variant());
+            window.imp().settings.get().expect("cannot get settings for device grouping").set_string("device-grouping", &grouping).expect("cannot set device grouping");
+            OVERSKRIDE_PROPS.lock().unwrap().device_grouping = grouping;
+
+            refilter_devices(&window.imp().main_listbox.get());
+        }));
+        self.add_action(&device_grouping_action);
+
+        // refresh devices action, possibly most important action here
         // refreshes the main list, checks if we can send a "refreshed list" message to the user
         // so no weird "adapter off" then "refreshed list" messages happen
         let refresh_action = gio::SimpleAction::new("refresh-devices", None);
@@ -1432,25 +2355,39 @@ impl OverskrideWindow {
                 let sender = sender0.clone();
                 let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
 
+                let show_all_adapters = OVERSKRIDE_PROPS.lock().unwrap().show_all_adapters;
+                let result = if show_all_adapters {
+                    device::get_devices_all_adapters(sender.clone()).await
+                } else {
+                    device::get_devices_continuous(sender.clone(), adapter_name).await
+                };
+
                 let mut can_send = true;
-                if let Err(err) = device::get_devices_continuous(sender.clone(), adapter_name).await {
-                    let string = err.message;
+                if let Err(err) = result {
 
                     can_send = false;
 
-                    sender
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
-                        .await.expect("cannot send message");
+                    // a blocked radio or a missing adapter already has the banner explaining it
+                    let radio_unavailable = {
+                        let props = OVERSKRIDE_PROPS.lock().unwrap();
+                        props.rfkill_state.is_blocked() || props.adapter_missing
+                    };
+
+                    if !radio_unavailable {
+                        sender
+                            .send(Message::PopupError(err, adw::ToastPriority::High))
+                            .await.expect("cannot send message");
+                    }
                     sender
                         .send(Message::UpdateListBoxImage())
                         .await.expect("cannot send message");
                 }
-                println!("can send: {}", can_send);
+                event_log::debug(Source::Adapter, "devices list refreshed").field("succeeded", can_send).record();
                 tokio::time::sleep(std::time::Duration::from_millis(100)).await;
                 if can_send {
                     sender
                         .send(Message::PopupError(
-                            "br-adapter-refreshed".to_string(),
+                            OverskrideError::DevicesRefreshed,
                             adw::ToastPriority::Normal,
                         ))
                         .await.expect("can't send message");
@@ -1458,7 +2395,194 @@ impl OverskrideWindow {
             }));
         });
         self.add_action(&refresh_action);
-        refresh_action.activate(None);
+
+        // searching for devices happens in scan sessions, outside of them only our own devices are tracked
+        let scan_action = gio::SimpleAction::new_stateful("scan", None, &false.to_variant());
+        let sender29 = sender.clone();
+        scan_action.connect_activate(move |action, _| {
+            let sender_clone = sender29.clone();
+            let scanning = action.state().and_then(|state| state.get::<bool>()).unwrap_or(false);
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::SetScanning(!scanning))
+                    .await.expect("cannot send message");
+            });
+        });
+        self.add_action(&scan_action);
+
+        // when only searching while focused, focusing the window starts the search instead
+        if OVERSKRIDE_PROPS.lock().unwrap().scan_only_when_focused {
+            refresh_action.activate(None);
+        }
+        else {
+            scan_action.activate(None);
+        }
+
+        let sender30 = sender.clone();
+        self.connect_is_active_notify(move |window| {
+            if !OVERSKRIDE_PROPS.lock().unwrap().scan_only_when_focused {
+                return;
+            }
+
+            let sender_clone = sender30.clone();
+            let active = window.is_active();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::SetScanning(active))
+                    .await.expect("cannot send message");
+            });
+        });
+
+        // the most important audio device that's around is connected once, if switching isn't turned off
+        let sender33 = sender.clone();
+        runtime().spawn(async move {
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+            if let Err(err) = audio_priority::connect_highest_present(adapter_name, sender33).await {
+                event_log::warning(Source::Device, "cannot connect the most important audio device").field("error", &err).record();
+            }
+        });
+
+        let audio_switching_modes = ["off", "startup", "always"];
+        let audio_switching_row = self.imp().audio_switching_row.get();
+        let audio_switching = OVERSKRIDE_PROPS.lock().unwrap().audio_switching.clone();
+        audio_switching_row.set_selected(audio_switching_modes.iter().position(|mode| *mode == audio_switching).unwrap_or(0) as u32);
+        let sender34 = sender.clone();
+        audio_switching_row.connect_selected_notify(move |row| {
+            let sender_clone = sender34.clone();
+            let audio_switching = audio_switching_modes.get(row.selected() as usize).unwrap_or(&"off").to_string();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::SetAudioSwitching(audio_switching))
+                    .await.expect("cannot send message");
+            });
+        });
+
+        populate_audio_priority_list(&self.imp().audio_priority_listbox.get(), sender.clone());
+
+        let export_backup_row = self.imp().export_backup_row.get();
+        let sender35 = sender.clone();
+        export_backup_row.connect_activated(move |_| {
+            let sender_clone = sender35.clone();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::ChooseBackupFile(true))
+                    .await.expect("cannot send message");
+            });
+        });
+
+        let windows_keys_row = self.imp().windows_keys_row.get();
+        let sender37 = sender.clone();
+        windows_keys_row.connect_activated(move |_| {
+            let sender_clone = sender37.clone();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::ChooseWindowsHive())
+                    .await.expect("cannot send message");
+            });
+        });
+
+        let import_backup_row = self.imp().import_backup_row.get();
+        let sender36 = sender.clone();
+        import_backup_row.connect_activated(move |_| {
+            let sender_clone = sender36.clone();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::ChooseBackupFile(false))
+                    .await.expect("cannot send message");
+            });
+        });
+
+        let scan_timeout_row = self.imp().scan_timeout_row.get();
+        scan_timeout_row.set_value(OVERSKRIDE_PROPS.lock().unwrap().scan_timeout as f64);
+        scan_timeout_row.connect_value_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |row| {
+            let scan_timeout = row.value() as u32;
+
+            window.imp().settings.get().expect("cannot get settings for scan timeout").set_uint("scan-timeout", scan_timeout).expect("cannot set scan timeout");
+            OVERSKRIDE_PROPS.lock().unwrap().scan_timeout = scan_timeout;
+        }));
+
+        let scan_when_focused_row = self.imp().scan_when_focused_row.get();
+        scan_when_focused_row.set_active(OVERSKRIDE_PROPS.lock().unwrap().scan_only_when_focused);
+
+        let sender40 = sender.clone();
+        scan_when_focused_row.connect_active_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |row| {
+            let enabled = row.is_active();
+
+            window.imp().settings.get().expect("cannot get settings for scanning when focused").set_boolean("scan-only-when-focused", enabled).expect("cannot set scanning when focused");
+            OVERSKRIDE_PROPS.lock().unwrap().scan_only_when_focused = enabled;
+
+            // follow the focus right away, instead of waiting for it to change
+            if enabled {
+                let sender_clone = sender40.clone();
+                let active = window.is_active();
+
+                glib::spawn_future_local(async move {
+                    sender_clone
+                        .send(Message::SetScanning(active))
+                        .await.expect("cannot send message");
+                });
+            }
+        }));
+
+        let distance_environment_list = self.imp().distance_environment_list.get();
+        let environment_names = rssi::ENVIRONMENTS.iter().map(|(_, name, _)| *name).collect::<Vec<&str>>();
+        distance_environment_list.splice(0, distance_environment_list.n_items(), &environment_names);
+
+        let distance_environment_row = self.imp().distance_environment_row.get();
+        let distance_environment = OVERSKRIDE_PROPS.lock().unwrap().distance_environment.clone();
+        distance_environment_row.set_selected(rssi::ENVIRONMENTS.iter().position(|(setting, _, _)| *setting == distance_environment).unwrap_or(2) as u32);
+        let sender39 = sender.clone();
+        distance_environment_row.connect_selected_notify(move |row| {
+            let sender_clone = sender39.clone();
+            let environment = rssi::ENVIRONMENTS.get(row.selected() as usize).map(|(setting, _, _)| setting.to_string()).unwrap_or("indoors".to_string());
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::SetDistanceEnvironment(environment))
+                    .await.expect("cannot send message");
+            });
+        });
+
+        // lifts an rfkill soft block, offered by the banner when bluetooth is blocked
+        let unblock_action = gio::SimpleAction::new("unblock-bluetooth", None);
+        let sender21 = sender.clone();
+        unblock_action.connect_activate(move |_, _| {
+            let sender = sender21.clone();
+            runtime().spawn(async move {
+                if let Err(err) = rfkill::unblock_bluetooth() {
+                    sender
+                        .send(Message::PopupError(err, adw::ToastPriority::High))
+                        .await.expect("cannot send message");
+                }
+            });
+        });
+        self.add_action(&unblock_action);
+
+        // opens the event log, for seeing what went on with a device or for bug reports
+        let event_log_action = gio::SimpleAction::new("event-log", None);
+        event_log_action.connect_activate(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |_, _| {
+            let page = EventLogPage::new();
+            page.start();
+
+            page.set_transient_for(Some(&window));
+            page.set_visible(true);
+        }));
+        self.add_action(&event_log_action);
 
         // try to connect to a device, this will fail often because bluetooth
         // it also updates the "loading spinner" on the row itself
@@ -1470,18 +2594,17 @@ impl OverskrideWindow {
 
             let sender_clone = sender1.clone();
             let address = OVERSKRIDE_PROPS.lock().unwrap().address;
-            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
 
             row.set_active(!row.active());
             runtime().spawn(async move {
                 if let Err(err) =
                     device::set_device_active(address, sender_clone.clone(), adapter_name).await
                 {
-                    let string = err.clone().message;
-                    println!("error while connecting {:?}\n", err);
+                    event_log::warning(Source::Device, "cannot connect or disconnect the device").device(address).field("error", &err).record();
 
                     sender_clone
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
+                        .send(Message::PopupError(err.into(), adw::ToastPriority::High))
                         .await.expect("cannot send message");
                     sender_clone
                         .send(Message::SwitchActive(false, address, true))
@@ -1503,16 +2626,15 @@ impl OverskrideWindow {
         blocked_row.connect_activated(move |row| {
             let sender_clone = sender2.clone();
             let address = OVERSKRIDE_PROPS.lock().unwrap().address;
-            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
             let current_state = !row.is_active();
 
             runtime().spawn(async move {
                 if let Err(err) =
                     device::set_device_blocked(address, sender_clone.clone(), adapter_name).await
                 {
-                    let string = err.message;
                     sender_clone
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
+                        .send(Message::PopupError(err.into(), adw::ToastPriority::High))
                         .await.expect("cannot send message");
                     sender_clone
                         .send(Message::SwitchBlocked(current_state))
@@ -1527,22 +2649,175 @@ impl OverskrideWindow {
         trusted_row.connect_activated(move |row| {
             let sender_clone = sender3.clone();
             let address = OVERSKRIDE_PROPS.lock().unwrap().address;
-            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
-            let trusted = !row.is_active();
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
+            let trusted = !row.is_active();
+
+            runtime().spawn(async move {
+                if let Err(err) =
+                    device::set_device_trusted(address, sender_clone.clone(), adapter_name).await
+                {
+                    sender_clone
+                        .send(Message::PopupError(err.into(), adw::ToastPriority::High))
+                        .await.expect("cannot send message");
+                    sender_clone
+                        .send(Message::SwitchTrusted(trusted))
+                        .await.expect("cannot send message");
+                };
+            });
+        });
+
+        // reconnect this device whenever it's lost
+        let keep_connected_row = self.imp().keep_connected_row.get();
+        let sender31 = sender.clone();
+        keep_connected_row.connect_activated(move |row| {
+            let sender_clone = sender31.clone();
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            let keep_connected = !row.is_active();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::SetKeepConnected(address, keep_connected))
+                    .await.expect("cannot send message");
+            });
+        });
+
+        // switch audio to and from this device depending on how important it is
+        let audio_priority_row = self.imp().audio_priority_row.get();
+        let sender32 = sender.clone();
+        audio_priority_row.connect_activated(move |row| {
+            let sender_clone = sender32.clone();
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            let prioritized = !row.is_active();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::SetAudioPriority(address, prioritized))
+                    .await.expect("cannot send message");
+            });
+        });
+
+        // the device's own settings, saved as soon as they're changed
+        let device_icon_list = self.imp().device_icon_list.get();
+        let icon_names = device_store::ICONS.iter().map(|(name, _)| *name).collect::<Vec<&str>>();
+        device_icon_list.splice(0, device_icon_list.n_items(), &icon_names);
+
+        let device_icon_row = self.imp().device_icon_row.get();
+        let sender38 = sender.clone();
+        device_icon_row.connect_selected_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |row| {
+            if *window.imp().filling_device_settings.borrow() {
+                return;
+            }
+
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            let icon = device_store::ICONS.get(row.selected() as usize).map(|(_, icon)| icon.to_string()).unwrap_or_default();
+            if !device_store::update(address, |settings| settings.icon = icon) {
+                return;
+            }
+
+            // going back to its own icon means asking what that was
+            let sender_clone = sender38.clone();
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
+            runtime().spawn(async move {
+                let icon = match bluer::Session::new().await.and_then(|session| session.adapter(&adapter_name)).and_then(|adapter| adapter.device(address)) {
+                    Ok(device) => device::get_device_icon(&device).await,
+                    Err(err) => Err(err),
+                };
+
+                match icon {
+                    Ok(icon) => sender_clone.send(Message::SwitchDeviceIcon(address, icon)).await.expect("cannot send message"),
+                    Err(err) => event_log::warning(Source::Device, "cannot get the device's icon").device(address).field("error", &err).record(),
+                }
+            });
+        }));
+
+        let preferred_profile_row = self.imp().preferred_profile_row.get();
+        preferred_profile_row.connect_selected_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |row| {
+            if *window.imp().filling_device_settings.borrow() {
+                return;
+            }
+
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            let profile = window.imp().preferred_profiles.borrow().get(row.selected() as usize).cloned().unwrap_or_default();
+            device_store::update(address, |settings| settings.audio_profile = profile);
+        }));
+
+        let battery_alert_row = self.imp().battery_alert_row.get();
+        battery_alert_row.connect_value_notify(move |row| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.battery_alert = row.value() as u8);
+        });
+
+        let auto_connect_row = self.imp().auto_connect_row.get();
+        auto_connect_row.connect_active_notify(move |row| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.auto_connect = row.is_active());
+        });
+
+        let auto_accept_files_row = self.imp().auto_accept_files_row.get();
+        auto_accept_files_row.connect_active_notify(move |row| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.auto_accept_files = row.is_active());
+        });
+
+        let proximity_row = self.imp().proximity_row.get();
+        proximity_row.connect_enable_expansion_notify(move |row| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.proximity = row.enables_expansion());
+        });
+
+        let proximity_away_row = self.imp().proximity_away_row.get();
+        proximity_away_row.connect_value_notify(move |row| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.proximity_away_rssi = row.value() as i16);
+        });
+
+        let proximity_back_row = self.imp().proximity_back_row.get();
+        proximity_back_row.connect_value_notify(move |row| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.proximity_back_rssi = row.value() as i16);
+        });
+
+        let proximity_lock_row = self.imp().proximity_lock_row.get();
+        proximity_lock_row.connect_active_notify(move |row| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.proximity_lock = row.is_active());
+        });
+
+        let proximity_unlock_row = self.imp().proximity_unlock_row.get();
+        proximity_unlock_row.connect_active_notify(move |row| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.proximity_unlock = row.is_active());
+        });
+
+        let proximity_away_command_row = self.imp().proximity_away_command_row.get();
+        proximity_away_command_row.connect_apply(move |entry| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.proximity_away_command = entry.text().trim().to_string());
+        });
+
+        let proximity_back_command_row = self.imp().proximity_back_command_row.get();
+        proximity_back_command_row.connect_apply(move |entry| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.proximity_back_command = entry.text().trim().to_string());
+        });
+
+        let download_folder_row = self.imp().download_folder_row.get();
+        download_folder_row.connect_apply(move |entry| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            let folder = entry.text().trim().trim_matches('/').to_string();
+            device_store::update(address, |settings| settings.download_folder = folder);
+        });
 
-            runtime().spawn(async move {
-                if let Err(err) =
-                    device::set_device_trusted(address, sender_clone.clone(), adapter_name).await
-                {
-                    let string = err.message;
-                    sender_clone
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
-                        .await.expect("cannot send message");
-                    sender_clone
-                        .send(Message::SwitchTrusted(trusted))
-                        .await.expect("cannot send message");
-                };
-            });
+        let notes_row = self.imp().notes_row.get();
+        notes_row.connect_apply(move |entry| {
+            let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+            device_store::update(address, |settings| settings.notes = entry.text().trim().to_string());
         });
 
         // change the currently selected devices name
@@ -1552,15 +2827,14 @@ impl OverskrideWindow {
             let sender_clone = sender4.clone();
             let name = entry.text().to_string().trim().to_string();
             let address = OVERSKRIDE_PROPS.lock().unwrap().address;
-            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
 
             runtime().spawn(async move {
                 if let Err(err) =
                     device::set_device_name(address, name, sender_clone.clone(), adapter_name).await
                 {
-                    let string = err.message;
                     sender_clone
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
+                        .send(Message::PopupError(err, adw::ToastPriority::High))
                         .await.expect("cannot send message");
                 }
             });
@@ -1573,7 +2847,7 @@ impl OverskrideWindow {
         remove_device_button.connect_clicked(move |_| {
             let sender_clone = sender4.clone();
             let address = OVERSKRIDE_PROPS.lock().unwrap().address;
-            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
 
             runtime().spawn(async move {
                 sender_clone
@@ -1581,9 +2855,8 @@ impl OverskrideWindow {
                     .await.expect("cannot send message");
                 if let Err(err) = device::remove_device(address, sender_clone.clone(), adapter_name).await
                 {
-                    let string = err.message;
                     sender_clone
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
+                        .send(Message::PopupError(err.into(), adw::ToastPriority::High))
                         .await.expect("cannot send message");
                 }
             });
@@ -1603,9 +2876,8 @@ impl OverskrideWindow {
                     if let Err(err) =
                         bluetooth_settings::set_adapter_powered(adapter_name, sender_clone.clone()).await
                     {
-                        let string = err.message;
                         sender_clone
-                            .send(Message::PopupError(string, adw::ToastPriority::High))
+                            .send(Message::PopupError(err.into(), adw::ToastPriority::High))
                             .await.expect("cannot send message");
                         sender_clone
                             .send(Message::SwitchAdapterPowered(false))
@@ -1626,9 +2898,8 @@ impl OverskrideWindow {
                 if let Err(err) =
                     bluetooth_settings::set_adapter_discoverable(adapter_name, sender_clone.clone()).await
                 {
-                    let string = "Adapter ".to_string() + &err.message;
                     sender_clone
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
+                        .send(Message::PopupError(err.into(), adw::ToastPriority::High))
                         .await.expect("cannot send message");
                     sender_clone
                         .send(Message::SwitchAdapterDiscoverable(false))
@@ -1651,9 +2922,8 @@ impl OverskrideWindow {
                     adapter_name,
                     sender_clone.clone(),
                 ).await {
-                    let string = "Adapter ".to_string() + &err.message;
                     sender_clone
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
+                        .send(Message::PopupError(err.into(), adw::ToastPriority::High))
                         .await.expect("cannot send message");
                 }
             });
@@ -1674,9 +2944,8 @@ impl OverskrideWindow {
                     adapter_name,
                     sender_clone.clone(),
                 ).await {
-                    let string = err.message;
                     sender_clone
-                        .send(Message::PopupError(string, adw::ToastPriority::High))
+                        .send(Message::PopupError(err.into(), adw::ToastPriority::High))
                         .await.expect("cannot send message");
                     sender_clone
                         .send(Message::SwitchAdapterTimeout(0))
@@ -1704,9 +2973,8 @@ impl OverskrideWindow {
                     if let Err(err) =
                         bluetooth_settings::get_adapter_properties(names, sender, adapter_name).await
                     {
-                        let string = "Adapter ".to_string() + &err.message;
                         sender_clone
-                            .send(Message::PopupError(string, adw::ToastPriority::Normal))
+                            .send(Message::PopupError(err.into(), adw::ToastPriority::Normal))
                             .await.expect("cannot send message");
                     }
                 }
@@ -1760,7 +3028,7 @@ impl OverskrideWindow {
                 if !connected {
                     another_clone
                         .send(Message::PopupError(
-                            "obex-transfer-not-connected".to_string(),
+                            OverskrideError::TransferNotConnected,
                             adw::ToastPriority::Normal,
                         ))
                         .await.expect("cannot send message");
@@ -1775,13 +3043,13 @@ impl OverskrideWindow {
 
                     let sender_clone = another_clone.clone();
                     std::thread::spawn(move || {
-                        obex::start_send_file(destination, source, sender_clone);
+                        obex::start_send_file(destination, source, vec![], sender_clone);
                     });
                 } else {
-                    println!("error while sending file, destination doesn't exist???");
+                    event_log::warning(Source::Obex, "cannot send files, no device to send them to").record();
                     another_clone
                         .send(Message::PopupError(
-                            "obex-transfer-not-connected".to_string(),
+                            OverskrideError::TransferNotConnected,
                             adw::ToastPriority::Normal,
                         ))
                         .await.expect("cannot send message");
@@ -1833,7 +3101,7 @@ impl OverskrideWindow {
                 }
 
                 OVERSKRIDE_PROPS.lock().unwrap().auto_accept_from_trusted = !active;
-                println!("auto accept is {}", OVERSKRIDE_PROPS.lock().unwrap().auto_accept_from_trusted);
+                event_log::debug(Source::Obex, "auto accepting from trusted devices changed").field("enabled", !active).record();
             });
         });
         auto_accept_trusted_row.set_active(OVERSKRIDE_PROPS.lock().unwrap().auto_accept_from_trusted);
@@ -1857,9 +3125,9 @@ impl OverskrideWindow {
                     .unwrap();
                 let address = current_device.get_bluer_address();
 
-                let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+                let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
 
-                let (name, address, manufacturer, device_type, distance, services_list) =
+                let (name, address, tx_power, distance, services_list, identity) =
                     if let Ok(info) = device::get_more_info(address, adapter_name).await {
                         info
                     } else {
@@ -1869,10 +3137,10 @@ impl OverskrideWindow {
                 message.initialize_from_info(
                     name,
                     address,
-                    manufacturer,
-                    device_type,
+                    tx_power,
                     distance,
                     services_list,
+                    identity,
                 );
 
                 message.set_transient_for(Some(&self_clone7));
@@ -1883,6 +3151,23 @@ impl OverskrideWindow {
             ));
         });
 
+        let diagnostics_row = self.imp().diagnostics_row.get();
+        diagnostics_row.connect_activated(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |_| {
+            let Some(current_device) = window.imp().main_listbox.get().selected_row().and_downcast::<DeviceActionRow>() else {
+                return;
+            };
+
+            let page = DiagnosticsPage::new();
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().device_adapter.clone();
+            page.start(current_device.title().to_string(), current_device.get_bluer_address(), adapter_name);
+
+            page.set_transient_for(Some(&window));
+            page.set_visible(true);
+        }));
+
         // set the hide unknown devices boolean
         let hide_unknowns_switch_row = self.imp().hide_unknowns_switch_row.get();
         let sender14 = sender.clone();
@@ -1898,6 +3183,235 @@ impl OverskrideWindow {
             });
         });
         hide_unknowns_switch_row.set_active(OVERSKRIDE_PROPS.lock().unwrap().hide_unknown_devices);
+
+        // let trusted phones use this device as a speaker
+        let audio_sink_switch_row = self.imp().audio_sink_switch_row.get();
+        let sender15 = sender.clone();
+        audio_sink_switch_row.connect_activated(move |row| {
+            let sender_clone = sender15.clone();
+            let enabled = !row.is_active();
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+
+            runtime().spawn(async move {
+                if let Err(err) = audio_sink::set_audio_sink_mode(adapter_name, enabled, sender_clone.clone()).await {
+                    sender_clone
+                        .send(Message::PopupError(err, adw::ToastPriority::High))
+                        .await.expect("cannot send message");
+                }
+            });
+        });
+        audio_sink_switch_row.set_active(OVERSKRIDE_PROPS.lock().unwrap().audio_sink_mode);
+
+        // show calls from trusted phones, only works if oFono is running
+        let call_integration_switch_row = self.imp().call_integration_switch_row.get();
+        let sender16 = sender.clone();
+        call_integration_switch_row.connect_activated(move |row| {
+            let sender_clone = sender16.clone();
+            let enabled = !row.is_active();
+
+            runtime().spawn(async move {
+                if let Err(err) = telephony::set_call_integration(enabled, sender_clone.clone()).await {
+                    sender_clone
+                        .send(Message::PopupError(err, adw::ToastPriority::High))
+                        .await.expect("cannot send message");
+                }
+            });
+        });
+        call_integration_switch_row.set_active(OVERSKRIDE_PROPS.lock().unwrap().call_integration);
+
+        // closing the window only hides it while running in the background, so both agents stay registered
+        let run_in_background_switch_row = self.imp().run_in_background_switch_row.get();
+        let sender22 = sender.clone();
+        run_in_background_switch_row.connect_activated(move |row| {
+            let sender_clone = sender22.clone();
+            let enabled = !row.is_active();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::SetRunInBackground(enabled))
+                    .await.expect("cannot send message");
+            });
+        });
+        run_in_background_switch_row.set_active(OVERSKRIDE_PROPS.lock().unwrap().run_in_background);
+
+        self.connect_close_request(|window| {
+            if OVERSKRIDE_PROPS.lock().unwrap().run_in_background {
+                // stopping here skips the class handler, which is what saves the window size
+                if let Err(err) = window.save_settings() {
+                    event_log::warning(Source::App, "cannot save the window size").field("error", err).record();
+                }
+                window.set_visible(false);
+                return glib::Propagation::Stop;
+            }
+
+            glib::Propagation::Proceed
+        });
+
+        // answering and rejecting calls are app actions so the call notification's buttons can use them too
+        let answer_call_action = gio::SimpleAction::new("answer-call", None);
+        let sender17 = sender.clone();
+        answer_call_action.connect_activate(move |_, _| {
+            let sender_clone = sender17.clone();
+            let path = OVERSKRIDE_PROPS.lock().unwrap().current_call.clone();
+            if path.is_empty() {
+                return;
+            }
+
+            runtime().spawn(async move {
+                if let Ok(Err(err)) = tokio::task::spawn_blocking(move || telephony::answer_call(path)).await {
+                    sender_clone
+                        .send(Message::PopupError(err, adw::ToastPriority::High))
+                        .await.expect("cannot send message");
+                }
+            });
+        });
+
+        let reject_call_action = gio::SimpleAction::new("reject-call", None);
+        let sender18 = sender.clone();
+        reject_call_action.connect_activate(move |_, _| {
+            let sender_clone = sender18.clone();
+            let path = OVERSKRIDE_PROPS.lock().unwrap().current_call.clone();
+            if path.is_empty() {
+                return;
+            }
+
+            runtime().spawn(async move {
+                if let Ok(Err(err)) = tokio::task::spawn_blocking(move || telephony::hangup_call(path)).await {
+                    sender_clone
+                        .send(Message::PopupError(err, adw::ToastPriority::High))
+                        .await.expect("cannot send message");
+                }
+            });
+        });
+
+        if let Some(application) = self.application() {
+            application.add_action(&answer_call_action);
+            application.add_action(&reject_call_action);
+        }
+
+        // list the devices of every adapter together, tagged by the adapter that found them
+        let show_all_adapters_switch_row = self.imp().show_all_adapters_switch_row.get();
+        let sender20 = sender.clone();
+        show_all_adapters_switch_row.connect_activated(move |row| {
+            let sender_clone = sender20.clone();
+            let enabled = !row.is_active();
+
+            glib::spawn_future_local(async move {
+                sender_clone
+                    .send(Message::SetShowAllAdapters(enabled))
+                    .await.expect("cannot send message");
+            });
+        });
+        show_all_adapters_switch_row.set_active(OVERSKRIDE_PROPS.lock().unwrap().show_all_adapters);
+
+        // picks which adapter the device page's actions go through
+        let connect_using_row = self.imp().connect_using_row.get();
+        connect_using_row.connect_selected_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |row| {
+            let adapter_name = window.imp().connect_using_adapters.borrow().get(row.selected() as usize).cloned();
+            if let Some(adapter_name) = adapter_name {
+                OVERSKRIDE_PROPS.lock().unwrap().device_adapter = adapter_name;
+            }
+        }));
+
+        // every discovery setting sends the whole set, which is saved and applied by restarting discovery
+        let discovery_settings = OVERSKRIDE_PROPS.lock().unwrap().discovery_settings.clone();
+        let transports = ["auto", "bredr", "le"];
+
+        let discovery_transport_row = self.imp().discovery_transport_row.get();
+        discovery_transport_row.set_selected(transports.iter().position(|transport| *transport == discovery_settings.transport).unwrap_or(0) as u32);
+        let sender23 = sender.clone();
+        discovery_transport_row.connect_selected_notify(move |row| {
+            let mut discovery_settings = OVERSKRIDE_PROPS.lock().unwrap().discovery_settings.clone();
+            discovery_settings.transport = transports.get(row.selected() as usize).unwrap_or(&"auto").to_string();
+            send_discovery_settings(sender23.clone(), discovery_settings);
+        });
+
+        let discovery_rssi_row = self.imp().discovery_rssi_row.get();
+        discovery_rssi_row.set_value(discovery_settings.rssi as f64);
+        let sender24 = sender.clone();
+        discovery_rssi_row.connect_value_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |_| {
+            window.send_discovery_values_later(sender24.clone());
+        }));
+
+        let discovery_pathloss_row = self.imp().discovery_pathloss_row.get();
+        discovery_pathloss_row.set_value(discovery_settings.pathloss as f64);
+        let sender25 = sender.clone();
+        discovery_pathloss_row.connect_value_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |_| {
+            window.send_discovery_values_later(sender25.clone());
+        }));
+
+        let discovery_uuids_row = self.imp().discovery_uuids_row.get();
+        discovery_uuids_row.set_text(&discovery_settings.uuids.join(", "));
+        let sender26 = sender.clone();
+        discovery_uuids_row.connect_apply(move |entry| {
+            let uuids = entry.text().split(',').map(|uuid| uuid.trim().to_string()).filter(|uuid| !uuid.is_empty()).collect::<Vec<String>>();
+
+            if let Err(err) = bluetooth_settings::parse_uuids(&uuids) {
+                let sender_clone = sender26.clone();
+                glib::spawn_future_local(async move {
+                    sender_clone
+                        .send(Message::PopupError(err, adw::ToastPriority::High))
+                        .await.expect("cannot send message");
+                });
+                return;
+            }
+
+            let mut discovery_settings = OVERSKRIDE_PROPS.lock().unwrap().discovery_settings.clone();
+            discovery_settings.uuids = uuids;
+            send_discovery_settings(sender26.clone(), discovery_settings);
+        });
+
+        let discovery_pattern_row = self.imp().discovery_pattern_row.get();
+        discovery_pattern_row.set_text(&discovery_settings.pattern);
+        let sender27 = sender.clone();
+        discovery_pattern_row.connect_apply(move |entry| {
+            let mut discovery_settings = OVERSKRIDE_PROPS.lock().unwrap().discovery_settings.clone();
+            discovery_settings.pattern = entry.text().trim().to_string();
+            send_discovery_settings(sender27.clone(), discovery_settings);
+        });
+
+        let discovery_duplicate_data_row = self.imp().discovery_duplicate_data_row.get();
+        discovery_duplicate_data_row.set_active(discovery_settings.duplicate_data);
+        let sender28 = sender.clone();
+        discovery_duplicate_data_row.connect_active_notify(move |row| {
+            let mut discovery_settings = OVERSKRIDE_PROPS.lock().unwrap().discovery_settings.clone();
+            discovery_settings.duplicate_data = row.is_active();
+            send_discovery_settings(sender28.clone(), discovery_settings);
+        });
+
+        // scanning only for broadcasts restarts discovery with an LE Audio filter
+        let broadcast_listbox = self.imp().broadcast_listbox.get();
+        let placeholder = gtk::Label::new(Some("No broadcasts found"));
+        placeholder.set_margin_top(12);
+        placeholder.set_margin_bottom(12);
+        placeholder.add_css_class("dim-label");
+        broadcast_listbox.set_placeholder(Some(&placeholder));
+
+        let broadcast_scan_switch = self.imp().broadcast_scan_switch.get();
+        let sender19 = sender.clone();
+        broadcast_scan_switch.connect_state_set(move |_, state| {
+            let sender_clone = sender19.clone();
+            let current = OVERSKRIDE_PROPS.lock().unwrap().broadcast_scanning;
+
+            if state != current {
+                glib::spawn_future_local(async move {
+                    sender_clone
+                        .send(Message::SetBroadcastScanning(state))
+                        .await.expect("cannot send message");
+                });
+            }
+
+            glib::Propagation::Proceed
+        });
     }
 
     /// on app exit, save the current settings
@@ -1913,8 +3427,7 @@ impl OverskrideWindow {
             .get()
             .expect("cannot get settings, setup improperly?");
 
-        println!("size is {:?}", size);
-
+        event_log::debug(Source::App, "saving settings").field("width", size.0).field("height", size.1).record();
         settings.set_int("window-width", size.0)?;
         settings.set_int("window-height", size.1)?;
         settings.set_boolean("window-maximized", self.is_maximized())?;
@@ -1936,8 +3449,6 @@ impl OverskrideWindow {
         let maximized = settings.boolean("window-maximized");
         let first_auto_accept = settings.boolean("first-auto-accept");
 
-        // println!("new size is {:?}", (width, height));
-
         self.set_default_size(width, height);
         self.set_maximized(maximized);
 
@@ -1972,14 +3483,138 @@ impl OverskrideWindow {
             store_folder += "/";
         }
 
-        println!("store folder is: {}", &store_folder);
+        event_log::debug(Source::Obex, "received files are stored").field("folder", &store_folder).record();
         file_save_location.set_text(&store_folder);
 
         let hide_unknown_devices = settings.boolean("hide-unknown-devices");
+        let audio_sink_mode = settings.boolean("audio-sink-mode");
+        let call_integration = settings.boolean("call-integration");
+        let show_all_adapters = settings.boolean("show-all-adapters");
+        let run_in_background = settings.boolean("run-in-background");
+        let device_filter = settings.string("device-filter").to_string();
+        let device_grouping = settings.string("device-grouping").to_string();
+        let discovery_settings = bluetooth_settings::DiscoverySettings {
+            transport: settings.string("discovery-transport").to_string(),
+            rssi: settings.value("discovery-rssi").get::<i16>().unwrap_or(0),
+            pathloss: settings.value("discovery-pathloss").get::<u16>().unwrap_or(0),
+            uuids: settings.strv("discovery-uuids").iter().map(|uuid| uuid.to_string()).collect(),
+            duplicate_data: settings.boolean("discovery-duplicate-data"),
+            pattern: settings.string("discovery-pattern").to_string(),
+        };
 
         OVERSKRIDE_PROPS.lock().unwrap().store_folder = store_folder;
         OVERSKRIDE_PROPS.lock().unwrap().auto_accept_first = first_auto_accept;
-        OVERSKRIDE_PROPS.lock().unwrap().hide_unknown_devices = hide_unknown_devices
+        OVERSKRIDE_PROPS.lock().unwrap().hide_unknown_devices = hide_unknown_devices;
+        OVERSKRIDE_PROPS.lock().unwrap().audio_sink_mode = audio_sink_mode;
+        OVERSKRIDE_PROPS.lock().unwrap().call_integration = call_integration;
+        OVERSKRIDE_PROPS.lock().unwrap().show_all_adapters = show_all_adapters;
+        OVERSKRIDE_PROPS.lock().unwrap().run_in_background = run_in_background;
+        OVERSKRIDE_PROPS.lock().unwrap().device_filter = device_filter;
+        OVERSKRIDE_PROPS.lock().unwrap().device_grouping = device_grouping;
+        OVERSKRIDE_PROPS.lock().unwrap().discovery_settings = discovery_settings;
+        OVERSKRIDE_PROPS.lock().unwrap().scan_timeout = settings.uint("scan-timeout");
+        OVERSKRIDE_PROPS.lock().unwrap().scan_only_when_focused = settings.boolean("scan-only-when-focused");
+        OVERSKRIDE_PROPS.lock().unwrap().keep_connected = settings.strv("keep-connected-devices").iter().filter_map(|address| address.parse::<bluer::Address>().ok()).collect();
+        OVERSKRIDE_PROPS.lock().unwrap().audio_priority = settings.strv("audio-priority").iter().filter_map(|address| address.parse::<bluer::Address>().ok()).collect();
+        OVERSKRIDE_PROPS.lock().unwrap().audio_switching = settings.string("audio-switching").to_string();
+        OVERSKRIDE_PROPS.lock().unwrap().distance_environment = settings.string("distance-environment").to_string();
+    }
+
+    /// Fills the preferred audio profile row with "don't change" and the given profiles (name => description),
+    /// keeping the device's preferred profile in it even if it isn't one of them (while it's disconnected there are none)
+    fn fill_preferred_profiles(&self, profiles: HashMap<String, String>) {
+        let address = OVERSKRIDE_PROPS.lock().unwrap().address;
+        let preferred = device_store::get(address).audio_profile;
+
+        let mut choices = profiles.into_iter().collect::<Vec<(String, String)>>();
+        choices.sort_by(|one, two| one.1.cmp(&two.1));
+        if !preferred.is_empty() && !choices.iter().any(|(name, _)| *name == preferred) {
+            choices.push((preferred.clone(), preferred.clone()));
+        }
+        choices.insert(0, (String::new(), "Don't Change".to_string()));
+
+        let filling = *self.imp().filling_device_settings.borrow();
+        *self.imp().filling_device_settings.borrow_mut() = true;
+
+        let descriptions = choices.iter().map(|(_, description)| description.as_str()).collect::<Vec<&str>>();
+        let preferred_profile_list = self.imp().preferred_profile_list.get();
+        preferred_profile_list.splice(0, preferred_profile_list.n_items(), &descriptions);
+
+        let selected = choices.iter().position(|(name, _)| *name == preferred).unwrap_or(0);
+        self.imp().preferred_profile_row.get().set_selected(selected as u32);
+        *self.imp().preferred_profiles.borrow_mut() = choices.into_iter().map(|(name, _)| name).collect();
+
+        *self.imp().filling_device_settings.borrow_mut() = filling;
+    }
+
+    /// saves the audio priority list, most important first
+    fn save_audio_priority(&self) {
+        let audio_priority = OVERSKRIDE_PROPS.lock().unwrap().audio_priority.iter().map(|address| address.to_string()).collect::<Vec<String>>();
+        self.imp().settings.get().expect("cannot get settings for audio priority").set_strv("audio-priority", audio_priority).expect("cannot set audio priority");
+    }
+
+    /// sends the signal and path loss rows' values once they stop changing for a moment, holding an arrow would restart discovery on every step otherwise
+    fn send_discovery_values_later(&self, sender: Sender<Message>) {
+        if let Some(source_id) = self.imp().discovery_settings_timeout_id.take() {
+            source_id.remove();
+        }
+
+        let source_id = glib::timeout_add_local_once(std::time::Duration::from_millis(500), clone!(
+            #[weak(rename_to = window)]
+            self,
+            move || {
+            window.imp().discovery_settings_timeout_id.take();
+
+            let mut discovery_settings = OVERSKRIDE_PROPS.lock().unwrap().discovery_settings.clone();
+            discovery_settings.rssi = window.imp().discovery_rssi_row.get().value() as i16;
+            discovery_settings.pathloss = window.imp().discovery_pathloss_row.get().value() as u16;
+            send_discovery_settings(sender, discovery_settings);
+        }));
+        self.imp().discovery_settings_timeout_id.replace(Some(source_id));
+    }
+
+    /// asks which paired device to send the given files to, then sends them, used for files opened with Overskride
+    pub fn send_files_to_picked_device(&self, file_paths: Vec<String>) {
+        let Some(sender) = OVERSKRIDE_PROPS.lock().unwrap().sender.clone() else {
+            return;
+        };
+
+        runtime().spawn(async move {
+            let adapter_name = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+
+            match device::get_paired_devices(adapter_name).await {
+                Ok((source, devices)) => {
+                    sender.send(Message::PickDeviceForFiles(file_paths, source, devices)).await.expect("cannot send message");
+                }
+                Err(err) => {
+                    sender.send(Message::PopupError(err.into(), adw::ToastPriority::High)).await.expect("cannot send message");
+                }
+            }
+        });
+    }
+
+    /// shows why bluetooth can't be used right now (blocked radio or no adapter), hiding the banner otherwise
+    fn update_radio_banner(&self) {
+        let radio_banner = self.imp().radio_banner.get();
+        let (rfkill_state, adapter_missing) = {
+            let props = OVERSKRIDE_PROPS.lock().unwrap();
+            (props.rfkill_state, props.adapter_missing)
+        };
+
+        if rfkill_state.hard_blocked {
+            radio_banner.set_title("Bluetooth is turned off by a hardware switch");
+            radio_banner.set_button_label(None);
+        }
+        else if rfkill_state.soft_blocked {
+            radio_banner.set_title("Bluetooth is blocked");
+            radio_banner.set_button_label(Some("Unblock"));
+        }
+        else if adapter_missing {
+            radio_banner.set_title("No Bluetooth adapter found, plug one in to continue");
+            radio_banner.set_button_label(None);
+        }
+
+        radio_banner.set_revealed(rfkill_state.is_blocked() || adapter_missing);
     }
 
     // first thing called when app launches, sets it up so it can be used basically
@@ -1990,49 +3625,91 @@ impl OverskrideWindow {
         unsafe {
             // makes a new sender, devices lut, rssi lut, and updates the current adapter name in gsettings
             OVERSKRIDE_PROPS.lock().unwrap().sender = Some(sender.clone());
-            DEVICES_LUT = Some(HashMap::new());
+            *DEVICES_LUT.lock().unwrap() = Some(HashMap::new());
             RSSI_LUT = Some(HashMap::new());
             let name = settings.string("current-adapter-name").to_string();
             let session = bluer::Session::new().await?;
-
-            // if current adapter doesn't exist, get the default adapter instead (first run/error stuff)
-            if name.is_empty() {
-                let adapter = session.default_adapter().await?;
-                OVERSKRIDE_PROPS.lock().unwrap().current_adapter = adapter.name().to_string();
-                OVERSKRIDE_PROPS.lock().unwrap().name = adapter.name().to_string();
-
-                let current_adapter = adapter.name().to_string();
-                settings
-                    .set_string("current-adapter-name", current_adapter.as_str())
-                    .expect("cannot set default adapter at start");
-                settings
-                    .set_string("original-adapter-name", current_adapter.as_str())
-                    .expect("cannot set original adapter at start");
+            let adapter_names = session.adapter_names().await?;
+
+            // if current adapter doesn't exist (first run, or it was unplugged), get the default adapter instead
+            if name.is_empty() || !adapter_names.contains(&name) {
+                if let Ok(adapter) = session.default_adapter().await {
+                    OVERSKRIDE_PROPS.lock().unwrap().current_adapter = adapter.name().to_string();
+                    OVERSKRIDE_PROPS.lock().unwrap().name = adapter.name().to_string();
+
+                    let current_adapter = adapter.name().to_string();
+                    settings
+                        .set_string("current-adapter-name", current_adapter.as_str())
+                        .expect("cannot set default adapter at start");
+                    if name.is_empty() {
+                        settings
+                            .set_string("original-adapter-name", current_adapter.as_str())
+                            .expect("cannot set original adapter at start");
+                    }
+                }
             } else {
                 OVERSKRIDE_PROPS.lock().unwrap().current_adapter = name.clone();
             }
 
             let mut lut = HashMap::new();
 
-            let adapter = session.adapter(OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone().as_str())?;
-            let alias = adapter.alias().await?;
-            println!("startup alias is: {}\n", alias);
-            self.imp()
-                .timeout_time_adjustment
-                .get()
-                .set_value(adapter.discoverable_timeout().await?.into());
+            // no adapter plugged in isn't fatal, the banner says so and we pick it up once it's plugged in
+            if adapter_names.is_empty() {
+                OVERSKRIDE_PROPS.lock().unwrap().adapter_missing = true;
+                sender.send(Message::SetAdapterMissing(true)).await.expect("cannot send message");
+            } else {
+                let adapter = session.adapter(OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone().as_str())?;
+                let alias = adapter.alias().await?;
+                event_log::debug(Source::Adapter, "adapter found on startup").field("adapter", adapter.name()).field("alias", &alias).record();
+                self.imp()
+                    .timeout_time_adjustment
+                    .get()
+                    .set_value(adapter.discoverable_timeout().await?.into());
 
-            lut.insert(alias.to_string(), OVERSKRIDE_PROPS.lock().unwrap().current_adapter.to_string());
+                lut.insert(alias.to_string(), OVERSKRIDE_PROPS.lock().unwrap().current_adapter.to_string());
+            }
             // update available adapters lut
             ADAPTERS_LUT = Some(lut);
 
             // start the bluetooth and obex agents in separate threads, so they're always available to receive input
+            // the supervisors register them again whenever bluetoothd or obexd restart
+            let clone = sender.clone();
+            std::thread::spawn(move || {
+                supervisor::supervise_obex_agent(clone);
+            });
             let clone = sender.clone();
             std::thread::spawn(move || {
-                register_obex_agent(clone.clone()).expect("cannot register obex agent");
+                supervisor::supervise_bluetooth_agent(clone);
             });
+            // keep the adapter list up to date when adapters are plugged in or removed
+            let clone = sender.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = bluetooth_settings::watch_adapters(clone.clone()) {
+                    event_log::warning(Source::Adapter, "cannot watch for adapters").field("error", &err).record();
+                }
+            });
+            // follow rfkill so a blocked radio shows up as such instead of as a broken adapter
+            let clone = sender.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = rfkill::watch_rfkill(clone.clone()) {
+                    event_log::warning(Source::App, "cannot watch rfkill").field("error", &err).record();
+                }
+            });
+            if OVERSKRIDE_PROPS.lock().unwrap().run_in_background {
+                spawn_tray(sender.clone());
+            }
+            // our own D-Bus API, for scripts and other apps
+            let clone = sender.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = dbus_api::serve_api(clone.clone()) {
+                    event_log::warning(Source::App, "cannot serve the api").field("error", &err).record();
+                }
+            });
+            // calls only show up if they're enabled, but watch for them anyways so enabling doesn't need a restart
             std::thread::spawn(move || {
-                register_bluetooth_agent(sender.clone()).expect("cannot register bluetooth agent");
+                if let Err(err) = telephony::watch_calls(sender.clone()) {
+                    event_log::warning(Source::App, "cannot watch for phone calls").field("error", &err).record();
+                }
             });
         }
 
@@ -2040,17 +3717,244 @@ impl OverskrideWindow {
     }
 }
 
+/// finds the row of the device with the given address in the device list
+fn find_device_row(listbox: &gtk::ListBox, address: bluer::Address) -> Option<DeviceActionRow> {
+    let mut index = 0;
+
+    while let Some(row) = listbox.row_at_index(index) {
+        let action_row = row.downcast::<DeviceActionRow>().expect("cannot downcast to action row.");
+        if action_row.get_bluer_address() == address {
+            return Some(action_row);
+        }
+        index += 1;
+    }
+
+    None
+}
+
+/// reapplies the search, filter, sorting and sections of the device list
+fn refilter_devices(listbox: &gtk::ListBox) {
+    listbox.invalidate_filter();
+    listbox.invalidate_sort();
+    listbox.invalidate_headers();
+}
+
+/// what the devices list filters, searches and sections devices by, taken from their rows
+#[derive(Debug, Clone, Default)]
+struct ListedDevice {
+    name: String,
+    address: bluer::Address,
+    manufacturer: String,
+    /// the bluez icon name, like "audio-headphones"
+    device_type: String,
+    paired: bool,
+    trusted: bool,
+    connected: bool,
+    /// 0 when it has no signal
+    rssi: i32,
+}
+
+impl ListedDevice {
+    fn from_row(row: &DeviceActionRow) -> Self {
+        ListedDevice {
+            name: row.title().to_string(),
+            address: row.get_bluer_address(),
+            manufacturer: row.manufacturer(),
+            device_type: row.device_type(),
+            paired: row.paired(),
+            trusted: row.trusted(),
+            connected: row.connected(),
+            rssi: row.rssi(),
+        }
+    }
+}
+
+/// whether this device passes the chosen filter and matches the search (by name, address, maker or type)
+fn device_matches(device: &ListedDevice, filter: &str, search: &str) -> bool {
+    let passes_filter = match filter {
+        "paired" => device.paired,
+        "trusted" => device.trusted,
+        "connected" => device.connected,
+        // in range (has a signal) but not one of ours yet
+        "nearby" => !device.paired && device.rssi != 0,
+        _ => true,
+    };
+
+    if !passes_filter {
+        return false;
+    }
+
+    if search.is_empty() {
+        return true;
+    }
+
+    [
+        device.name.clone(),
+        device.address.to_string(),
+        device.manufacturer.clone(),
+        device.device_type.clone(),
+        device_type_name(&device.device_type).to_string(),
+    ]
+    .iter()
+    .any(|field| field.to_lowercase().contains(search))
+}
+
+/// turns a bluez device icon name (like "audio-headphones") into the name of a section
+fn device_type_name(icon: &str) -> &'static str {
+    match icon {
+        i if i.starts_with("audio") => "Audio",
+        i if i.starts_with("phone") => "Phones",
+        i if i.starts_with("computer") => "Computers",
+        i if i.starts_with("input") => "Input Devices",
+        i if i.starts_with("camera") || i.starts_with("video") => "Cameras",
+        i if i.starts_with("printer") || i.starts_with("scanner") => "Printers and Scanners",
+        _ => "Other",
+    }
+}
+
+/// which section this device goes in, the number keeps the sections in order ("My Devices" before "Nearby", "Other" last)
+fn device_group(device: &ListedDevice, grouping: &str) -> (u8, String) {
+    match grouping {
+        "owned" if device.paired || device.trusted || device.connected => (0, "My Devices".to_string()),
+        "owned" => (1, "Nearby".to_string()),
+        "type" => {
+            let name = device_type_name(&device.device_type);
+            ((name == "Other") as u8, name.to_string())
+        }
+        _ => (0, String::new()),
+    }
+}
+
+/// the section header of this device: its adapter when showing all adapters, and its group when grouping
+fn device_header(row: &DeviceActionRow) -> String {
+    let (show_all_adapters, grouping) = {
+        let props = OVERSKRIDE_PROPS.lock().unwrap();
+        (props.show_all_adapters, props.device_grouping.clone())
+    };
+
+    let (_, group) = device_group(&ListedDevice::from_row(row), &grouping);
+    match (show_all_adapters, group.is_empty()) {
+        (true, true) => row.adapter_alias(),
+        (true, false) => format!("{} · {}", row.adapter_alias(), group),
+        (false, _) => group,
+    }
+}
+
+/// devices without a usable name, and beacons that can't be connected to, which "hide unknown devices" hides
+fn is_unknown_device(row: &DeviceActionRow) -> bool {
+    row.title() == "Unknown Device" || row.beacon()
+}
+
+/// saves and applies new discovery settings, from any of the discovery rows
+fn send_discovery_settings(sender: Sender<Message>, discovery_settings: bluetooth_settings::DiscoverySettings) {
+    glib::spawn_future_local(async move {
+        sender
+            .send(Message::SetDiscoverySettings(discovery_settings))
+            .await.expect("cannot send message");
+    });
+}
+
+/// fills the audio priority list in the settings, with buttons to move each device up or down or take it off the list
+fn populate_audio_priority_list(listbox: &gtk::ListBox, sender: Sender<Message>) {
+    while let Some(row) = listbox.row_at_index(0) {
+        listbox.remove(&row);
+    }
+
+    let audio_priority = OVERSKRIDE_PROPS.lock().unwrap().audio_priority.clone();
+    let current_adapter = OVERSKRIDE_PROPS.lock().unwrap().current_adapter.clone();
+    for (index, address) in audio_priority.iter().enumerate() {
+        let address = *address;
+        let row = adw::ActionRow::new();
+        row.set_title(&listed_name(&current_adapter, address).unwrap_or(address.to_string()));
+        row.set_subtitle(&address.to_string());
+
+        // moving by nothing takes it off the list instead
+        let buttons = [
+            ("go-up-symbolic", "Move Up", index > 0, -1),
+            ("go-down-symbolic", "Move Down", index + 1 < audio_priority.len(), 1),
+            ("user-trash-symbolic", "Remove", true, 0),
+        ];
+
+        for (icon_name, tooltip, sensitive, offset) in buttons {
+            let button = gtk::Button::from_icon_name(icon_name);
+            button.set_tooltip_text(Some(tooltip));
+            button.set_sensitive(sensitive);
+            button.set_valign(gtk::Align::Center);
+            button.add_css_class("flat");
+
+            let sender = sender.clone();
+            button.connect_clicked(move |_| {
+                let sender = sender.clone();
+                let message = match offset {
+                    0 => Message::SetAudioPriority(address, false),
+                    offset => Message::MoveAudioPriority(address, offset),
+                };
+
+                glib::spawn_future_local(async move {
+                    sender.send(message).await.expect("cannot send message");
+                });
+            });
+
+            row.add_suffix(&button);
+        }
+
+        listbox.append(&row);
+    }
+}
+
+/// shows the tray icon in its own thread, it stays up until background running is turned off
+fn spawn_tray(sender: Sender<Message>) {
+    std::thread::spawn(move || {
+        if let Err(err) = tray::run_tray(sender) {
+            event_log::warning(Source::App, "cannot show the tray icon").field("error", &err).record();
+        }
+    });
+}
+
+/// removes a device found by the adapter from the devices lookup table, so it can be added again if it's found later
+pub fn forget_device(adapter_name: &str, address: bluer::Address) {
+    if let Some(devices_lut) = DEVICES_LUT.lock().unwrap().as_mut() {
+        devices_lut.remove(&(adapter_name.to_string(), address));
+    }
+}
+
+/// updates the name of a device found by the adapter in the devices lookup table
+pub fn remember_device(adapter_name: &str, address: bluer::Address, name: String) {
+    DEVICES_LUT.lock().unwrap().get_or_insert_with(HashMap::new).insert((adapter_name.to_string(), address), name);
+}
+
+/// whether the adapter's device is listed already
+pub fn is_listed(adapter_name: &str, address: bluer::Address) -> bool {
+    DEVICES_LUT.lock().unwrap().as_ref().is_some_and(|devices_lut| devices_lut.contains_key(&(adapter_name.to_string(), address)))
+}
+
+/// the name a device is listed with, the one found by the adapter if it has one, otherwise the one found by any other adapter
+pub fn listed_name(adapter_name: &str, address: bluer::Address) -> Option<String> {
+    let devices_lut = DEVICES_LUT.lock().unwrap();
+    let devices_lut = devices_lut.as_ref()?;
+
+    devices_lut.get(&(adapter_name.to_string(), address))
+        .or_else(|| devices_lut.iter().find(|((_, listed), _)| *listed == address).map(|(_, name)| name))
+        .cloned()
+}
+
+/// whether another device found by the adapter is already listed with the name
+pub fn is_name_taken(adapter_name: &str, address: bluer::Address, name: &str) -> bool {
+    DEVICES_LUT.lock().unwrap().as_ref().is_some_and(|devices_lut| {
+        devices_lut.iter().any(|((adapter, listed), listed_name)| adapter == adapter_name && *listed != address && listed_name.trim() == name)
+    })
+}
+
 /// Creates a new [DeviceActionRow](DeviceActionRow) from a device, includes all needed info in the row
 #[tokio::main]
 async fn add_child_row(device: bluer::Device) -> bluer::Result<DeviceActionRow> {
     let child_row = DeviceActionRow::new();
-    // println!("added device name is {:?}", device.name().await?);
 
     let mut name = device.alias().await?;
     let address = device.address();
     let rssi = match device.rssi().await? {
         None => 0,
-        Some(n) => n as i32,
+        Some(n) => rssi::add_sample(device.address(), n) as i32,
     };
     let active = device.is_connected().await?;
     child_row.set_connected(active);
@@ -2058,17 +3962,31 @@ async fn add_child_row(device: bluer::Device) -> bluer::Result<DeviceActionRow>
     let trusted = device.is_trusted().await?;
     child_row.set_row_trusted(trusted);
 
+    // used by the search and the filters
+    child_row.set_paired(device.is_paired().await?);
+    let identity = identity::get_device_identity(&device).await?;
+    child_row.set_manufacturer([identity.vendor.clone().into_iter().collect(), identity.manufacturers.clone()].concat().join(", "));
+    // bluez picks an icon from the class or appearance, but only knows a few of them
+    child_row.set_device_type(device_store::icon_for(device.address(), device.icon().await?.or(identity.icon.clone()).unwrap_or_default()));
+    child_row.set_subtitle(&identity.summary());
+
     // set the address of this device
     child_row.set_bluer_address(address);
 
     // check for LE devices or other stuff that doesn't have a name, instead an address like "XX-XX-XX-XX-XX-XX"
-    // then replace with "Unknown Device" because its cleaner
-    if let Ok(bad_title) = bluer::Address::from_str(name.clone().replace('-', ":").as_str()) {
-        name = "Unknown Device".to_string();
-        child_row.set_title("Unknown Device");
+    // then name it after what it advertises, or "Unknown Device" because its cleaner
+    if advertisement::is_nameless(&name) {
+        event_log::debug(Source::Device, "device has no name, looking at what it advertises").device(device.address()).field("name", &name).record();
+
+        match advertisement::resolve_name(&device).await? {
+            Some(advertised) => {
+                name = advertised.name;
+                child_row.set_beacon(advertised.beacon);
+            }
+            None => name = "Unknown Device".to_string(),
+        }
 
-        // child_row.set_subtitle(bad_title.to_string().as_str());
-        println!("broken device title is {:?}", bad_title);
+        child_row.set_title(name.as_str());
     } else {
         child_row.set_title(name.clone().as_str());
     }
@@ -2076,28 +3994,26 @@ async fn add_child_row(device: bluer::Device) -> bluer::Result<DeviceActionRow>
     let props = OVERSKRIDE_PROPS.lock().unwrap();
     child_row.set_activatable(true);
     // sets the adapter that this device was connected to with
-    child_row.set_row_adapter_name(props.current_adapter.clone());
+    child_row.set_row_adapter_name(device.adapter_name().to_string());
 
     // sets the adapter address for ease of access
     if let Ok(adapter) = bluer::Session::new()
         .await?
-        .adapter(&props.current_adapter.clone())
+        .adapter(device.adapter_name())
     {
         let address = adapter.address().await?;
         child_row.set_bluer_adapter_address(address);
+
+        // tag the device with its adapter when every adapter is in the same list
+        let alias = adapter.alias().await?;
+        child_row.set_adapter_alias(alias);
     };
 
     // change the RSSI icon of the device
     child_row.set_row_rssi(rssi);
 
     // update the device lookup table with the new info
-    unsafe {
-        let mut devices_lut = DEVICES_LUT.clone().unwrap();
-        devices_lut.insert(address, name.clone());
-        //println!("lut (add) is: {:?}", devices_lut);
-        DEVICES_LUT = Some(devices_lut);
-        //println!("big lut (add) is: {:?}", DEVICES_LUT.clone());
-    }
+    remember_device(device.adapter_name(), address, name.clone());
 
     let sender = props.sender.clone().unwrap();
     sender
@@ -2111,13 +4027,12 @@ async fn add_child_row(device: bluer::Device) -> bluer::Result<DeviceActionRow>
     child_row.connect_activated(move |row| {
         OVERSKRIDE_PROPS.lock().unwrap().current_index = row.index();
         OVERSKRIDE_PROPS.lock().unwrap().address = row.get_bluer_address();
+        OVERSKRIDE_PROPS.lock().unwrap().device_adapter = row.adapter_name();
 
         let address = row.get_bluer_address();
         let adapter_name = row.adapter_name();
         let sender_clone = sender.clone();
 
-        // println!("row address {} with adapter {}", address.clone(), adapter_name.clone());
-
         // try to retrieve device properties and update UI
         runtime().spawn(async move {
             let sender_clone_clone = sender_clone.clone(); // lmao i love rust
@@ -2125,13 +4040,12 @@ async fn add_child_row(device: bluer::Device) -> bluer::Result<DeviceActionRow>
             if let Err(err) =
                 device::get_device_properties(address, sender_clone_clone.clone(), adapter_name).await
             {
-                let string = err.message;
 
                 sender_clone_clone
                     .send(Message::GoToBluetoothSettings(true))
                     .await.expect("cannot send message");
                 sender_clone_clone
-                    .send(Message::PopupError(string, adw::ToastPriority::High))
+                    .send(Message::PopupError(err.into(), adw::ToastPriority::High))
                     .await.expect("cannot send message");
             }
         });
@@ -2139,11 +4053,98 @@ async fn add_child_row(device: bluer::Device) -> bluer::Result<DeviceActionRow>
 
     Ok(child_row)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headphones() -> ListedDevice {
+        ListedDevice {
+            name: "WH-1000XM4".to_string(),
+            address: bluer::Address::new([0x38, 0x18, 0x4c, 0x12, 0x34, 0x56]),
+            manufacturer: "Sony Corporation".to_string(),
+            device_type: "audio-headphones".to_string(),
+            paired: true,
+            trusted: true,
+            connected: true,
+            rssi: -60,
+        }
+    }
+
+    fn stranger() -> ListedDevice {
+        ListedDevice {
+            name: "Unknown Device".to_string(),
+            address: bluer::Address::new([0x7a, 0x01, 0x02, 0x03, 0x04, 0x05]),
+            device_type: "phone".to_string(),
+            rssi: -85,
+            ..ListedDevice::default()
+        }
+    }
+
+    #[test]
+    fn filters() {
+        let out_of_range = ListedDevice { rssi: 0, ..stranger() };
+
+        assert!(device_matches(&headphones(), "all", ""));
+        assert!(device_matches(&headphones(), "paired", ""));
+        assert!(device_matches(&headphones(), "connected", ""));
+        assert!(!device_matches(&headphones(), "nearby", ""));
+
+        assert!(device_matches(&stranger(), "nearby", ""));
+        assert!(!device_matches(&out_of_range, "nearby", ""));
+        assert!(!device_matches(&stranger(), "paired", ""));
+        assert!(!device_matches(&stranger(), "trusted", ""));
+        assert!(!device_matches(&stranger(), "connected", ""));
+    }
+
+    #[test]
+    fn search_looks_at_name_address_maker_and_type() {
+        // the search is lowercased before it gets here
+        for search in ["wh-1000", "38:18:4c", "sony", "headphones", "audio"] {
+            assert!(device_matches(&headphones(), "all", search), "{}", search);
+        }
+
+        assert!(!device_matches(&headphones(), "all", "keyboard"));
+        // the filter still applies to what the search finds
+        assert!(!device_matches(&headphones(), "nearby", "sony"));
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(device_type_name("audio-headset"), "Audio");
+        assert_eq!(device_type_name("phone"), "Phones");
+        assert_eq!(device_type_name("computer"), "Computers");
+        assert_eq!(device_type_name("input-gaming"), "Input Devices");
+        assert_eq!(device_type_name("camera-video"), "Cameras");
+        assert_eq!(device_type_name("video-display"), "Cameras");
+        assert_eq!(device_type_name("scanner"), "Printers and Scanners");
+        assert_eq!(device_type_name("bluetooth-symbolic"), "Other");
+        assert_eq!(device_type_name(""), "Other");
+    }
+
+    #[test]
+    fn groups() {
+        let trusted_only = ListedDevice { trusted: true, ..stranger() };
+        let printer = ListedDevice { device_type: "printer".to_string(), ..stranger() };
+        let other = ListedDevice { device_type: String::new(), ..stranger() };
+
+        assert_eq!(device_group(&headphones(), "none"), (0, String::new()));
+
+        assert_eq!(device_group(&headphones(), "owned"), (0, "My Devices".to_string()));
+        assert_eq!(device_group(&trusted_only, "owned"), (0, "My Devices".to_string()));
+        assert_eq!(device_group(&stranger(), "owned"), (1, "Nearby".to_string()));
+
+        assert_eq!(device_group(&headphones(), "type"), (0, "Audio".to_string()));
+        assert_eq!(device_group(&printer, "type"), (0, "Printers and Scanners".to_string()));
+        // "Other" sorts after the named sections
+        assert_eq!(device_group(&other, "type"), (1, "Other".to_string()));
+        assert!(device_group(&printer, "type") < device_group(&other, "type"));
+    }
+}
+
 // TODO
 // - use fxhashmap for even faster lookups
 // - add option to auto trust device on pair (include warning about how dangerous it is)
-// - background running, with a status taskbar thingy wtv its name is
-// - add a loop for if obex and bluetooth agents fail
 // - make new battery implementation
 // - add a battery enable experimental thingy
 // - add a auto accept service if previous